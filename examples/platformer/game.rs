@@ -5,18 +5,19 @@ use sdl2::{
     event::Event,
     keyboard::Scancode,
     rect::Rect,
-    render::{
-        Canvas, TextureCreator
-    }, video::{
-        Window, WindowContext
-    }
+    render::Canvas,
+    video::Window
 };
 use ycraft::{
+    camera::Camera,
     collision::CollisionShape,
+    input::Input,
+    kinematic::move_and_slide,
     obj::{
-        ControlObjectBehavior, Frame, GameObjectBehavior, GameObjectState, Sprite
-    }, res::{
-        Font, Image, Sound
+        ControlObjectBehavior, Frame, GameObjectBehavior, GameObjectState, Others, Sprite
+    }, query::Query,
+    res::{
+        Resources, Sound
     }, room::Room
 };
 
@@ -25,6 +26,10 @@ const JUMP_SPD: f64 = 1500.0;
 const ACC: f64 = 50.0;
 const GRAVITY: f64 = 4096.0;
 const EXTRA_GRAV: f64 = 13000.0;
+const ROOM_WIDTH: u32 = 1920;
+const ROOM_HEIGHT: u32 = 1080;
+const CAM_ZOOM: f64 = 2.0;
+const CAM_LAG: f64 = 0.01;
 const BRICK_POS: [(f64, f64); 6] = [
     (96.0, 900.0),
     (160.0, 1000.0),
@@ -63,7 +68,15 @@ pub enum Rm {
     Room0
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Act {
+    Left,
+    Right,
+    Jump,
+    Reset
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Data {
     Brick(usize),
     Player
@@ -79,31 +92,29 @@ struct Brick {
 impl Brick {
     pub fn new(def_pos: (f64, f64), id: usize) -> Self {
         Self {
-            state: GameObjectState {
-                name: "brick".to_string(),
-                pos: def_pos,
-                collider: CollisionShape::Rect { center: (0, 0), size: (64, 64) },
-                cur_spr: Spr::Brick,
-                sprs: HashMap::from([(
+            state: GameObjectState::new(
+                "brick", def_pos, CollisionShape::Rect { center: (0.0, 0.0), size: (64.0, 64.0) },
+                Spr::Brick, HashMap::from([(
                     Spr::Brick,
                     Sprite::new(
                         vec![ Frame::new(Img::Brick, Rect::new(0, 0, 32, 32), (64, 64)) ],
                         0.0, (16, 16)
                     )
-                )]), custom: Data::Brick(id)
-            }, def_pos,
+                )]), Data::Brick(id)
+            ).with_solid(true),
+            def_pos,
             should_die: false
         }
     }
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Brick {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn handle_sdl_event(&mut self, event: &Event) {
@@ -130,8 +141,6 @@ struct Player {
     state: GameObjectState<Img, Spr, Data>,
     def_pos: (f64, f64),
     vel: (f64, f64),
-    right: bool,
-    left: bool,
     grounded: bool,
     extra_grav: bool,
     facing_right: bool,
@@ -142,12 +151,9 @@ struct Player {
 impl Player {
     pub fn new(def_pos: (f64, f64)) -> Self {
         Self {
-            state: GameObjectState {
-                name: "player".to_string(),
-                pos: def_pos,
-                collider: CollisionShape::Rect { center: (0, 0), size: (64, 64) },
-                cur_spr: Spr::Idle,
-                sprs: HashMap::from([
+            state: GameObjectState::new(
+                "player", def_pos, CollisionShape::Rect { center: (0.0, 0.0), size: (64.0, 64.0) },
+                Spr::Idle, HashMap::from([
                     (
                         Spr::Idle,
                         Sprite::new(
@@ -163,11 +169,9 @@ impl Player {
                             ], 12.0, (16, 16)
                         )
                     )
-                ]), custom: Data::Player
-            }, def_pos,
+                ]), Data::Player
+            ), def_pos,
             vel: (0.0, 0.0),
-            right: false,
-            left: false,
             grounded: false,
             extra_grav: false,
             facing_right: true,
@@ -178,18 +182,16 @@ impl Player {
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Player {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn on_reset(&mut self) -> bool {
         self.state.pos = self.def_pos;
-        self.right = false;
-        self.left = false;
         self.grounded = false;
         self.state.cur_spr = Spr::Idle;
         self.extra_grav = false;
@@ -199,64 +201,44 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Player {
         false
     }
 
-    fn handle_sdl_event(&mut self, event: &Event) {
-        match event {
-            Event::KeyDown { scancode, .. } if *scancode == Some(Scancode::Up) => {
-                if self.grounded {
-                    self.state.pos.1 -= 1.0;
-                    self.vel.1 = -JUMP_SPD;
-                    self.play_jump_sound = true;
-                }
-            }, Event::KeyDown { scancode, .. } if *scancode == Some(Scancode::Left) => {
-                self.left = true;
-            }, Event::KeyDown { scancode, .. } if *scancode == Some(Scancode::Right) => {
-                self.right = true;
-            }, Event::KeyUp { scancode, .. } if *scancode == Some(Scancode::Up) => {
-                if self.vel.1 < -0.1 {
-                    self.extra_grav = true;
-                }
-            }, Event::KeyUp { scancode, .. } if *scancode == Some(Scancode::Left) => {
-                self.left = false;
-            }, Event::KeyUp { scancode, .. } if *scancode == Some(Scancode::Right) => {
-                self.right = false;
-            }, Event::KeyUp { scancode, .. } if *scancode == Some(Scancode::R) => {
-                self.should_reset = true;
-            }, _ => {}
-        }
-    }
-
     fn update(
-            &mut self, delta: f64,
+            &mut self, delta: f64, input: &mut Input,
             _ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-            others: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
+            others: &Others<Img, Snd, Fnt, Spr, Rm, Data>) -> (
                 Option<Rm>, Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
             ) {
+        if input.just_released(Act::Reset) {
+            self.should_reset = true;
+        }
         if self.should_reset {
             // We can destroy bricks, so when we reset, we want to respawn the bricks that have
             // been destroyed. It should be all of them, but technically we don't know, so we have
             // to formulaically figure it out.
-            let mut existing_bricks = Vec::new();
-            for obj in others.iter() {
-                if let Data::Brick(id) = obj.state().custom {
-                    existing_bricks.push(id);
-                }
-            }
-            let to_respawn = (0..=5).into_iter()
-                .map(|id| if !existing_bricks.contains(&id) {
-                    Some(
-                        Box::new(Brick::new(BRICK_POS[id], id))
-                            as Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>
-                    )
-                } else {
-                    None
-                }).filter(|brick| brick.is_some())
-                .map(|brick| brick.unwrap())
-                .collect::<Vec<Box<_>>>();
+            let to_respawn = (0..BRICK_POS.len())
+                .filter(|id| others.filter(|state| state.custom == Data::Brick(*id)).is_empty())
+                .map(|id| {
+                    Box::new(Brick::new(BRICK_POS[id], id))
+                        as Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>
+                }).collect::<Vec<Box<_>>>();
             return (Some(Rm::Room0), to_respawn);
         }
 
-        self.state.pos.0 += self.vel.0 * delta;
-        self.state.pos.1 += self.vel.1 * delta;
+        if input.just_pressed(Act::Jump) && self.grounded {
+            self.vel.1 = -JUMP_SPD;
+            self.play_jump_sound = true;
+        }
+        if input.just_released(Act::Jump) && self.vel.1 < -0.1 {
+            self.extra_grav = true;
+        }
+
+        let res = move_and_slide(&mut self.state, (self.vel.0 * delta, self.vel.1 * delta), others);
+        self.grounded = res.on_floor;
+        if res.on_floor || (res.on_ceiling && self.vel.1 < 0.0) {
+            self.vel.1 = 0.0;
+        }
+        if res.on_wall && res.remainder.0 * self.vel.0 > 0.0 {
+            self.vel.0 = 0.0;
+        }
 
         // Animate
         if let Some(spr) = self.state.sprs.get_mut(&self.state.cur_spr) {
@@ -275,37 +257,9 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Player {
         }
 
         // Update vel at the end, so collisions can affect it!
-        let hor = (if self.right { 1.0 } else { 0.0 }) + (if self.left { -1.0 } else { 0.0 });
+        let hor = input.axis(Act::Left, Act::Right);
         self.vel.0 = ycraft::util::lerp(self.vel.0, hor * MOVE_SPD, ACC * delta);
 
-        if let CollisionShape::Rect { center, size } = self.state.collider {
-            let gnd_check = CollisionShape::Rect {
-                center: (
-                    center.0 + self.state.pos.0 as i32,
-                    center.1 + (self.state.pos.1 + self.vel.1 * delta) as i32 + 1
-                ), size
-            };
-            self.grounded = false;
-            for other in others.iter() {
-                if self.state.pos.1 > other.state().pos.1 - size.1 as f64 * 0.8 {
-                    continue;
-                }
-                if let Data::Brick(_) = other.state().custom {
-                    let mut other_col = other.state().collider.clone();
-                    if let CollisionShape::Rect { center: ref mut other_center, .. } = other_col {
-                        other_center.0 += other.state().pos.0 as i32;
-                        other_center.1 += other.state().pos.1 as i32;
-                    };
-                    if gnd_check.collides_with(&other_col) {
-                        if let CollisionShape::Rect { center, size } = other_col {
-                            self.grounded = true;
-                            self.vel.1 = 0.0;
-                            self.state.pos.1 = (center.1 - size.1 as i32 - 1) as f64;
-                        }
-                    }
-                }
-            }
-        }
         if self.grounded || self.vel.1 > 0.1 {
             self.extra_grav = false;
         }
@@ -318,46 +272,31 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Player {
         (None, vec![])
     }
 
-    fn on_collision(
-            &mut self,
-            other: &Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>) {
-        if let Data::Brick(_) = other.state().custom {
-            if (other.state().pos.0 <= self.state.pos.0 && self.vel.0 < -0.1)
-                    || (other.state().pos.0 >= self.state.pos.0 && self.vel.0 > 0.1) {
-                self.state.pos.0 -= if self.vel.0 > 0.1 { 1.0 } else { -1.0 };
-                self.vel.0 = 0.0;
-            }
-        }
-    }
-
     fn render(
-            &mut self, cnv: &mut Canvas<Window>,
-            imgs: &HashMap<Img, Image>, snds: &HashMap<Snd, Sound>,
-            _fonts: &HashMap<Fnt, Font>, _creator: &TextureCreator<WindowContext>,
-            elapsed: f64) -> Result<(), String> {
+            &mut self, cnv: &mut Canvas<Window>, cam: &Camera, res: &Resources<Img, Snd, Fnt>,
+            elapsed: f64, alpha: f64) -> Result<(), String> {
         if self.play_jump_sound {
-            snds[&Snd::Jump].play()?;
+            res.snds[&Snd::Jump].play()?;
             self.play_jump_sound = false;
         }
 
         if !Sound::is_music_playing() {
-            snds[&Snd::Music].play()?;
+            res.snds[&Snd::Music].play()?;
         }
 
         // Default render
-        let mut state = self.state().clone();
-        let GameObjectState { ref mut sprs, ref mut cur_spr, pos, .. } = state;
+        let pos = self.state.lerp_pos(alpha);
+        let GameObjectState { sprs, cur_spr, .. } = &mut self.state;
         if let Some(spr) = sprs.get_mut(cur_spr) {
             spr.update(elapsed);
-            spr.render(cnv, imgs, (pos.0 as i32, pos.1 as i32))?;
+            spr.render(cnv, cam, res.imgs, (pos.0 as i32, pos.1 as i32))?;
         }
-        self.set_state(&state);
         Ok(())
     }
 }
 
 pub fn room0() -> Room<Img, Snd, Fnt, Spr, Rm, Data> {
-    Room::new(
+    let room = Room::new(
         vec![
             Box::new(Player::new((256.0, 900.0))),
             Box::new(Brick::new(BRICK_POS[0], 0)),
@@ -367,6 +306,13 @@ pub fn room0() -> Room<Img, Snd, Fnt, Spr, Rm, Data> {
             Box::new(Brick::new(BRICK_POS[4], 4)),
             Box::new(Brick::new(BRICK_POS[5], 5))
         ], false
+    );
+    let player = room.objs[0].state().id;
+    room.with_camera(
+        Camera::new()
+            .with_zoom(CAM_ZOOM)
+            .with_follow(player, CAM_LAG)
+            .with_bounds(Rect::new(0, 0, ROOM_WIDTH, ROOM_HEIGHT))
     )
 }
 
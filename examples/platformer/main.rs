@@ -1,18 +1,27 @@
 mod game;
 
 use std::collections::HashMap;
-use sdl2::pixels::Color;
+use sdl2::{
+    controller::{
+        Axis, Button
+    }, keyboard::Scancode,
+    pixels::Color
+};
 use ycraft::{
-    app::run,
-    obj::ControlObjectBehavior
+    app::{
+        App, ScaleMode, Timestep
+    }, input::{
+        Binding, Input
+    }, obj::ControlObjectBehavior
 };
 use game::{
-    Fnt, Img, Rm, Snd
+    Act, Fnt, Img, Rm, Snd
 };
 
 const DEF_WIN_WIDTH: u32 = 1920;
 const DEF_WIN_HEIGHT: u32 = 1080;
 const FPS: f64 = 60.0;
+const TICK_RATE: f64 = 120.0;
 const BG_COLOR: Color = Color::RGB(0x60, 0x60, 0x80);
 
 fn main() -> Result<(), String> {
@@ -39,9 +48,26 @@ fn main() -> Result<(), String> {
     let ctl_objs: Vec<Box<dyn ControlObjectBehavior<_, _, _, _, _, _>>> = vec![
     ];
 
-    run(
-        "Y-Craft", DEF_WIN_WIDTH, DEF_WIN_HEIGHT, FPS, &BG_COLOR,
-        Rm::Room0, &rooms, &ctl_objs, &snds, &imgs, &fonts
-    )
+    let input = Input::new()
+        .with_binding(Act::Left, Binding::Key(Scancode::Left))
+        .with_binding(Act::Left, Binding::Button(Button::DPadLeft))
+        .with_binding(Act::Left, Binding::Axis { axis: Axis::LeftX, positive: false })
+        .with_binding(Act::Right, Binding::Key(Scancode::Right))
+        .with_binding(Act::Right, Binding::Button(Button::DPadRight))
+        .with_binding(Act::Right, Binding::Axis { axis: Axis::LeftX, positive: true })
+        .with_binding(Act::Jump, Binding::Key(Scancode::Up))
+        .with_binding(Act::Jump, Binding::Button(Button::A))
+        .with_binding(Act::Reset, Binding::Key(Scancode::R))
+        .with_binding(Act::Reset, Binding::Button(Button::Back));
+
+    App::builder("Y-Craft", DEF_WIN_WIDTH, DEF_WIN_HEIGHT)
+        .fps(FPS)
+        .bg_color(BG_COLOR)
+        .timestep(Timestep::Fixed(TICK_RATE))
+        .min_size(DEF_WIN_WIDTH / 4, DEF_WIN_HEIGHT / 4)
+        .scale_mode(ScaleMode::Letterbox)
+        .input(input)
+        .build()
+        .run(Rm::Room0, &rooms, &ctl_objs, &snds, &imgs, &fonts)
 }
 
@@ -8,8 +8,9 @@ use sdl2::{
 };
 use ycraft::{
     collision::CollisionShape,
+    input::Input,
     obj::{
-        ControlObjectBehavior, Frame, GameObjectBehavior, GameObjectState, Sprite
+        ControlObjectBehavior, Frame, GameObjectBehavior, GameObjectState, Others, Sprite
     }, room::Room
 };
 use crate::game::{
@@ -25,31 +26,29 @@ struct DeadScreen {
 impl DeadScreen {
     pub fn new() -> Self {
         Self {
-            state: GameObjectState {
-                name: "dead".to_string(),
-                pos: (0.0, 0.0),
-                collider: CollisionShape::Rect { center: (320, 180), size: (640, 480) },
-                cur_spr: Spr::Dead,
-                sprs: HashMap::from([(
+            state: GameObjectState::new(
+                "dead", (0.0, 0.0),
+                CollisionShape::Rect { center: (320.0, 180.0), size: (640.0, 480.0) },
+                Spr::Dead, HashMap::from([(
                     Spr::Dead,
                     Sprite::new(
                         vec![Frame::new(
                             Img::Dead, Rect::new(0, 0, 640, 360), (640, 360)
                         )], 0.0, (0, 0)
                     )
-                )]), custom: Data::Dead
-            }, change_room: false
+                )]), Data::Dead
+            ), change_room: false
         }
     }
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for DeadScreen {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn on_reset(&mut self) -> bool {
@@ -66,9 +65,9 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for DeadScreen {
     }
 
     fn update(
-            &mut self, _delta: f64,
+            &mut self, _delta: f64, _input: &mut Input,
             _ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-            _others: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
+            _others: &Others<Img, Snd, Fnt, Spr, Rm, Data>) -> (
                 Option<Rm>,
                 Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
             ) {
@@ -89,31 +88,29 @@ struct WinScreen {
 impl WinScreen {
     pub fn new() -> Self {
         Self {
-            state: GameObjectState {
-                name: "win".to_string(),
-                pos: (0.0, 0.0),
-                collider: CollisionShape::Rect { center: (320, 180), size: (640, 480) },
-                cur_spr: Spr::Win,
-                sprs: HashMap::from([(
+            state: GameObjectState::new(
+                "win", (0.0, 0.0),
+                CollisionShape::Rect { center: (320.0, 180.0), size: (640.0, 480.0) },
+                Spr::Win, HashMap::from([(
                     Spr::Win,
                     Sprite::new(
                         vec![Frame::new(
                             Img::Win, Rect::new(0, 0, 640, 360), (640, 360)
                         )], 0.0, (0, 0)
                     )
-                )]), custom: Data::Win
-            }, change_room: false
+                )]), Data::Win
+            ), change_room: false
         }
     }
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for WinScreen {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn on_reset(&mut self) -> bool {
@@ -130,9 +127,9 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for WinScreen {
     }
 
     fn update(
-            &mut self, _delta: f64,
+            &mut self, _delta: f64, _input: &mut Input,
             _ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-            _others: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
+            _others: &Others<Img, Snd, Fnt, Spr, Rm, Data>) -> (
                 Option<Rm>,
                 Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
             ) {
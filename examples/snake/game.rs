@@ -1,19 +1,16 @@
 //! Global game stuff used by all objs/rooms
 
-use std::collections::HashMap;
 use sdl2::{
     pixels::Color,
-    render::{
-        Canvas, TextureCreator
-    }, video::{
-        Window, WindowContext
-    }
+    render::Canvas,
+    video::Window
 };
 use ycraft::{
+    input::Input,
     obj::{
         ControlObjectBehavior, GameObjectBehavior
     }, res::{
-        Font, Image, Sound
+        Resources, Sound
     }
 };
 use crate::play::Dir;
@@ -63,6 +60,14 @@ pub enum Rm {
     Win
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Act {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
 /// Used for sharing data between objects via GameObjectBehavior trait
 #[derive(Clone, Copy)]
 pub enum Data {
@@ -95,11 +100,11 @@ impl Score {
 
 impl ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Score {
     fn data(&self) -> Data {
-        self.data.clone()
+        self.data
     }
 
     fn update(
-            &mut self, _delta: f64, cur_room: &Rm,
+            &mut self, _delta: f64, _input: &mut Input, cur_room: &Rm,
             _others: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
             room_objs: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
                 Option<Rm>, Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
@@ -124,16 +129,14 @@ impl ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Score {
 
     fn render(
             &mut self, cnv: &mut Canvas<Window>, cur_room: &Rm,
-            _imgs: &HashMap<Img, Image>, snds: &HashMap<Snd, Sound>,
-            fonts: &HashMap<Fnt, Font>, creator: &TextureCreator<WindowContext>,
-            _elapsed: f64) -> Result<(), String> {
+            res: &Resources<Img, Snd, Fnt>, _elapsed: f64) -> Result<(), String> {
         if !Sound::is_music_playing() {
-            snds[&Snd::Music].play()?;
+            res.snds[&Snd::Music].play()?;
         }
         match cur_room {
             Rm::Play | Rm::Dead => if let Data::Score(score) = self.data {
-                fonts[&Fnt::Geist].render(
-                    cnv, creator, format!("Score: {}", score).as_str(), &Color::WHITE,
+                res.fonts[&Fnt::Geist].render(
+                    cnv, res.creator, format!("Score: {}", score).as_str(), &Color::WHITE,
                     (16, 16), 0.0, (false, false)
                 )?;
             }, _ => {}
@@ -4,13 +4,18 @@ mod play;
 mod end;
 
 use std::collections::HashMap;
-use sdl2::pixels::Color;
+use sdl2::{
+    keyboard::Scancode,
+    pixels::Color
+};
 use ycraft::{
-    app::run,
-    obj::ControlObjectBehavior
+    app::App,
+    input::{
+        Binding, Input
+    }, obj::ControlObjectBehavior
 };
 use game::{
-    Fnt, Img, Rm, Score, Snd
+    Act, Fnt, Img, Rm, Score, Snd
 };
 
 const DEF_WIN_WIDTH: u32 = 640;
@@ -50,9 +55,21 @@ fn main() -> Result<(), String> {
         Box::new(Score::new())
     ];
 
-    run(
-        "Y-Craft", DEF_WIN_WIDTH, DEF_WIN_HEIGHT, FPS, &BG_COLOR,
-        Rm::Title, &rooms, &ctl_objs, &snds, &imgs, &fonts
-    )
+    let input = Input::new()
+        .with_binding(Act::Up, Binding::Key(Scancode::Up))
+        .with_binding(Act::Up, Binding::Key(Scancode::W))
+        .with_binding(Act::Down, Binding::Key(Scancode::Down))
+        .with_binding(Act::Down, Binding::Key(Scancode::S))
+        .with_binding(Act::Left, Binding::Key(Scancode::Left))
+        .with_binding(Act::Left, Binding::Key(Scancode::A))
+        .with_binding(Act::Right, Binding::Key(Scancode::Right))
+        .with_binding(Act::Right, Binding::Key(Scancode::D));
+
+    App::builder("Y-Craft", DEF_WIN_WIDTH, DEF_WIN_HEIGHT)
+        .fps(FPS)
+        .bg_color(BG_COLOR)
+        .input(input)
+        .build()
+        .run(Rm::Title, &rooms, &ctl_objs, &snds, &imgs, &fonts)
 }
 
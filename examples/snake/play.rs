@@ -3,20 +3,28 @@
 use std::collections::HashMap;
 use rand::Rng;
 use sdl2::{
-    event::Event,
-    keyboard::Scancode,
-    rect::Rect, render::{Canvas, TextureCreator}, video::{Window, WindowContext}
+    rect::Rect, render::Canvas, video::Window
 };
 use ycraft::{
+    camera::Camera,
     collision::CollisionShape,
+    input::Input,
     obj::{
-        ControlObjectBehavior, Frame, GameObjectBehavior, GameObjectState, Sprite
-    }, res::{Font, Image, Sound}, room::Room
+        ControlObjectBehavior, Frame, GameObjectBehavior, GameObjectState, Others, Sprite
+    }, query::Query,
+    res::Resources, room::Room
 };
 use crate::game::{
-    Img, Snd, Fnt, Spr, Rm, Data, BASE_MOVE_SPD, MOVE_SPD_INC
+    Act, Img, Snd, Fnt, Spr, Rm, Data, BASE_MOVE_SPD, MOVE_SPD_INC
 };
 
+/// Keep the board behind the snake no matter how many body pieces get added after it
+const BOARD_DEPTH: i32 = 100;
+
+/// Collision layers. Only the head and the mouse care about what they touch
+const LAYER_SNAKE: u32 = 1 << 0;
+const LAYER_MOUSE: u32 = 1 << 1;
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum Dir {
     Up,
@@ -40,22 +48,20 @@ impl SnakeHead {
     pub fn new() -> Self {
         let pos = (640.0 / 2.0 + 32.0 + 32.0 / 2.0, 352.0 / 2.0);
         Self {
-            state: GameObjectState {
-                name: "head".to_string(),
-                pos,
-                collider: CollisionShape::Rect { center: (0, 0), size: (31, 31) },
-                cur_spr: Spr::Head,
-                sprs: HashMap::from([(
+            state: GameObjectState::new(
+                "head", pos, CollisionShape::Rect { center: (0.0, 0.0), size: (31.0, 31.0) },
+                Spr::Head, HashMap::from([(
                     Spr::Head,
                     Sprite::new(
                         vec![ Frame::new(Img::Snake, Rect::new(0, 0, 32, 32), (32, 32)) ],
                         0.0, (16, 16)
                     )
-                )]), custom: Data::Head {
+                )]), Data::Head {
                     dir: Dir::Right,
                     lurch_propagation: 0
                 }
-            }, move_spd: BASE_MOVE_SPD,
+            ).with_layers(LAYER_SNAKE, LAYER_SNAKE | LAYER_MOUSE),
+            move_spd: BASE_MOVE_SPD,
             inter_pos: pos,
             can_change_dir: true,
             add_body_seg: false,
@@ -66,12 +72,12 @@ impl SnakeHead {
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for SnakeHead {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn on_reset(&mut self) -> bool {
@@ -85,51 +91,45 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for SnakeHead {
         false
     }
 
-    fn handle_sdl_event(&mut self, event: &Event) {
-        match event {
-            Event::KeyDown { scancode, .. } => if scancode.is_some() {
-                if let Data::Head { ref mut dir, .. } = self.state.custom {
-                    if self.can_change_dir {
-                        // Keep how much you've moved in a dir when switching.
-                        // This keeps each increment the same time length.
-                        // It's like momentum
-                        let pos_dif = match *dir {
-                            Dir::Up | Dir::Down => (self.inter_pos.1 - self.state.pos.1).abs(),
-                            Dir::Left | Dir::Right => (self.inter_pos.0 - self.state.pos.0).abs()
-                        };
-                        self.inter_pos = self.state.pos;
-                        match scancode.unwrap() {
-                            Scancode::Up => {
-                                self.can_change_dir = false;
-                                *dir = Dir::Up;
-                                self.inter_pos.1 -= pos_dif;
-                            }, Scancode::Down => {
-                                self.can_change_dir = false;
-                                *dir = Dir::Down;
-                                self.inter_pos.1 += pos_dif;
-                            }, Scancode::Left => {
-                                self.can_change_dir = false;
-                                *dir = Dir::Left;
-                                self.inter_pos.0 -= pos_dif;
-                            }, Scancode::Right => {
-                                self.can_change_dir = false;
-                                *dir = Dir::Right;
-                                self.inter_pos.0 += pos_dif;
-                            }, _ => {}
-                        }
-                    }
-                }
-            }, _ => {}
-        }
-    }
-
     fn update(
-            &mut self, delta: f64,
+            &mut self, delta: f64, input: &mut Input,
             ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-            others: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
+            others: &Others<Img, Snd, Fnt, Spr, Rm, Data>) -> (
                 Option<Rm>,
                 Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
             ) {
+        let new_dir = if input.just_pressed(Act::Up) {
+            Some(Dir::Up)
+        } else if input.just_pressed(Act::Down) {
+            Some(Dir::Down)
+        } else if input.just_pressed(Act::Left) {
+            Some(Dir::Left)
+        } else if input.just_pressed(Act::Right) {
+            Some(Dir::Right)
+        } else {
+            None
+        };
+        if let (Some(new_dir), Data::Head { ref mut dir, .. }) = (new_dir, &mut self.state.custom) {
+            if self.can_change_dir {
+                // Keep how much you've moved in a dir when switching.
+                // This keeps each increment the same time length.
+                // It's like momentum
+                let pos_dif = match *dir {
+                    Dir::Up | Dir::Down => (self.inter_pos.1 - self.state.pos.1).abs(),
+                    Dir::Left | Dir::Right => (self.inter_pos.0 - self.state.pos.0).abs()
+                };
+                self.inter_pos = self.state.pos;
+                self.can_change_dir = false;
+                *dir = new_dir;
+                match new_dir {
+                    Dir::Up => self.inter_pos.1 -= pos_dif,
+                    Dir::Down => self.inter_pos.1 += pos_dif,
+                    Dir::Left => self.inter_pos.0 -= pos_dif,
+                    Dir::Right => self.inter_pos.0 += pos_dif
+                }
+            }
+        }
+
         let mut score = 0;
         for obj in ctl_objs.iter() {
             if let Data::Score(sc) = obj.data() {
@@ -235,23 +235,20 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for SnakeHead {
     }
 
     fn render(
-                &mut self, cnv: &mut Canvas<Window>,
-                imgs: &HashMap<Img, Image>, snds: &HashMap<Snd, Sound>,
-                _fonts: &HashMap<Fnt, Font>, _creator: &TextureCreator<WindowContext>,
-                elapsed: f64) -> Result<(), String> {
+                &mut self, cnv: &mut Canvas<Window>, cam: &Camera, res: &Resources<Img, Snd, Fnt>,
+                elapsed: f64, alpha: f64) -> Result<(), String> {
         if self.play_eat_snd {
-            snds[&Snd::Bite].play()?;
+            res.snds[&Snd::Bite].play()?;
             self.play_eat_snd = false;
         }
 
         // Default render
-        let mut state = self.state().clone();
-        let GameObjectState { ref mut sprs, ref mut cur_spr, pos, .. } = state;
+        let pos = self.state.lerp_pos(alpha);
+        let GameObjectState { sprs, cur_spr, .. } = &mut self.state;
         if let Some(spr) = sprs.get_mut(cur_spr) {
             spr.update(elapsed);
-            spr.render(cnv, imgs, (pos.0 as i32, pos.1 as i32))?;
+            spr.render(cnv, cam, res.imgs, (pos.0 as i32, pos.1 as i32))?;
         }
-        self.set_state(&state);
         Ok(())
     }
 }
@@ -267,22 +264,21 @@ struct SnakeBody {
 impl SnakeBody {
     pub fn new(index: isize, def_pos: (f64, f64)) -> Self {
         Self {
-            state: GameObjectState {
-                name: format!("snake_body_{}", index),
-                pos: def_pos,
-                collider: CollisionShape::Rect { center: (0, 0), size: (31, 31) },
-                cur_spr: Spr::Body,
-                sprs: HashMap::from([(
+            state: GameObjectState::new(
+                &format!("snake_body_{}", index), def_pos,
+                CollisionShape::Rect { center: (0.0, 0.0), size: (31.0, 31.0) },
+                Spr::Body, HashMap::from([(
                     Spr::Body,
                     Sprite::new(
                         vec![ Frame::new(Img::Snake, Rect::new(32, 0, 32, 32), (32, 32)) ],
                         0.0, (16, 16)
                     )
-                )]), custom: Data::Body {
+                )]), Data::Body {
                     index,
                     dir: Dir::Right
                 }
-            }, last_dir: Dir::Right,
+            ).with_layers(LAYER_SNAKE, 0),
+            last_dir: Dir::Right,
             last_pos: def_pos,
             def_pos
         }
@@ -290,12 +286,12 @@ impl SnakeBody {
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for SnakeBody {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn on_reset(&mut self) -> bool {
@@ -315,9 +311,9 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for SnakeBody {
     }
 
     fn update(
-            &mut self, _delta: f64,
+            &mut self, _delta: f64, _input: &mut Input,
             _ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-            others: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
+            others: &Others<Img, Snd, Fnt, Spr, Rm, Data>) -> (
                 Option<Rm>,
                 Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
             ) {
@@ -327,8 +323,8 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for SnakeBody {
             } else {
                 format!("snake_body_{}", *index - 1)
             };
-            for other in others.iter() {
-                if *other.state().name == parent_id && other.state().pos != self.last_pos {
+            for other in others.named(&parent_id) {
+                if other.state().pos != self.last_pos {
                     self.state.pos = self.last_pos;
                     *dir = self.last_dir;
                     self.last_pos = other.state().pos;
@@ -367,19 +363,18 @@ struct SnakeTail {
 impl SnakeTail {
     pub fn new() -> Self {
         Self {
-            state: GameObjectState {
-                name: "snake_tail".to_string(),
-                pos: (640.0 / 2.0 - 32.0 - 32.0 / 2.0, 352.0 / 2.0),
-                cur_spr: Spr::Tail,
-                sprs: HashMap::from([(
+            state: GameObjectState::new(
+                "snake_tail", (640.0 / 2.0 - 32.0 - 32.0 / 2.0, 352.0 / 2.0),
+                CollisionShape::Rect { center: (0.0, 0.0), size: (31.0, 31.0) },
+                Spr::Tail, HashMap::from([(
                     Spr::Tail,
                     Sprite::new(
                         vec![ Frame::new(Img::Snake, Rect::new(0, 32, 32, 32), (32, 32)) ],
                         0.0, (16, 16)
                     )
-                )]), collider: CollisionShape::Rect { center: (0, 0), size: (31, 31) },
-                custom: Data::Tail
-            }, dir: Dir::Right,
+                )]), Data::Tail
+            ).with_layers(LAYER_SNAKE, 0),
+            dir: Dir::Right,
             last_dir: Dir::Right,
             last_pos: (640.0 / 2.0 - 32.0 - 32.0 / 2.0, 352.0 / 2.0),
         }
@@ -387,12 +382,12 @@ impl SnakeTail {
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for SnakeTail {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn on_reset(&mut self) -> bool {
@@ -405,9 +400,9 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for SnakeTail {
     }
 
     fn update(
-            &mut self, _delta: f64,
+            &mut self, _delta: f64, _input: &mut Input,
             _ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-            others: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
+            others: &Others<Img, Snd, Fnt, Spr, Rm, Data>) -> (
                 Option<Rm>, Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
             ) {
         let mut max_body = -1;
@@ -448,38 +443,36 @@ struct Mouse {
 impl Mouse {
     pub fn new() -> Self {
         Self {
-            state: GameObjectState {
-                name: "mouse".to_string(),
-                pos: Self::random_mouse_pos(),
-                cur_spr: Spr::Mouse,
-                sprs: HashMap::from([(
+            state: GameObjectState::new(
+                "mouse", Self::random_mouse_pos(),
+                CollisionShape::Circle { center: (0.0, 0.0), radius: 15.0 },
+                Spr::Mouse, HashMap::from([(
                     Spr::Mouse,
                     Sprite::new(
                         vec![ Frame::new(Img::Mouse, Rect::new(0, 0, 32, 32), (32, 32)) ],
                         0.0, (16, 16)
                     )
-                )]), collider: CollisionShape::Circle { center: (0, 0), radius: 15 },
-                custom: Data::Mouse
-            }
+                )]), Data::Mouse
+            ).with_layers(LAYER_MOUSE, LAYER_SNAKE)
         }
     }
 
     fn random_mouse_pos() -> (f64, f64) {
         let mut rng = rand::thread_rng();
         (
-            (rng.gen_range(32.0..640.0 - 96.0) / 32.0 as f64).floor() * 32.0 + 16.0,
-            (rng.gen_range(32.0..360.0 - 96.0) / 32.0 as f64).floor() * 32.0 + 16.0
+            (rng.gen_range(32.0..640.0_f64 - 96.0) / 32.0).floor() * 32.0 + 16.0,
+            (rng.gen_range(32.0..360.0_f64 - 96.0) / 32.0).floor() * 32.0 + 16.0
         )
     }
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Mouse {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn on_reset(&mut self) -> bool {
@@ -488,7 +481,7 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Mouse {
         false
     }
 
-    fn on_collision(
+    fn on_collision_enter(
             &mut self,
             other: &Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>) {
         if let Data::Head { .. } = other.state().custom {
@@ -505,31 +498,29 @@ struct Board {
 impl Board {
     pub fn new() -> Self {
         Self {
-            state: GameObjectState {
-                name: "board".to_string(),
-                pos: (0.0, 0.0),
-                collider: CollisionShape::Rect { center: (320, 180), size: (640, 480) },
-                cur_spr: Spr::Board,
-                sprs: HashMap::from([(
+            state: GameObjectState::new(
+                "board", (0.0, 0.0),
+                CollisionShape::Rect { center: (320.0, 180.0), size: (640.0, 480.0) },
+                Spr::Board, HashMap::from([(
                     Spr::Board,
                     Sprite::new(
                         vec![Frame::new(
                             Img::Board, Rect::new(0, 0, 640, 360), (640, 360)
                         )], 0.0, (0, 0)
                     )
-                )]), custom: Data::Board
-            }
+                )]), Data::Board
+            ).with_layers(0, 0).with_depth(BOARD_DEPTH)
         }
     }
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Board {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn on_reset(&mut self) -> bool {
@@ -8,8 +8,9 @@ use sdl2::{
 };
 use ycraft::{
     collision::CollisionShape,
+    input::Input,
     obj::{
-        ControlObjectBehavior, Frame, GameObjectBehavior, GameObjectState, Sprite
+        ControlObjectBehavior, Frame, GameObjectBehavior, GameObjectState, Others, Sprite
     }, room::Room
 };
 use crate::game::{
@@ -25,31 +26,29 @@ struct TitleScreenImage {
 impl TitleScreenImage {
     pub fn new() -> Self {
         Self {
-            state: GameObjectState {
-                name: "title".to_string(),
-                pos: (0.0, 0.0),
-                collider: CollisionShape::Rect { center: (320, 180), size: (640, 480) },
-                cur_spr: Spr::Title,
-                sprs: HashMap::from([(
+            state: GameObjectState::new(
+                "title", (0.0, 0.0),
+                CollisionShape::Rect { center: (320.0, 180.0), size: (640.0, 480.0) },
+                Spr::Title, HashMap::from([(
                     Spr::Title,
                     Sprite::new(
                         vec![Frame::new(
                             Img::Title, Rect::new(0, 0, 640, 360), (640, 360)
                         )], 0.0, (0, 0)
                     )
-                )]), custom: Data::Title
-            }, change_room: false
+                )]), Data::Title
+            ), change_room: false
         }
     }
 }
 
 impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for TitleScreenImage {
-    fn state(&self) -> GameObjectState<Img, Spr, Data> {
-        self.state.clone()
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
     }
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
-        self.state = new_state.clone();
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
     }
 
     fn on_reset(&mut self) -> bool {
@@ -66,9 +65,9 @@ impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for TitleScreenImage {
     }
 
     fn update(
-            &mut self, _delta: f64,
+            &mut self, _delta: f64, _input: &mut Input,
             _ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-            _others: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
+            _others: &Others<Img, Snd, Fnt, Spr, Rm, Data>) -> (
                 Option<Rm>,
                 Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
             ) {
@@ -7,119 +7,330 @@ use std::{
     }
 };
 use sdl2::{
-    event::{Event, WindowEvent},
+    event::Event,
     keyboard::{
         Mod, Scancode
     }, mixer::{
-        InitFlag, AUDIO_S16LSB, DEFAULT_CHANNELS
+        AudioFormat, InitFlag, AUDIO_S16LSB, DEFAULT_CHANNELS
     }, pixels::Color,
-    video::FullscreenType
+    rect::Rect,
+    render::{
+        BlendMode, Canvas
+    }, video::{
+        FullscreenType, Window
+    }
 };
 use crate::{
-    obj::ControlObjectBehavior,
+    controller::Controllers,
+    input::Input,
+    obj::ControlObject,
     res::{
         Font,
         Image,
+        Resources,
         Sound
     }, room::Room, IndexRestriction
 };
 
-/// Create a window and run the game
-///
-/// - Title, width, height, fps, and bg_color all refer to window params
-/// - start_room and rooms are the "scenes" of your game
-/// - ctl_objs are objects that are updated and exist outside of the room
-/// - snd_srcs, img_srcs, and font_srcs are file paths to resources
-pub fn run<'a, 'b, Img, Snd, Fnt, Spr, Rm, Data>(
-    title: &str, width: u32, height: u32, fps: f64, bg_color: &Color,
-    start_room: Rm, rooms: &HashMap<Rm, Room<Img, Snd, Fnt, Spr, Rm, Data>>,
-    ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-    snd_srcs: &[(Snd, &'static [u8], bool)], img_srcs: &[(Img, &[u8])],
-    font_srcs: &[(Fnt, u16, &[u8])]) -> Result<(), String> where
-        Spr: IndexRestriction,
-        Img: IndexRestriction,
-        Snd: IndexRestriction,
-        Fnt: IndexRestriction,
-        Rm: IndexRestriction,
-        Data: Clone {
-    let ctx = sdl2::init()?;
-    let _ = ctx.audio()?;
-    let subsys = ctx.video()?;
-    let win = subsys
-        .window(title, width, height)
-        .resizable()
-        .position_centered()
-        .build()
-        .map_err(|e| e.to_string())?;
-    let mut event_pump = ctx.event_pump()?;
-    let mut cnv = win.into_canvas()
-        .accelerated()
-        .present_vsync()
-        .build()
-        .map_err(|e| e.to_string())?;
-    let creator = cnv.texture_creator();
-    
-    let ttf_ctx = sdl2::ttf::init().map_err(|e| e.to_string())?;
-
-    let freq = 44100;
-    let format = AUDIO_S16LSB;
-    let channels = DEFAULT_CHANNELS;
-    let chunk_size = 1024;
-    sdl2::mixer::open_audio(freq, format, channels, chunk_size)?;
-    let _ = sdl2::mixer::init(InitFlag::MP3 | InitFlag::FLAC | InitFlag::MOD | InitFlag::OGG)?;
-    sdl2::mixer::allocate_channels(16);
-    
-    // Load resources from file paths
-    let mut snds = HashMap::new();
-    for (key, src, is_music) in snd_srcs.iter() {
-        if *is_music {
-            snds.insert(*key, Sound::load_music(src)?);
-        } else {
-            snds.insert(*key, Sound::load_chunk(src)?);
-        }
-    }
-    let mut imgs = HashMap::new();
-    for (key, src) in img_srcs.iter() {
-        let mut img = image::load_from_memory(src)
-            .map_err(|e| e.to_string())?
-            .to_rgba8();
-        imgs.insert(*key, Image::new(&mut img, &creator)?);
-    }
-    let mut fonts = HashMap::new();
-    for (key, size, src) in font_srcs.iter() {
-        fonts.insert(*key, Font::new(src, *size, &ttf_ctx)?);
-    }
-
-    let mut rooms = rooms.clone();
-    let mut ctl_objs = ctl_objs.clone();
-
-    // Create a timed 60fps game loop
-    let mut room_reset = false;
-    let mut start = Instant::now();
-    let mut elapsed = 0.0;
-    let mut room = start_room;
-    let mut is_fullscreen = false;
-    'game: loop {
-        // Maintain fps
-        std::thread::sleep(Duration::from_millis(1)); // Force a sleep bc CPU is really fast lol
-        let delta = start.elapsed().as_secs_f64();
-        start = Instant::now();
-        elapsed += delta;
-
-        if let Some(rm) = rooms.get_mut(&room) {
-            // Only reset at start of loop, triggered by something in the loop
-            if room_reset {
-                if !rm.persistant {
-                    rm.reset();
+/// Longest frame the fixed timestep will try to catch up on. Anything longer (e.g. dragging the
+/// window) is dropped instead of running a huge burst of updates
+const MAX_FRAME_TIME: f64 = 0.25;
+
+/// How the game loop advances time between updates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Timestep {
+    /// Update once per loop with however much wall-clock time has passed since the last loop
+    Variable,
+
+    /// Update a constant number of times per second (the tick rate) with a constant delta. Render
+    /// receives how far the game is between ticks so objects can interpolate their positions
+    Fixed(f64)
+}
+
+/// How the game's logical width and height are fit into the window when the two don't match
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Fill the whole window, even if that distorts the image
+    Stretch,
+
+    /// Scale as large as possible while keeping the aspect ratio. Leftover space becomes bars
+    Letterbox,
+
+    /// Like Letterbox, but only scale by whole numbers so every pixel stays the same size
+    PixelPerfect
+}
+
+/// Keeps track of game time between loops and decides how many updates each loop runs. Kept apart
+/// from the window so the loop's timing can be tested on its own
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clock {
+    pub timestep: Timestep,
+    pub accumulator: f64
+}
+
+impl Clock {
+    pub fn new(timestep: Timestep) -> Self {
+        Self {
+            timestep,
+            accumulator: 0.0
+        }
+    }
+
+    /// Add a loop's worth of time and run update for every tick it covers, passing the delta.
+    /// Update returns true to stop early (e.g. when the room changes), and the rest of the ticks
+    /// are dropped. Returns how many updates ran
+    pub fn tick<F>(&mut self, frame_time: f64, mut update: F) -> usize where
+            F: FnMut(f64) -> bool {
+        let (delta, ticks) = match self.timestep {
+            Timestep::Variable => (frame_time, 1),
+            Timestep::Fixed(tick_rate) => {
+                let delta = 1.0 / tick_rate;
+                self.accumulator += frame_time.min(MAX_FRAME_TIME);
+                let mut ticks = 0;
+                while self.accumulator >= delta {
+                    self.accumulator -= delta;
+                    ticks += 1;
                 }
-                room_reset = false;
+                (delta, ticks)
+            }
+        };
+        for tick in 0..ticks {
+            if update(delta) {
+                return tick + 1;
             }
+        }
+        ticks
+    }
+
+    /// How far the game is between the last tick and the next one, from 0 up to (not including) 1.
+    /// Always 1 with a variable timestep, since every update lands right on the frame
+    pub fn alpha(&self) -> f64 {
+        match self.timestep {
+            Timestep::Variable => 1.0,
+            Timestep::Fixed(tick_rate) => (self.accumulator * tick_rate).min(1.0 - f64::EPSILON)
+        }
+    }
+}
+
+impl ScaleMode {
+    /// Where the game's logical area ends up in a window of the given size
+    pub fn dest_rect(&self, logical: (u32, u32), window: (u32, u32)) -> Rect {
+        let scale = (
+            window.0 as f64 / logical.0 as f64,
+            window.1 as f64 / logical.1 as f64
+        );
+        let scale = match self {
+            ScaleMode::Stretch => return Rect::new(0, 0, window.0, window.1),
+            ScaleMode::Letterbox => scale.0.min(scale.1),
+            ScaleMode::PixelPerfect => scale.0.min(scale.1).floor().max(1.0)
+        };
+        let size = (
+            (logical.0 as f64 * scale).round() as u32,
+            (logical.1 as f64 * scale).round() as u32
+        );
+        Rect::new(
+            (window.0 as i32 - size.0 as i32) / 2,
+            (window.1 as i32 - size.1 as i32) / 2,
+            size.0, size.1
+        )
+    }
+
+    /// Convert a point in window coordinates (like the mouse position) to logical coordinates
+    pub fn window_to_logical(
+            &self, logical: (u32, u32), window: (u32, u32), pos: (i32, i32)) -> (i32, i32) {
+        let dest = self.dest_rect(logical, window);
+        (
+            ((pos.0 - dest.x) as f64 * logical.0 as f64 / dest.w as f64).floor() as i32,
+            ((pos.1 - dest.y) as f64 * logical.1 as f64 / dest.h as f64).floor() as i32
+        )
+    }
+
+    /// Convert a distance in window pixels (like mouse movement) to logical pixels
+    fn window_to_logical_rel(
+            &self, logical: (u32, u32), window: (u32, u32), rel: (i32, i32)) -> (i32, i32) {
+        let dest = self.dest_rect(logical, window);
+        (
+            (rel.0 as f64 * logical.0 as f64 / dest.w as f64).round() as i32,
+            (rel.1 as f64 * logical.1 as f64 / dest.h as f64).round() as i32
+        )
+    }
+}
+
+/// Window, renderer, audio, and loop settings for a game. Make one with App::builder, then call
+/// run with your rooms and resources
+#[derive(Clone)]
+pub struct App {
+    title: String,
+    width: u32,
+    height: u32,
+    fps: f64,
+    bg_color: Color,
+    timestep: Timestep,
+    audio_freq: i32,
+    audio_format: AudioFormat,
+    audio_channels: i32,
+    audio_chunk_size: i32,
+    mixer_channels: i32,
+    vsync: bool,
+    resizable: bool,
+    borderless: bool,
+    fullscreen: bool,
+    fullscreen_toggle: bool,
+    position: Option<(i32, i32)>,
+    min_size: Option<(u32, u32)>,
+    scale_mode: ScaleMode,
+    bar_color: Color,
+    input: Input,
+    controller_mappings: Vec<String>,
+    on_controller_error: Option<fn(&str)>
+}
+
+impl App {
+    /// Start configuring an app. Everything not set on the builder keeps the same defaults run uses
+    pub fn builder(title: &str, width: u32, height: u32) -> AppBuilder {
+        AppBuilder {
+            app: Self {
+                title: title.to_string(),
+                width,
+                height,
+                fps: 60.0,
+                bg_color: Color::BLACK,
+                timestep: Timestep::Variable,
+                audio_freq: 44100,
+                audio_format: AUDIO_S16LSB,
+                audio_channels: DEFAULT_CHANNELS,
+                audio_chunk_size: 1024,
+                mixer_channels: 16,
+                vsync: true,
+                resizable: true,
+                borderless: false,
+                fullscreen: false,
+                fullscreen_toggle: true,
+                position: None,
+                min_size: None,
+                scale_mode: ScaleMode::Stretch,
+                bar_color: Color::BLACK,
+                input: Input::new(),
+                controller_mappings: Vec::new(),
+                on_controller_error: None
+            }
+        }
+    }
+
+    /// Create the window and run the game
+    ///
+    /// - start_room and rooms are the "scenes" of your game
+    /// - ctl_objs are objects that are updated and exist outside of the room
+    /// - snd_srcs, img_srcs, and font_srcs are the raw bytes of resources
+    pub fn run<Img, Snd, Fnt, Spr, Rm, Data>(
+            &self, start_room: Rm, rooms: &HashMap<Rm, Room<Img, Snd, Fnt, Spr, Rm, Data>>,
+            ctl_objs: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+            snd_srcs: &[(Snd, &'static [u8], bool)], img_srcs: &[(Img, &[u8])],
+            font_srcs: &[(Fnt, u16, &[u8])]) -> Result<(), String> where
+                Spr: IndexRestriction,
+                Img: IndexRestriction,
+                Snd: IndexRestriction,
+                Fnt: IndexRestriction,
+                Rm: IndexRestriction,
+                Data: Clone {
+        let ctx = sdl2::init()?;
+        let _ = ctx.audio()?;
+        let subsys = ctx.video()?;
+        let mut win_builder = subsys.window(&self.title, self.width, self.height);
+        if let Some((x, y)) = self.position {
+            win_builder.position(x, y);
+        } else {
+            win_builder.position_centered();
+        }
+        if self.resizable {
+            win_builder.resizable();
+        }
+        if self.borderless {
+            win_builder.borderless();
+        }
+        if self.fullscreen {
+            win_builder.fullscreen();
+        }
+        let mut win = win_builder.build().map_err(|e| e.to_string())?;
+        if let Some((w, h)) = self.min_size {
+            win.set_minimum_size(w, h).map_err(|e| e.to_string())?;
+        }
+        let mut event_pump = ctx.event_pump()?;
+        let mut controllers = Controllers::new(&ctx)?;
+        for mappings in self.controller_mappings.iter() {
+            controllers.add_mappings(mappings)?;
+        }
+        let mut cnv_builder = win.into_canvas().accelerated();
+        if self.vsync {
+            cnv_builder = cnv_builder.present_vsync();
+        }
+        let mut cnv = cnv_builder.build().map_err(|e| e.to_string())?;
+        let creator = cnv.texture_creator();
+
+        // Everything is drawn at the logical size first, then scaled into the window
+        let logical = (self.width, self.height);
+        let mut target = creator.create_texture_target(None, self.width, self.height)
+            .map_err(|e| e.to_string())?;
+        target.set_blend_mode(BlendMode::None);
+
+        let ttf_ctx = sdl2::ttf::init().map_err(|e| e.to_string())?;
+
+        sdl2::mixer::open_audio(
+            self.audio_freq, self.audio_format, self.audio_channels, self.audio_chunk_size
+        )?;
+        let _ = sdl2::mixer::init(InitFlag::MP3 | InitFlag::FLAC | InitFlag::MOD | InitFlag::OGG)?;
+        sdl2::mixer::allocate_channels(self.mixer_channels);
+
+        // Load resources from file paths
+        let mut snds = HashMap::new();
+        for (key, src, is_music) in snd_srcs.iter() {
+            if *is_music {
+                snds.insert(*key, Sound::load_music(src)?);
+            } else {
+                snds.insert(*key, Sound::load_chunk(src)?);
+            }
+        }
+        let mut imgs = HashMap::new();
+        for (key, src) in img_srcs.iter() {
+            let mut img = image::load_from_memory(src)
+                .map_err(|e| e.to_string())?
+                .to_rgba8();
+            imgs.insert(*key, Image::new(&mut img, &creator)?);
+        }
+        let mut fonts = HashMap::new();
+        for (key, size, src) in font_srcs.iter() {
+            fonts.insert(*key, Font::new(src, *size, &ttf_ctx)?);
+        }
+        let resources = Resources {
+            imgs: &imgs,
+            snds: &snds,
+            fonts: &fonts,
+            creator: &creator
+        };
+
+        let mut game = Game::new(start_room, rooms, ctl_objs);
+        game.input = self.input.clone();
+        game.set_view_size((self.width as f64, self.height as f64));
+
+        // Create a timed game loop
+        let mut start = Instant::now();
+        let mut since_render = 0.0;
+        let mut clock = Clock::new(self.timestep);
+        let mut is_fullscreen = self.fullscreen;
+        'game: loop {
+            // Maintain fps
+            std::thread::sleep(Duration::from_millis(1)); // Force a sleep bc CPU is really fast lol
+            let frame_time = start.elapsed().as_secs_f64();
+            start = Instant::now();
+            since_render += frame_time;
 
             // Update
             for event in event_pump.poll_iter() {
+                let event = self.map_mouse_event(event, cnv.window().size());
                 match event {
                     Event::KeyUp { scancode, keymod, .. }
-                            if scancode == Some(Scancode::Return)
+                            if self.fullscreen_toggle
+                                && scancode == Some(Scancode::Return)
                                 && keymod.contains(Mod::LALTMOD) => {
                         cnv.window_mut().set_fullscreen(if is_fullscreen {
                             is_fullscreen = false;
@@ -128,54 +339,335 @@ pub fn run<'a, 'b, Img, Snd, Fnt, Spr, Rm, Data>(
                             is_fullscreen = true;
                             FullscreenType::True
                         })?;
-                    }, Event::Window { win_event, .. } => {
-                        if let WindowEvent::Resized(w, h) = win_event {
-                            cnv.set_scale(w as f32 / width as f32, h as f32 / height as f32)?;
-                        }
                     }, Event::Quit { .. } => {
                         break 'game;
                     }, _ => {}
                 }
-                rm.handle_sdl_event(&event);
-                for obj in ctl_objs.iter_mut() {
-                    obj.handle_sdl_event(&event);
-                }
-            }
-            let other_ctls = ctl_objs.clone();
-            let mut new_room = rm.update(delta, &other_ctls);
-            let rm_objs = rm.objs.clone();
-            let mut to_add = vec![];
-            for obj in ctl_objs.iter_mut() {
-                let ret = obj.update(delta, &room, &other_ctls, &rm_objs);
-                if ret.0.is_some() && new_room.is_none() {
-                    new_room = ret.0;
-                }
-                if ret.1.len() > 0 && to_add.len() < 1 {
-                    to_add = ret.1.clone();
+                // A pad that fails to open shouldn't end the game, it just doesn't get used
+                if let Err(err) = controllers.handle_sdl_event(&event, &mut game.input) {
+                    if let Some(on_controller_error) = self.on_controller_error {
+                        on_controller_error(&err);
+                    }
                 }
+                game.handle_sdl_event(&event);
             }
-            if to_add.len() > 0 {
-                rm.objs.append(&mut to_add);
-            }
 
-            if elapsed > 1.0 / fps {
+            clock.tick(frame_time, |delta| game.update(delta));
+
+            if since_render > 1.0 / self.fps {
+                let alpha = clock.alpha();
+                let mut res = Ok(());
+                cnv.with_texture_canvas(&mut target, |tex_cnv| {
+                    tex_cnv.set_draw_color(self.bg_color);
+                    tex_cnv.clear();
+                    res = game.render(tex_cnv, &resources, since_render, alpha);
+                }).map_err(|e| e.to_string())?;
+                res?;
+                cnv.set_draw_color(self.bar_color);
                 cnv.clear();
-                cnv.set_draw_color(*bg_color);
-                rm.render(&mut cnv, &imgs, &snds, &fonts, &creator, elapsed)?;
-                for obj in ctl_objs.iter_mut() {
-                    obj.render(&mut cnv, &room, &imgs, &snds, &fonts, &creator, elapsed)?;
-                }
+                let dest = self.scale_mode.dest_rect(logical, cnv.output_size()?);
+                cnv.copy(&target, None, Some(dest))?;
                 cnv.present();
-                elapsed = 0.0;
+                since_render = 0.0;
             }
 
-            if new_room.is_some() {
-                room = new_room.unwrap();
-                room_reset = true;
-            }
+            game.change_room();
+        }
+
+        Ok(())
+    }
+
+    /// Move mouse positions from window coordinates into the game's logical coordinates
+    fn map_mouse_event(&self, event: Event, window: (u32, u32)) -> Event {
+        let logical = (self.width, self.height);
+        match event {
+            Event::MouseMotion {
+                    timestamp, window_id, which, mousestate, x, y, xrel, yrel } => {
+                let (x, y) = self.scale_mode.window_to_logical(logical, window, (x, y));
+                let (xrel, yrel) = self.scale_mode.window_to_logical_rel(
+                    logical, window, (xrel, yrel)
+                );
+                Event::MouseMotion { timestamp, window_id, which, mousestate, x, y, xrel, yrel }
+            }, Event::MouseButtonDown { timestamp, window_id, which, mouse_btn, clicks, x, y } => {
+                let (x, y) = self.scale_mode.window_to_logical(logical, window, (x, y));
+                Event::MouseButtonDown { timestamp, window_id, which, mouse_btn, clicks, x, y }
+            }, Event::MouseButtonUp { timestamp, window_id, which, mouse_btn, clicks, x, y } => {
+                let (x, y) = self.scale_mode.window_to_logical(logical, window, (x, y));
+                Event::MouseButtonUp { timestamp, window_id, which, mouse_btn, clicks, x, y }
+            }, _ => event
         }
     }
+}
 
-    Ok(())
+/// Builds an App one setting at a time
+#[derive(Clone)]
+pub struct AppBuilder {
+    app: App
 }
 
+impl AppBuilder {
+    /// How often to draw a frame
+    pub fn fps(mut self, fps: f64) -> Self {
+        self.app.fps = fps;
+        self
+    }
+
+    pub fn bg_color(mut self, bg_color: Color) -> Self {
+        self.app.bg_color = bg_color;
+        self
+    }
+
+    pub fn timestep(mut self, timestep: Timestep) -> Self {
+        self.app.timestep = timestep;
+        self
+    }
+
+    /// Audio frequency in Hz. Defaults to 44100
+    pub fn audio_freq(mut self, freq: i32) -> Self {
+        self.app.audio_freq = freq;
+        self
+    }
+
+    /// Sample format from sdl2::mixer (AUDIO_S16LSB, AUDIO_F32LSB, etc). Defaults to AUDIO_S16LSB
+    pub fn audio_format(mut self, format: AudioFormat) -> Self {
+        self.app.audio_format = format;
+        self
+    }
+
+    /// Output channels, i.e. 1 for mono or 2 for stereo. Defaults to stereo
+    pub fn audio_channels(mut self, channels: i32) -> Self {
+        self.app.audio_channels = channels;
+        self
+    }
+
+    /// Size of the audio buffer in bytes. Defaults to 1024
+    pub fn audio_chunk_size(mut self, chunk_size: i32) -> Self {
+        self.app.audio_chunk_size = chunk_size;
+        self
+    }
+
+    /// How many sounds can play at once. Defaults to 16
+    pub fn mixer_channels(mut self, channels: i32) -> Self {
+        self.app.mixer_channels = channels;
+        self
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.app.vsync = vsync;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.app.resizable = resizable;
+        self
+    }
+
+    pub fn borderless(mut self, borderless: bool) -> Self {
+        self.app.borderless = borderless;
+        self
+    }
+
+    /// Start the window in fullscreen
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.app.fullscreen = fullscreen;
+        self
+    }
+
+    /// Whether Alt+Enter switches between fullscreen and windowed. Defaults to true
+    pub fn fullscreen_toggle(mut self, fullscreen_toggle: bool) -> Self {
+        self.app.fullscreen_toggle = fullscreen_toggle;
+        self
+    }
+
+    /// Where to put the window on screen. Centered if not set
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.app.position = Some((x, y));
+        self
+    }
+
+    /// Smallest size the user can resize the window to
+    pub fn min_size(mut self, width: u32, height: u32) -> Self {
+        self.app.min_size = Some((width, height));
+        self
+    }
+
+    /// How to fit the game into the window when they're different sizes. Defaults to Stretch
+    pub fn scale_mode(mut self, scale_mode: ScaleMode) -> Self {
+        self.app.scale_mode = scale_mode;
+        self
+    }
+
+    /// Color of the bars around the game with the Letterbox and PixelPerfect scale modes
+    pub fn bar_color(mut self, bar_color: Color) -> Self {
+        self.app.bar_color = bar_color;
+        self
+    }
+
+    /// Action bindings the game starts with
+    pub fn input(mut self, input: Input) -> Self {
+        self.app.input = input;
+        self
+    }
+
+    /// Extra SDL controller mappings (e.g. the contents of gamecontrollerdb.txt) for controllers
+    /// SDL doesn't know about. Can be called more than once
+    pub fn controller_mappings(mut self, mappings: &str) -> Self {
+        self.app.controller_mappings.push(mappings.to_string());
+        self
+    }
+
+    /// Called with what went wrong when a controller is plugged in but can't be opened, e.g. to
+    /// log it. The game keeps running without that controller either way
+    pub fn on_controller_error(mut self, on_controller_error: fn(&str)) -> Self {
+        self.app.on_controller_error = Some(on_controller_error);
+        self
+    }
+
+    pub fn build(self) -> App {
+        self.app
+    }
+}
+
+/// Create a window and run the game
+///
+/// - Title, width, height, fps, and bg_color all refer to window params
+/// - start_room and rooms are the "scenes" of your game
+/// - ctl_objs are objects that are updated and exist outside of the room
+/// - snd_srcs, img_srcs, and font_srcs are file paths to resources
+///
+/// This is shorthand for App::builder with default settings. Use that for more control
+#[allow(clippy::too_many_arguments)]
+pub fn run<Img, Snd, Fnt, Spr, Rm, Data>(
+    title: &str, width: u32, height: u32, fps: f64, bg_color: &Color,
+    start_room: Rm, rooms: &HashMap<Rm, Room<Img, Snd, Fnt, Spr, Rm, Data>>,
+    ctl_objs: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+    snd_srcs: &[(Snd, &'static [u8], bool)], img_srcs: &[(Img, &[u8])],
+    font_srcs: &[(Fnt, u16, &[u8])]) -> Result<(), String> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    App::builder(title, width, height)
+        .fps(fps)
+        .bg_color(*bg_color)
+        .build()
+        .run(start_room, rooms, ctl_objs, snd_srcs, img_srcs, font_srcs)
+}
+
+/// The window-independent part of a running game: the rooms, the control objects, and which room
+/// is active. The windowed app and the headless driver both run their games through this
+pub(crate) struct Game<Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    pub rooms: HashMap<Rm, Room<Img, Snd, Fnt, Spr, Rm, Data>>,
+    pub ctl_objs: Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+    pub room: Rm,
+    pub input: Input,
+    next_room: Option<Rm>
+}
+
+impl<Img, Snd, Fnt, Spr, Rm, Data> Game<Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    pub fn new(
+            start_room: Rm, rooms: &HashMap<Rm, Room<Img, Snd, Fnt, Spr, Rm, Data>>,
+            ctl_objs: &[ControlObject<Img, Snd, Fnt, Spr, Rm, Data>]) -> Self {
+        Self {
+            rooms: rooms.clone(),
+            ctl_objs: ctl_objs.to_vec(),
+            room: start_room,
+            input: Input::new(),
+            next_room: None
+        }
+    }
+
+    pub fn handle_sdl_event(&mut self, event: &Event) {
+        self.input.handle_sdl_event(event);
+        if let Some(rm) = self.rooms.get_mut(&self.room) {
+            rm.handle_sdl_event(event);
+        }
+        for obj in self.ctl_objs.iter_mut() {
+            obj.handle_sdl_event(event);
+        }
+    }
+
+    /// Run a single update of the current room and the control objects. Returns true if something
+    /// asked to change rooms, in which case no more updates should run until change_room is called
+    pub fn update(&mut self, delta: f64) -> bool {
+        let Some(rm) = self.rooms.get_mut(&self.room) else {
+            return false;
+        };
+        let mouse_pos = self.input.mouse_pos();
+        let mouse_pos = (mouse_pos.0 as f64, mouse_pos.1 as f64);
+        self.input.set_mouse_world_pos(rm.screen_to_world(mouse_pos));
+        let mut new_room = rm.update(delta, &mut self.input, &self.ctl_objs);
+        let mut to_add = vec![];
+        for i in 0..self.ctl_objs.len() {
+            // Take the object out while it updates so it can see the rest without copying them
+            let mut obj = self.ctl_objs.remove(i);
+            let ret = obj.update(delta, &mut self.input, &self.room, &self.ctl_objs, &rm.objs);
+            self.ctl_objs.insert(i, obj);
+            if ret.0.is_some() && new_room.is_none() {
+                new_room = ret.0;
+            }
+            if !ret.1.is_empty() && to_add.is_empty() {
+                to_add = ret.1;
+            }
+        }
+        for obj in to_add {
+            rm.add(obj);
+        }
+        self.input.end_update();
+        if new_room.is_some() {
+            self.next_room = new_room;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn render(
+            &mut self, cnv: &mut Canvas<Window>, res: &Resources<Img, Snd, Fnt>,
+            elapsed: f64, alpha: f64) -> Result<(), String> {
+        if let Some(rm) = self.rooms.get_mut(&self.room) {
+            rm.render(cnv, res, elapsed, alpha)?;
+        }
+        for obj in self.ctl_objs.iter_mut() {
+            obj.render(cnv, &self.room, res, elapsed)?;
+        }
+        Ok(())
+    }
+
+    /// Tell every room's camera how big the screen is, and center the current room's camera on its
+    /// target now that it knows
+    pub fn set_view_size(&mut self, size: (f64, f64)) {
+        for rm in self.rooms.values_mut() {
+            rm.cam.size = size;
+        }
+        if let Some(rm) = self.rooms.get_mut(&self.room) {
+            rm.snap_camera();
+        }
+    }
+
+    /// Switch to whatever room an update asked for, resetting it unless it's persistant
+    pub fn change_room(&mut self) {
+        if let Some(room) = self.next_room.take() {
+            self.room = room;
+            if let Some(rm) = self.rooms.get_mut(&self.room) {
+                if !rm.persistant {
+                    rm.reset();
+                }
+            }
+        }
+    }
+}
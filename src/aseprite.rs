@@ -0,0 +1,164 @@
+//! Load sprite sheets exported from Aseprite (https://www.aseprite.org), so animations don't have
+//! to be cut up by hand
+
+use std::collections::HashMap;
+use sdl2::rect::Rect;
+use serde::Deserialize;
+use serde_json::Value;
+use crate::{
+    obj::{
+        AnimDirection, Frame, Sprite
+    }, IndexRestriction
+};
+
+/// Turns the tags of an Aseprite sheet's JSON data into sprites. Works with both the "Array" and
+/// "Hash" JSON options. Each frame keeps the duration it has in Aseprite, and each sprite plays in
+/// its tag's direction. Trimmed frames aren't supported, so export with trimming off
+///
+/// - img: Img (the sheet's image, already loaded like any other)
+/// - tags: HashMap<String, Spr> (sprite for each tag name. Other tags aren't loaded)
+/// - all_frames: Option<Spr> (sprite to make out of every frame, e.g. for sheets with no tags)
+/// - origin: (i32, i32) (see Sprite::new)
+/// - scale: f64 (how much bigger than the sheet to draw each frame)
+pub struct AsepriteLoader<Img, Spr> where Img: IndexRestriction, Spr: IndexRestriction {
+    pub img: Img,
+    pub tags: HashMap<String, Spr>,
+    pub all_frames: Option<Spr>,
+    pub origin: (i32, i32),
+    pub scale: f64
+}
+
+impl<Img, Spr> AsepriteLoader<Img, Spr> where Img: IndexRestriction, Spr: IndexRestriction {
+    pub fn new(img: Img) -> Self {
+        Self {
+            img,
+            tags: HashMap::new(),
+            all_frames: None,
+            origin: (0, 0),
+            scale: 1.0
+        }
+    }
+
+    pub fn with_tag(mut self, tag: &str, spr: Spr) -> Self {
+        self.tags.insert(tag.to_string(), spr);
+        self
+    }
+
+    pub fn with_all_frames(mut self, spr: Spr) -> Self {
+        self.all_frames = Some(spr);
+        self
+    }
+
+    pub fn with_origin(mut self, origin: (i32, i32)) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Build a sprite for every registered tag, ready to give to GameObjectState::new
+    pub fn load(&self, bytes: &[u8]) -> Result<HashMap<Spr, Sprite<Img>>, String> {
+        let sheet: AseSheet = serde_json::from_slice(bytes).map_err(|err| err.to_string())?;
+
+        let frames = match sheet.frames {
+            Value::Array(frames) => frames,
+            // The hash form is keyed by whatever --filename-format makes, so the only thing to
+            // go by is the order Aseprite wrote them in, which serde_json keeps
+            Value::Object(frames) => frames.into_iter().map(|(_, frame)| frame).collect(),
+            _ => return Err("Sprite sheet frames should be a list or a map".to_string())
+        };
+        let frames = frames.into_iter()
+            .map(|frame| {
+                let frame: AseFrame = serde_json::from_value(frame)
+                    .map_err(|err| err.to_string())?;
+                if frame.trimmed {
+                    return Err(
+                        "Sprite sheet has trimmed frames, which aren't supported".to_string()
+                    );
+                }
+                let rect = frame.frame;
+                Ok(Frame::new(
+                    self.img,
+                    Rect::new(rect.x, rect.y, rect.w, rect.h),
+                    (
+                        (rect.w as f64 * self.scale).round() as i32,
+                        (rect.h as f64 * self.scale).round() as i32
+                    )
+                ).with_duration(frame.duration / 1000.0))
+            }).collect::<Result<Vec<_>, String>>()?;
+
+        let mut sprs = HashMap::new();
+        if let Some(spr) = self.all_frames {
+            sprs.insert(spr, self.sprite(frames.clone(), AnimDirection::Forward));
+        }
+        for (name, spr) in self.tags.iter() {
+            let tag = sheet.meta.frame_tags.iter().find(|tag| tag.name == *name)
+                .ok_or(format!("No tag '{}' in the sprite sheet", name))?;
+            let tag_frames = frames.get(tag.from..=tag.to)
+                .ok_or(format!("Tag '{}' goes past the last frame", name))?;
+            let dir = match tag.direction.as_str() {
+                "reverse" => AnimDirection::Reverse,
+                "pingpong" | "pingpong_reverse" => AnimDirection::PingPong,
+                _ => AnimDirection::Forward
+            };
+            let mut sprite = self.sprite(tag_frames.to_vec(), dir);
+            if tag.direction == "pingpong_reverse" {
+                // Start at the end and head back down first
+                sprite.anim_idx = tag_frames.len() - 1;
+                sprite.anim_backwards = true;
+            }
+            sprs.insert(*spr, sprite);
+        }
+        Ok(sprs)
+    }
+
+    fn sprite(&self, frames: Vec<Frame<Img>>, dir: AnimDirection) -> Sprite<Img> {
+        // Every frame has its own duration, but keep anim_spd close in case they're taken away
+        let total = frames.iter().filter_map(Frame::duration).sum::<f64>();
+        let anim_spd = if total > 0.0 { frames.len() as f64 / total } else { 0.0 };
+        Sprite::new(frames, anim_spd, self.origin).with_anim_dir(dir)
+    }
+}
+
+/// The parts of Aseprite's JSON format that get used
+#[derive(Deserialize)]
+struct AseSheet {
+    frames: Value,
+    meta: AseMeta
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AseMeta {
+    #[serde(default)]
+    frame_tags: Vec<AseTag>
+}
+
+#[derive(Deserialize)]
+struct AseTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String
+}
+
+#[derive(Deserialize)]
+struct AseFrame {
+    frame: AseRect,
+    #[serde(default)]
+    trimmed: bool,
+    #[serde(default)]
+    duration: f64
+}
+
+#[derive(Deserialize)]
+struct AseRect {
+    x: i32,
+    y: i32,
+    w: u32,
+    h: u32
+}
@@ -0,0 +1,191 @@
+//! Views into a room that can scroll around, follow an object, zoom, and share the screen
+
+use sdl2::{
+    pixels::Color,
+    rect::Rect
+};
+use crate::{
+    obj::ObjectId,
+    util::lerp
+};
+
+/// Decides which part of a room ends up on screen. Every room owns one
+///
+/// - pos: (f64, f64) (top left of the view in world coordinates)
+/// - zoom: f64 (2.0 makes everything twice as big, so half as much of the room fits on screen)
+/// - size: (f64, f64) (size of the screen in logical pixels, filled in by the engine)
+/// - follow: Option<ObjectId> (object to keep centered)
+/// - lag: f64 (fraction of the distance to the target still left after a second, 0 snaps to it)
+/// - bounds: Option<Rect> (part of the room the view is never allowed to leave)
+#[derive(Clone, Debug)]
+pub struct Camera {
+    pub pos: (f64, f64),
+    pub prev_pos: (f64, f64),
+    pub zoom: f64,
+    pub size: (f64, f64),
+    pub follow: Option<ObjectId>,
+    pub lag: f64,
+    pub bounds: Option<Rect>
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camera {
+    /// A camera at the origin with no zoom, which draws everything exactly where it is
+    pub fn new() -> Self {
+        Self {
+            pos: (0.0, 0.0),
+            prev_pos: (0.0, 0.0),
+            zoom: 1.0,
+            size: (0.0, 0.0),
+            follow: None,
+            lag: 0.0,
+            bounds: None
+        }
+    }
+
+    pub fn with_pos(mut self, pos: (f64, f64)) -> Self {
+        self.pos = pos;
+        self.prev_pos = pos;
+        self
+    }
+
+    pub fn with_zoom(mut self, zoom: f64) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// Keep an object centered on screen. Room::add gives back the id, and objects passed to
+    /// Room::new are numbered from 1 in order
+    pub fn with_follow(mut self, id: ObjectId, lag: f64) -> Self {
+        self.follow = Some(id);
+        self.lag = lag;
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: Rect) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// How much of the room fits on screen at the current zoom
+    pub fn view_size(&self) -> (f64, f64) {
+        (self.size.0 / self.zoom, self.size.1 / self.zoom)
+    }
+
+    /// The part of the room that's on screen
+    pub fn view_rect(&self) -> Rect {
+        let view_size = self.view_size();
+        Rect::new(
+            self.pos.0.floor() as i32, self.pos.1.floor() as i32,
+            view_size.0.ceil() as u32, view_size.1.ceil() as u32
+        )
+    }
+
+    pub fn world_to_screen(&self, pos: (f64, f64)) -> (f64, f64) {
+        ((pos.0 - self.pos.0) * self.zoom, (pos.1 - self.pos.1) * self.zoom)
+    }
+
+    /// Useful for mouse picking. Input::mouse_world_pos already does this with the room's camera
+    pub fn screen_to_world(&self, pos: (f64, f64)) -> (f64, f64) {
+        (pos.0 / self.zoom + self.pos.0, pos.1 / self.zoom + self.pos.1)
+    }
+
+    /// Move towards the position that would center the target, then stay inside the bounds
+    pub fn update(&mut self, delta: f64, target: Option<(f64, f64)>) {
+        self.prev_pos = self.pos;
+        if let Some(target) = target {
+            let goal = self.centered_on(target);
+            let weight = 1.0 - self.lag.clamp(0.0, 1.0).powf(delta);
+            self.pos = (lerp(self.pos.0, goal.0, weight), lerp(self.pos.1, goal.1, weight));
+        }
+        self.pos = self.clamped(self.pos);
+    }
+
+    /// Jump straight to the target with no lag or interpolation, e.g. when a room resets
+    pub fn snap_to(&mut self, target: Option<(f64, f64)>) {
+        if let Some(target) = target {
+            self.pos = self.centered_on(target);
+        }
+        self.pos = self.clamped(self.pos);
+        self.prev_pos = self.pos;
+    }
+
+    /// A copy of the camera between its last two positions, for drawing with interpolation
+    pub fn lerped(&self, alpha: f64) -> Self {
+        let mut cam = self.clone();
+        cam.pos = (
+            lerp(self.prev_pos.0, self.pos.0, alpha),
+            lerp(self.prev_pos.1, self.pos.1, alpha)
+        );
+        cam
+    }
+
+    fn centered_on(&self, target: (f64, f64)) -> (f64, f64) {
+        let view_size = self.view_size();
+        (target.0 - view_size.0 / 2.0, target.1 - view_size.1 / 2.0)
+    }
+
+    fn clamped(&self, pos: (f64, f64)) -> (f64, f64) {
+        let Some(bounds) = self.bounds else {
+            return pos;
+        };
+        let view_size = self.view_size();
+        let clamp_axis = |val: f64, start: i32, len: u32, view_len: f64| {
+            let (start, len) = (start as f64, len as f64);
+            if view_len >= len {
+                // Bounds are smaller than the view, so just center them
+                start + (len - view_len) / 2.0
+            } else {
+                val.clamp(start, start + len - view_len)
+            }
+        };
+        (
+            clamp_axis(pos.0, bounds.x(), bounds.width(), view_size.0),
+            clamp_axis(pos.1, bounds.y(), bounds.height(), view_size.1)
+        )
+    }
+}
+
+/// A rectangle of the screen that shows the room through its own camera. Give a room several of
+/// these for split screen or a minimap
+///
+/// - dest: Rect (where on screen the view goes, in logical pixels)
+/// - cam: Camera (its size is always the size of dest)
+/// - bg_color: Option<Color> (fill the viewport before drawing, e.g. to make a minimap stand out)
+#[derive(Clone, Debug)]
+pub struct Viewport {
+    pub dest: Rect,
+    pub cam: Camera,
+    pub bg_color: Option<Color>
+}
+
+impl Viewport {
+    pub fn new(dest: Rect, mut cam: Camera) -> Self {
+        cam.size = (dest.width() as f64, dest.height() as f64);
+        Self {
+            dest,
+            cam,
+            bg_color: None
+        }
+    }
+
+    pub fn with_bg_color(mut self, bg_color: Color) -> Self {
+        self.bg_color = Some(bg_color);
+        self
+    }
+
+    /// Convert a screen position into the room, if it's inside the viewport
+    pub fn screen_to_world(&self, pos: (f64, f64)) -> Option<(f64, f64)> {
+        let (x, y) = (pos.0 - self.dest.x() as f64, pos.1 - self.dest.y() as f64);
+        if x < 0.0 || y < 0.0 || x >= self.dest.width() as f64 || y >= self.dest.height() as f64 {
+            None
+        } else {
+            Some(self.cam.screen_to_world((x, y)))
+        }
+    }
+}
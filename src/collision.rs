@@ -1,208 +1,751 @@
 //! Implement collision shapes and handle collisions between them
 
-use sdl2::rect::Rect;
+use std::collections::{
+    HashMap, HashSet
+};
 
-/// Colliders that attach to GameObjects. Support Circle and Rect colliders
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// Shapes covering more grid cells than this skip the grid and get checked against everything
+const MAX_CELLS_PER_SHAPE: i64 = 64;
+
+/// Wiggle room for floating point error when checking if contact points are touching
+const CONTACT_EPSILON: f64 = 1e-6;
+
+/// Colliders that attach to GameObjects. Support Circle, Rect, and (convex) Polygon colliders.
+///
+/// Polygon points are relative to the center and can wind either way. A polygon with one point
+/// acts like a single point, and one with no points never collides with anything.
+///
+/// Everything is in floating point so colliders can sit between pixels like objects can. Use
+/// scaled and rotated to transform one (or let the object's sprite do it, see GameObjectState)
+#[derive(Clone, Debug, PartialEq)]
 pub enum CollisionShape {
     Circle {
-        center: (i32, i32),
-        radius: u32
+        center: (f64, f64),
+        radius: f64
     }, Rect {
-        center: (i32, i32),
-        size: (u32, u32)
+        center: (f64, f64),
+        size: (f64, f64)
     }, Polygon {
-        center: (i32, i32),
-        points: Vec<(i32, i32)>
+        center: (f64, f64),
+        points: Vec<(f64, f64)>
+    }
+}
+
+/// How two shapes overlap
+///
+/// - normal: (f64, f64) (unit vector pointing from the first shape towards the second)
+/// - depth: f64 (how far they overlap along the normal. Touching shapes have a depth of 0)
+/// - points: Vec<(f64, f64)> (where they touch, one or two points in room coordinates)
+///
+/// Moving the first shape by -normal * depth (or the second by normal * depth) separates them
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contact {
+    pub normal: (f64, f64),
+    pub depth: f64,
+    pub points: Vec<(f64, f64)>
+}
+
+impl Contact {
+    /// The same contact from the other shape's point of view
+    pub fn flipped(&self) -> Self {
+        Self {
+            normal: (-self.normal.0, -self.normal.1),
+            depth: self.depth,
+            points: self.points.clone()
+        }
     }
 }
 
+/// Where a ray or a moving shape first hits something
+///
+/// - point: (f64, f64) (where it hit, in room coordinates)
+/// - normal: (f64, f64) (unit vector pointing out of the surface that was hit)
+/// - dist: f64 (how far the ray or shape got before hitting)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit {
+    pub point: (f64, f64),
+    pub normal: (f64, f64),
+    pub dist: f64
+}
+
 impl CollisionShape {
-    pub fn collides_with(&self, other: &CollisionShape) -> bool {
+    /// The smallest box around the shape. Shapes can only collide if their boxes overlap
+    pub fn aabb(&self) -> Aabb {
+        let (min, max) = match self.convex() {
+            Convex::Circle { center, radius } => (
+                (center.0 - radius, center.1 - radius),
+                (center.0 + radius, center.1 + radius)
+            ), Convex::Polygon { points } if !points.is_empty() => {
+                points.iter().fold((points[0], points[0]), |(min, max), point| (
+                    (min.0.min(point.0), min.1.min(point.1)),
+                    (max.0.max(point.0), max.1.max(point.1))
+                ))
+            }, Convex::Polygon { .. } => (self.center(), self.center())
+        };
+        Aabb {
+            min: (min.0.floor() as i32, min.1.floor() as i32),
+            max: (max.0.ceil() as i32, max.1.ceil() as i32)
+        }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
         match self {
-            CollisionShape::Circle { center, radius } => {
-                match other {
-                    CollisionShape::Circle { center: other_center, radius: other_radius } => {
-                        let a = other_center.0 - center.0;
-                        let b = other_center.1 - center.1;
-                        let c = other_radius + radius;
-                        ((a * a) + (b * b)) as u32 <= (c * c)
-                    }, CollisionShape::Rect { center: other_center, size: other_size } => {
-                        let mut test = center.clone();
-                        let rect = Rect::new(
-                            other_center.0 - other_size.0 as i32 / 2,
-                            other_center.1 - other_size.1 as i32 / 2,
-                            other_size.0,
-                            other_size.1
-                        );
-                        if center.0 < rect.x {
-                            test.0 = rect.x;
-                        } else if center.0 > rect.x + rect.w {
-                            test.0 = rect.x + rect.w;
-                        }
-                        if center.1 < rect.y {
-                            test.1 = rect.y;
-                        } else if center.1 > rect.y + rect.h {
-                            test.1 = rect.y + rect.h;
-                        }
-                        let dist_lat = (center.0 - test.0, center.1 - test.1);
-                        let dist_sqrd = (dist_lat.0 * dist_lat.0) + (dist_lat.1 * dist_lat.1);
-                        dist_sqrd as u32 <= radius * radius
-                    }, CollisionShape::Polygon { center: other_center, points } => {
-                        for point in points.iter() {
-                            let dists = (
-                                other_center.0 + point.0 - center.0,
-                                other_center.1 + point.1 - center.1
-                            );
-                            let dist_sqrd = dists.0 * dists.0 + dists.1 + dists.1;
-                            if (dist_sqrd as u32) < radius * radius {
-                                return true;
-                            }
-                        }
-                        false
-                    }
+            CollisionShape::Circle { center, .. }
+                    | CollisionShape::Rect { center, .. }
+                    | CollisionShape::Polygon { center, .. } => *center
+        }
+    }
+
+    /// The same shape with its center moved, e.g. from an object's origin to its spot in the room
+    pub fn moved(&self, offset: (f64, f64)) -> Self {
+        let mut shape = self.clone();
+        match shape {
+            CollisionShape::Circle { ref mut center, .. }
+                    | CollisionShape::Rect { ref mut center, .. }
+                    | CollisionShape::Polygon { ref mut center, .. } => {
+                center.0 += offset.0;
+                center.1 += offset.1;
+            }
+        }
+        shape
+    }
+
+    /// The same shape stretched away from (0, 0), the object's origin. Circles can't be squashed,
+    /// so they grow by the bigger of the two scales
+    pub fn scaled(&self, scale: (f64, f64)) -> Self {
+        match self {
+            CollisionShape::Circle { center, radius } => CollisionShape::Circle {
+                center: (center.0 * scale.0, center.1 * scale.1),
+                radius: radius * scale.0.abs().max(scale.1.abs())
+            }, CollisionShape::Rect { center, size } => CollisionShape::Rect {
+                center: (center.0 * scale.0, center.1 * scale.1),
+                size: (size.0 * scale.0.abs(), size.1 * scale.1.abs())
+            }, CollisionShape::Polygon { center, points } => CollisionShape::Polygon {
+                center: (center.0 * scale.0, center.1 * scale.1),
+                points: points.iter().map(|point| (point.0 * scale.0, point.1 * scale.1)).collect()
+            }
+        }
+    }
+
+    /// The same shape turned clockwise by angle degrees around a point, the same way SDL rotates
+    /// sprites. Rotated rects turn into polygons
+    pub fn rotated(&self, angle: f64, pivot: (f64, f64)) -> Self {
+        if angle % 360.0 == 0.0 {
+            return self.clone();
+        }
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let (sin, cos) = if angle % 90.0 == 0.0 {
+            // Keep quarter turns exact so rects stay pixel aligned
+            (sin.round(), cos.round())
+        } else {
+            (sin, cos)
+        };
+        let rotate = |vec: (f64, f64)| (vec.0 * cos - vec.1 * sin, vec.0 * sin + vec.1 * cos);
+        let center = self.center();
+        let offset = rotate((center.0 - pivot.0, center.1 - pivot.1));
+        let center = (pivot.0 + offset.0, pivot.1 + offset.1);
+        match self {
+            CollisionShape::Circle { radius, .. } => CollisionShape::Circle {
+                center,
+                radius: *radius
+            }, CollisionShape::Rect { size, .. } => {
+                let (w, h) = (size.0 / 2.0, size.1 / 2.0);
+                CollisionShape::Polygon {
+                    center,
+                    points: [ (-w, -h), (w, -h), (w, h), (-w, h) ].into_iter().map(rotate).collect()
                 }
-            }, CollisionShape::Rect { center, size } => {
-                match other {
-                    CollisionShape::Circle { center: other_center, radius: other_radius } => {
-                        let mut test = other_center.clone();
-                        let rect = Rect::new(
-                            center.0 - size.0 as i32 / 2,
-                            center.1 - size.1 as i32 / 2,
-                            size.0,
-                            size.1
-                        );
-                        if other_center.0 < rect.x {
-                            test.0 = rect.x;
-                        } else if other_center.0 > rect.x + rect.w {
-                            test.0 = rect.x + rect.w;
-                        }
-                        if other_center.1 < rect.y {
-                            test.1 = rect.y;
-                        } else if other_center.1 > rect.y + rect.h {
-                            test.1 = rect.y + rect.h;
-                        }
-                        let dist_lat = (other_center.0 - test.0, other_center.1 - test.1);
-                        let dist_sqrd = (dist_lat.0 * dist_lat.0) + (dist_lat.1 * dist_lat.1);
-                        dist_sqrd as u32 <= other_radius * other_radius
-                    }, CollisionShape::Rect { center: other_center, size: other_size } => {
-                        let r1 = Rect::new(
-                            center.0 - size.0 as i32 / 2,
-                            center.1 - size.1 as i32 / 2,
-                            size.0,
-                            size.1
-                        );
-                        let r2 = Rect::new(
-                            other_center.0 - other_size.1 as i32 / 2,
-                            other_center.1 - other_size.1 as i32 / 2,
-                            other_size.0,
-                            other_size.1
-                        );
-                        r1.x + r1.w >= r2.x
-                            && r1.x <= r2.x + r2.w
-                            && r1.y + r1.h >= r2.y
-                            && r1.y <= r2.y + r2.h
-                    }, CollisionShape::Polygon { center: other_center, points } => {
-                        for point in points.iter() {
-                            let transformed_point = (
-                                other_center.0 + point.0,
-                                other_center.1 + point.1
-                            );
-                            let (left, right) = (
-                                center.0 - size.0 as i32 / 2,
-                                center.0 + size.0 as i32 / 2
-                            );
-                            let (top, bottom) = (
-                                center.1 - size.1 as i32 / 2,
-                                center.1 + size.1 as i32 / 2
-                            );
-                            if transformed_point.0 >= left
-                                    && transformed_point.0 <= right
-                                    && transformed_point.1 >= top
-                                    && transformed_point.1 <= bottom {
-                                return true;
-                            }
-                        }
-                        false
-                    }
+            }, CollisionShape::Polygon { points, .. } => CollisionShape::Polygon {
+                center,
+                points: points.iter().copied().map(rotate).collect()
+            }
+        }
+    }
+
+    /// The same shape mirrored across a point horizontally and/or vertically, like a flipped sprite
+    pub fn flipped(&self, flip: (bool, bool), pivot: (f64, f64)) -> Self {
+        let mirror = |val: f64, pivot: f64, flip: bool| if flip { 2.0 * pivot - val } else { val };
+        let sign = (if flip.0 { -1.0 } else { 1.0 }, if flip.1 { -1.0 } else { 1.0 });
+        let mut shape = self.clone();
+        match shape {
+            CollisionShape::Circle { ref mut center, .. }
+                    | CollisionShape::Rect { ref mut center, .. } => {
+                *center = (mirror(center.0, pivot.0, flip.0), mirror(center.1, pivot.1, flip.1));
+            }, CollisionShape::Polygon { ref mut center, ref mut points } => {
+                *center = (mirror(center.0, pivot.0, flip.0), mirror(center.1, pivot.1, flip.1));
+                for point in points.iter_mut() {
+                    *point = (point.0 * sign.0, point.1 * sign.1);
                 }
-            }, CollisionShape::Polygon { center, points } => {
-                match other {
-                    CollisionShape::Circle { center: other_center, radius } => {
-                        for point in points.iter() {
-                            let dists = (
-                                center.0 + point.0 - other_center.0,
-                                center.1 + point.1 - other_center.1
-                            );
-                            let dist_sqrd = dists.0 * dists.0 + dists.1 + dists.1;
-                            if (dist_sqrd as u32) < radius * radius {
-                                return true;
-                            }
-                        }
-                        false
-                    }, CollisionShape::Rect { center: other_center, size } => {
-                        for point in points.iter() {
-                            let transformed_point = (
-                                center.0 + point.0,
-                                center.1 + point.1
-                            );
-                            let (left, right) = (
-                                other_center.0 - size.0 as i32 / 2,
-                                other_center.0 + size.0 as i32 / 2
-                            );
-                            let (top, bottom) = (
-                                other_center.1 - size.1 as i32 / 2,
-                                other_center.1 + size.1 as i32 / 2
-                            );
-                            if transformed_point.0 >= left
-                                    && transformed_point.0 <= right
-                                    && transformed_point.1 >= top
-                                    && transformed_point.1 <= bottom {
-                                return true;
-                            }
-                        }
-                        false
-                    }, CollisionShape::Polygon { center: other_center, points: other_points } => {
-                        // Iterate over each edge of the first polygon
-                        for i in 0..points.len() {
-                            let p1 = points[i];
-                            let p2 = points[(i + 1) % points.len()];
-
-                            // Calculate the normal of the edge
-                            let normal = ((p2.1 - p1.1), -(p2.0 - p1.0));
-
-                            // Project both polygons onto the normal
-                            let (min1, max1) = project_polygon(&points, &normal, &center);
-                            let (min2, max2) = project_polygon(
-                                &other_points, &normal, &other_center
-                            );
-
-                            // Check for overlap on the projected axis
-                            if max1 < min2 || max2 < min1 {
-                                return false;
-                            }
+            }
+        }
+        shape
+    }
+
+    /// True if the shapes overlap or touch
+    pub fn collides_with(&self, other: &CollisionShape) -> bool {
+        self.separation(other).is_some()
+    }
+
+    /// Find how two shapes overlap using the separating axis theorem, or None if they don't
+    pub fn contact(&self, other: &CollisionShape) -> Option<Contact> {
+        let (normal, depth) = self.separation(other)?;
+        let points = match (self.convex(), other.convex()) {
+            (Convex::Circle { center, radius }, _) => vec![
+                (center.0 + normal.0 * (radius - depth), center.1 + normal.1 * (radius - depth))
+            ], (_, Convex::Circle { center, radius }) => vec![
+                (center.0 - normal.0 * (radius - depth), center.1 - normal.1 * (radius - depth))
+            ], (Convex::Polygon { points }, Convex::Polygon { points: other_points }) => {
+                clip_contact_points(&points, &other_points, normal)
+            }
+        };
+        Some(Contact {
+            normal,
+            depth,
+            points
+        })
+    }
+
+    /// Where a ray starting at origin first enters the shape, if it does within max_dist. A ray
+    /// that starts inside hits right away, with a normal pointing back along it
+    pub fn raycast(&self, origin: (f64, f64), dir: (f64, f64), max_dist: f64) -> Option<Hit> {
+        let dir = normalize(dir)?;
+        let inside = Hit {
+            point: origin,
+            normal: (-dir.0, -dir.1),
+            dist: 0.0
+        };
+        let (dist, normal) = match self.convex() {
+            Convex::Circle { center, radius } => {
+                let to_origin = (origin.0 - center.0, origin.1 - center.1);
+                let b = dot(to_origin, dir);
+                let c = dot(to_origin, to_origin) - radius * radius;
+                if c <= 0.0 {
+                    return Some(inside);
+                }
+                let disc = b * b - c;
+                if disc < 0.0 || b > 0.0 {
+                    return None;
+                }
+                let dist = -b - disc.sqrt();
+                let point = (origin.0 + dir.0 * dist, origin.1 + dir.1 * dist);
+                let normal = normalize((point.0 - center.0, point.1 - center.1))
+                    .unwrap_or(inside.normal);
+                (dist, normal)
+            }, shape @ Convex::Polygon { .. } => {
+                // A convex polygon is where all its edges' slabs overlap, so clip the ray to them
+                let (mut enter, mut exit) = (0.0, max_dist);
+                let mut normal = None;
+                for axis in shape.edge_normals() {
+                    let (min, max) = shape.project(axis);
+                    let (start, speed) = (dot(origin, axis), dot(dir, axis));
+                    if speed == 0.0 {
+                        if start < min || start > max {
+                            return None;
                         }
-                        true
+                        continue;
+                    }
+                    let (t1, t2) = ((min - start) / speed, (max - start) / speed);
+                    if t1.min(t2) > enter {
+                        enter = t1.min(t2);
+                        normal = Some(if speed > 0.0 { (-axis.0, -axis.1) } else { axis });
                     }
+                    exit = exit.min(t1.max(t2));
+                    if enter > exit {
+                        return None;
+                    }
+                }
+                let Some(normal) = normal else {
+                    return if shape.is_empty() { None } else { Some(inside) };
+                };
+                (enter, normal)
+            }
+        };
+        if dist > max_dist {
+            return None;
+        }
+        Some(Hit {
+            point: (origin.0 + dir.0 * dist, origin.1 + dir.1 * dist),
+            normal,
+            dist
+        })
+    }
+
+    /// Slide this shape along motion and find where it first touches other. The normal points out
+    /// of other, and a shape that starts out touching it hits right away.
+    ///
+    /// The sweep is exact instead of stepped, so even zero-width shapes can't be skipped over
+    pub fn cast(&self, motion: (f64, f64), other: &CollisionShape) -> Option<Hit> {
+        let len = (motion.0 * motion.0 + motion.1 * motion.1).sqrt();
+        let moved_by = |dist: f64| {
+            let t = if len > 0.0 { dist / len } else { 0.0 };
+            self.moved((motion.0 * t, motion.1 * t))
+        };
+        if let Some(contact) = self.contact(other) {
+            return Some(Hit {
+                point: contact.points[0],
+                normal: (-contact.normal.0, -contact.normal.1),
+                dist: 0.0
+            });
+        }
+        let (start, end) = (self.aabb(), self.moved(motion).aabb());
+        let swept = Aabb {
+            min: (start.min.0.min(end.min.0), start.min.1.min(end.min.1)),
+            max: (start.max.0.max(end.max.0), start.max.1.max(end.max.1))
+        };
+        if len == 0.0 || !swept.overlaps(&other.aabb()) {
+            return None;
+        }
+
+        // The shapes touch once the motion reaches the Minkowski difference of other and self
+        // (every point of other minus every point of self), which is the hull of the vertex
+        // differences rounded by both radii. So it's a ray cast into that
+        let (a_points, a_radius) = self.convex().core();
+        let (b_points, b_radius) = other.convex().core();
+        if a_points.is_empty() || b_points.is_empty() {
+            return None;
+        }
+        let diff = hull(
+            b_points.iter()
+                .flat_map(|b| a_points.iter().map(move |a| (b.0 - a.0, b.1 - a.1)))
+                .collect()
+        );
+        let hit = raycast_rounded(&diff, a_radius + b_radius, motion, len)?;
+
+        // Touching exactly can round either way, so nudge in a bit to find the contact point
+        let moved = moved_by(hit.dist);
+        let point = moved.contact(other)
+            .or_else(|| moved_by(hit.dist + CONTACT_EPSILON).contact(other))
+            .map_or_else(|| moved.convex().support(hit.normal), |contact| contact.points[0]);
+        Some(Hit {
+            point,
+            normal: hit.normal,
+            dist: hit.dist
+        })
+    }
+
+    /// The axis of least overlap (pointing from self to other) and how much they overlap on it
+    fn separation(&self, other: &CollisionShape) -> Option<((f64, f64), f64)> {
+        if !self.aabb().overlaps(&other.aabb()) {
+            return None;
+        }
+        let (a, b) = (self.convex(), other.convex());
+        if a.is_empty() || b.is_empty() {
+            return None;
+        }
+        let (a_center, b_center) = (a.centroid(), b.centroid());
+
+        // Every axis that could separate the shapes: polygon edge normals, plus the line from a
+        // circle's center to the closest point of the other shape
+        let mut axes = a.edge_normals();
+        axes.append(&mut b.edge_normals());
+        for (shape, other_shape) in [ (&a, &b), (&b, &a) ] {
+            if let Convex::Circle { center, .. } = shape {
+                let closest = other_shape.closest_feature(*center);
+                if let Some(axis) = normalize((closest.0 - center.0, closest.1 - center.1)) {
+                    axes.push(axis);
                 }
             }
         }
+        if axes.is_empty() {
+            // Only circles and points sharing a center end up with no axes. Any direction pulls
+            // them apart, so push them up by both radii (points just touch)
+            if a_center != b_center {
+                return None;
+            }
+            let radius = |shape: &Convex| match shape {
+                Convex::Circle { radius, .. } => *radius,
+                Convex::Polygon { .. } => 0.0
+            };
+            return Some(((0.0, -1.0), radius(&a) + radius(&b)));
+        }
+
+        let mut best: Option<((f64, f64), f64)> = None;
+        for axis in axes {
+            let (a_min, a_max) = a.project(axis);
+            let (b_min, b_max) = b.project(axis);
+            // Point the normal from a to b: towards whichever side b sticks out of a the least
+            let (fwd, back) = (a_max - b_min, b_max - a_min);
+            let depth = fwd.min(back);
+            if depth < 0.0 {
+                return None;
+            }
+            if !best.is_some_and(|(_, best_depth)| best_depth <= depth) {
+                let to_b = if fwd == back {
+                    dot((b_center.0 - a_center.0, b_center.1 - a_center.1), axis)
+                } else {
+                    back - fwd
+                };
+                let axis = if to_b < 0.0 { (-axis.0, -axis.1) } else { axis };
+                best = Some((axis, depth));
+            }
+        }
+        best
+    }
+
+    /// Put every shape in terms of room-space points
+    fn convex(&self) -> Convex {
+        match self {
+            CollisionShape::Circle { center, radius } => Convex::Circle {
+                center: *center,
+                radius: *radius
+            }, CollisionShape::Rect { center, size } => {
+                let (x, y) = *center;
+                let (w, h) = (size.0 / 2.0, size.1 / 2.0);
+                Convex::from_points(vec![
+                    (x - w, y - h), (x + w, y - h), (x + w, y + h), (x - w, y + h)
+                ])
+            }, CollisionShape::Polygon { center, points } => Convex::from_points(
+                points.iter().map(|point| (center.0 + point.0, center.1 + point.1)).collect()
+            )
+        }
     }
 }
 
-fn project_polygon(points: &[(i32, i32)], axis: &(i32, i32), center: &(i32, i32)) -> (i32, i32) {
-    let mut min = i32::MAX;
-    let mut max = i32::MIN;
-    for point in points.iter() {
-        let proj_point = (point.0 + center.0, point.1 + center.1);
-        let dot_prod = proj_point.0 * axis.0 + proj_point.1 * axis.1;
-        if dot_prod < min {
-            min = dot_prod;
+/// Internal version of a shape that the separating axis math works on
+enum Convex {
+    Circle {
+        center: (f64, f64),
+        radius: f64
+    }, Polygon {
+        points: Vec<(f64, f64)>
+    }
+}
+
+impl Convex {
+    /// Build a polygon, turning a polygon that's really a single point into a point-sized circle
+    fn from_points(mut points: Vec<(f64, f64)>) -> Self {
+        points.dedup();
+        while points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() == 1 {
+            Convex::Circle {
+                center: points[0],
+                radius: 0.0
+            }
+        } else {
+            Convex::Polygon {
+                points
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Convex::Polygon { points } if points.is_empty())
+    }
+
+    fn centroid(&self) -> (f64, f64) {
+        match self {
+            Convex::Circle { center, .. } => *center,
+            Convex::Polygon { points } => {
+                let sum = points.iter()
+                    .fold((0.0, 0.0), |sum, point| (sum.0 + point.0, sum.1 + point.1));
+                (sum.0 / points.len() as f64, sum.1 / points.len() as f64)
+            }
         }
-        if dot_prod > max {
-            max = dot_prod;
+    }
+
+    /// Unit normals of every edge. A line segment also needs its own direction as an axis
+    fn edge_normals(&self) -> Vec<(f64, f64)> {
+        let Convex::Polygon { points } = self else {
+            return vec![];
+        };
+        let mut normals = Vec::new();
+        for i in 0..points.len() {
+            let (p1, p2) = (points[i], points[(i + 1) % points.len()]);
+            if let Some(edge) = normalize((p2.0 - p1.0, p2.1 - p1.1)) {
+                normals.push((edge.1, -edge.0));
+                if points.len() == 2 {
+                    normals.push(edge);
+                }
+            }
+        }
+        normals
+    }
+
+    /// The vertex (or circle center) closest to a point
+    fn closest_feature(&self, pos: (f64, f64)) -> (f64, f64) {
+        match self {
+            Convex::Circle { center, .. } => *center,
+            Convex::Polygon { points } => *points.iter()
+                .min_by(|a, b| dist_sqrd(**a, pos).total_cmp(&dist_sqrd(**b, pos)))
+                .unwrap_or(&pos)
         }
     }
-    (min, max)
+
+    /// The points and radius that make up the shape: a circle is its center rounded by its radius,
+    /// and a polygon is its points rounded by nothing
+    fn core(self) -> (Vec<(f64, f64)>, f64) {
+        match self {
+            Convex::Circle { center, radius } => (vec![ center ], radius),
+            Convex::Polygon { points } => (points, 0.0)
+        }
+    }
+
+    /// The point furthest along dir
+    fn support(&self, dir: (f64, f64)) -> (f64, f64) {
+        match self {
+            Convex::Circle { center, radius } => {
+                let dir = normalize(dir).unwrap_or((0.0, 0.0));
+                (center.0 + dir.0 * radius, center.1 + dir.1 * radius)
+            }, Convex::Polygon { points } => support(points, dir)
+        }
+    }
+
+    fn project(&self, axis: (f64, f64)) -> (f64, f64) {
+        match self {
+            Convex::Circle { center, radius } => {
+                let proj = dot(*center, axis);
+                (proj - radius, proj + radius)
+            }, Convex::Polygon { points } => points.iter()
+                .map(|point| dot(*point, axis))
+                .fold((f64::MAX, f64::MIN), |(min, max), proj| (min.min(proj), max.max(proj)))
+        }
+    }
+}
+
+/// An edge as (start, end, the vertex that was furthest along the direction it was picked for)
+type Edge = ((f64, f64), (f64, f64), (f64, f64));
+
+/// Polygon vs polygon contact points: clip the edge of b that hit a against the edge of a it hit
+/// (or the other way around, whichever edge faces the normal better)
+fn clip_contact_points(
+        a: &[(f64, f64)], b: &[(f64, f64)], normal: (f64, f64)) -> Vec<(f64, f64)> {
+    let neg_normal = (-normal.0, -normal.1);
+    let a_edge = best_edge(a, normal);
+    let b_edge = best_edge(b, neg_normal);
+    let (ref_edge, inc_edge, ref_dir) = if edge_alignment(&a_edge, normal)
+            <= edge_alignment(&b_edge, normal) {
+        (a_edge, b_edge, normal)
+    } else {
+        (b_edge, a_edge, neg_normal)
+    };
+    let deepest = support(b, neg_normal);
+    let Some(ref_vec) = normalize((ref_edge.1.0 - ref_edge.0.0, ref_edge.1.1 - ref_edge.0.1)) else {
+        return vec![ deepest ];
+    };
+
+    // Cut off the parts of the incident edge past either end of the reference edge
+    let clipped = clip(inc_edge.0, inc_edge.1, ref_vec, dot(ref_vec, ref_edge.0));
+    if clipped.len() < 2 {
+        return vec![ deepest ];
+    }
+    let neg_ref_vec = (-ref_vec.0, -ref_vec.1);
+    let clipped = clip(clipped[0], clipped[1], neg_ref_vec, dot(neg_ref_vec, ref_edge.1));
+    if clipped.len() < 2 {
+        return vec![ deepest ];
+    }
+
+    // Only keep points that are actually behind the reference edge
+    let ref_normal = (ref_vec.1, -ref_vec.0);
+    let ref_normal = if dot(ref_normal, ref_dir) < 0.0 {
+        (-ref_normal.0, -ref_normal.1)
+    } else {
+        ref_normal
+    };
+    let face = dot(ref_normal, ref_edge.2);
+    let points = clipped.into_iter()
+        .filter(|point| dot(ref_normal, *point) <= face + CONTACT_EPSILON)
+        .collect::<Vec<_>>();
+    if points.is_empty() {
+        vec![ deepest ]
+    } else {
+        points
+    }
+}
+
+/// The edge next to the vertex furthest along dir that's closest to facing dir
+fn best_edge(points: &[(f64, f64)], dir: (f64, f64)) -> Edge {
+    let idx = (0..points.len())
+        .max_by(|a, b| dot(points[*a], dir).total_cmp(&dot(points[*b], dir)))
+        .unwrap_or(0);
+    let vert = points[idx];
+    let prev = points[(idx + points.len() - 1) % points.len()];
+    let next = points[(idx + 1) % points.len()];
+    let to_prev = normalize((prev.0 - vert.0, prev.1 - vert.1)).unwrap_or((0.0, 0.0));
+    let to_next = normalize((next.0 - vert.0, next.1 - vert.1)).unwrap_or((0.0, 0.0));
+    if dot(to_prev, dir).abs() <= dot(to_next, dir).abs() {
+        (prev, vert, vert)
+    } else {
+        (vert, next, vert)
+    }
 }
 
+/// How far an edge is from being perpendicular to a direction (0 means it's facing it exactly)
+fn edge_alignment(edge: &Edge, dir: (f64, f64)) -> f64 {
+    normalize((edge.1.0 - edge.0.0, edge.1.1 - edge.0.1))
+        .map_or(f64::MAX, |edge_dir| dot(edge_dir, dir).abs())
+}
+
+/// The vertex furthest along dir
+fn support(points: &[(f64, f64)], dir: (f64, f64)) -> (f64, f64) {
+    *points.iter()
+        .max_by(|a, b| dot(**a, dir).total_cmp(&dot(**b, dir)))
+        .unwrap_or(&(0.0, 0.0))
+}
+
+/// Keep the part of a line segment where dot(dir, point) >= offset
+fn clip(p1: (f64, f64), p2: (f64, f64), dir: (f64, f64), offset: f64) -> Vec<(f64, f64)> {
+    let (d1, d2) = (dot(dir, p1) - offset, dot(dir, p2) - offset);
+    let mut points = Vec::new();
+    if d1 >= 0.0 {
+        points.push(p1);
+    }
+    if d2 >= 0.0 {
+        points.push(p2);
+    }
+    if d1 * d2 < 0.0 {
+        let t = d1 / (d1 - d2);
+        points.push((p1.0 + (p2.0 - p1.0) * t, p1.1 + (p2.1 - p1.1) * t));
+    }
+    points
+}
+
+/// The convex hull of some points, in order around the outside and without any points in the
+/// middle of an edge. Points all on one line give back just the two ends
+fn hull(mut points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    points.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+
+    // Build the bottom then the top, dropping any turn the wrong way
+    let mut hull: Vec<(f64, f64)> = Vec::new();
+    for pass in [ points.clone(), points.into_iter().rev().collect() ] {
+        let base = hull.len();
+        for point in pass {
+            while hull.len() >= base + 2
+                    && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+/// Cast a ray from the origin into a convex polygon (or segment or point) rounded by a radius
+fn raycast_rounded(
+        points: &[(f64, f64)], radius: f64, dir: (f64, f64), max_dist: f64) -> Option<Hit> {
+    if radius <= 0.0 {
+        let points = points.to_vec();
+        return CollisionShape::Polygon { center: (0.0, 0.0), points }
+            .raycast((0.0, 0.0), dir, max_dist);
+    }
+
+    // The rounded shape is a circle on every corner plus every edge pushed out by the radius
+    let centroid = Convex::Polygon { points: points.to_vec() }.centroid();
+    let corners = points.iter().map(|point| CollisionShape::Circle { center: *point, radius });
+    let edges = (0..points.len()).filter(|_| points.len() > 1).filter_map(|i| {
+        let (p1, p2) = (points[i], points[(i + 1) % points.len()]);
+        let edge = normalize((p2.0 - p1.0, p2.1 - p1.1))?;
+        let mid = ((p1.0 + p2.0) / 2.0, (p1.1 + p2.1) / 2.0);
+        let out = if dot((edge.1, -edge.0), (mid.0 - centroid.0, mid.1 - centroid.1)) < 0.0 {
+            (-edge.1, edge.0)
+        } else {
+            (edge.1, -edge.0)
+        };
+        let push = (out.0 * radius, out.1 * radius);
+        Some(CollisionShape::Polygon {
+            center: (0.0, 0.0),
+            points: vec![ p1, p2, (p2.0 + push.0, p2.1 + push.1), (p1.0 + push.0, p1.1 + push.1) ]
+        })
+    });
+    corners.chain(edges)
+        .filter_map(|piece| piece.raycast((0.0, 0.0), dir, max_dist))
+        .min_by(|a, b| a.dist.total_cmp(&b.dist))
+}
+
+fn dot(a: (f64, f64), b: (f64, f64)) -> f64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn dist_sqrd(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
+
+fn normalize(vec: (f64, f64)) -> Option<(f64, f64)> {
+    let len = (vec.0 * vec.0 + vec.1 * vec.1).sqrt();
+    if len > 0.0 { Some((vec.0 / len, vec.1 / len)) } else { None }
+}
+
+/// An axis-aligned bounding box. Both min and max are inside the box, so boxes that only touch
+/// still overlap, same as with collides_with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Aabb {
+    pub min: (i32, i32),
+    pub max: (i32, i32)
+}
+
+impl Aabb {
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.0 <= other.max.0
+            && other.min.0 <= self.max.0
+            && self.min.1 <= other.max.1
+            && other.min.1 <= self.max.1
+    }
+}
+
+/// A uniform grid of buckets for finding shapes that might collide without checking every pair
+///
+/// Pick a cell size around the size of a typical object. Too small and big objects land in lots of
+/// cells, too big and every cell has lots of objects
+pub struct SpatialHash {
+    cell_size: i32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    large: Vec<usize>,
+    count: usize
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: u32) -> Self {
+        Self {
+            cell_size: cell_size.max(1) as i32,
+            cells: HashMap::new(),
+            large: Vec::new(),
+            count: 0
+        }
+    }
+
+    /// Add a shape's box under an index of your choosing (e.g. where it is in a list of objects)
+    pub fn insert(&mut self, idx: usize, aabb: &Aabb) {
+        self.count = self.count.max(idx + 1);
+        let min = (aabb.min.0.div_euclid(self.cell_size), aabb.min.1.div_euclid(self.cell_size));
+        let max = (aabb.max.0.div_euclid(self.cell_size), aabb.max.1.div_euclid(self.cell_size));
+        let cells = (max.0 as i64 - min.0 as i64 + 1) * (max.1 as i64 - min.1 as i64 + 1);
+        if cells > MAX_CELLS_PER_SHAPE {
+            self.large.push(idx);
+            return;
+        }
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                self.cells.entry((x, y)).or_default().push(idx);
+            }
+        }
+    }
+
+    /// Every pair of indices (smallest first) that share a cell. Their boxes might still not
+    /// overlap, so check that before anything more expensive
+    pub fn pairs(&self) -> HashSet<(usize, usize)> {
+        let mut pairs = HashSet::new();
+        for idxs in self.cells.values() {
+            for (i, a) in idxs.iter().enumerate() {
+                for b in idxs[i + 1..].iter() {
+                    pairs.insert(((*a).min(*b), (*a).max(*b)));
+                }
+            }
+        }
+        for a in self.large.iter() {
+            for b in 0..self.count {
+                if *a != b {
+                    pairs.insert(((*a).min(b), (*a).max(b)));
+                }
+            }
+        }
+        pairs
+    }
+}
@@ -0,0 +1,309 @@
+//! Open and close game controllers as they're plugged in and keep track of their state
+
+use std::{
+    collections::{
+        hash_map::Entry,
+        HashMap, HashSet
+    }, os::raw::c_char
+};
+use sdl2::{
+    controller::{
+        Axis, Button, GameController
+    }, event::Event,
+    sys,
+    GameControllerSubsystem, Sdl
+};
+use crate::input::Input;
+
+/// Default radial dead zone for sticks, as a fraction of how far they can be pushed
+pub const DEF_STICK_DEAD_ZONE: f64 = 0.15;
+
+/// Default dead zone for triggers, as a fraction of how far they can be pulled
+pub const DEF_TRIGGER_DEAD_ZONE: f64 = 0.05;
+
+/// How far an axis must be pushed before an input binding to it counts as held
+const AXIS_PRESS_THRESHOLD: f64 = 0.5;
+
+/// Every axis and button a virtual controller has, in the order SDL numbers them
+const VIRTUAL_AXES: [Axis; 6] = [
+    Axis::LeftX, Axis::LeftY, Axis::RightX, Axis::RightY, Axis::TriggerLeft, Axis::TriggerRight
+];
+const VIRTUAL_BUTTONS: [Button; 15] = [
+    Button::A, Button::B, Button::X, Button::Y, Button::Back, Button::Guide, Button::Start,
+    Button::LeftStick, Button::RightStick, Button::LeftShoulder, Button::RightShoulder,
+    Button::DPadUp, Button::DPadDown, Button::DPadLeft, Button::DPadRight
+];
+const VIRTUAL_MAPPING: &str = "a:b0,b:b1,x:b2,y:b3,back:b4,guide:b5,start:b6,leftstick:b7,\
+    rightstick:b8,leftshoulder:b9,rightshoulder:b10,dpup:b11,dpdown:b12,dpleft:b13,dpright:b14,\
+    leftx:a0,lefty:a1,rightx:a2,righty:a3,lefttrigger:a4,righttrigger:a5,";
+
+/// The state of one connected controller. Get these from Input::controller by player index.
+///
+/// Besides buttons, each direction of an axis (e.g. (Axis::LeftX, false) for pushing the left
+/// stick left) counts as held while it's pushed at least halfway, for Input's axis bindings
+#[derive(Clone, Debug)]
+pub struct Controller {
+    pub instance_id: u32,
+    pub name: String,
+    pub stick_dead_zone: f64,
+    pub trigger_dead_zone: f64,
+    held: HashSet<Button>,
+    pressed: HashSet<Button>,
+    released: HashSet<Button>,
+    axes: HashMap<Axis, f64>,
+    held_dirs: HashSet<(Axis, bool)>,
+    pressed_dirs: HashSet<(Axis, bool)>,
+    released_dirs: HashSet<(Axis, bool)>
+}
+
+impl Controller {
+    pub fn new(instance_id: u32, name: &str) -> Self {
+        Self {
+            instance_id,
+            name: name.to_string(),
+            stick_dead_zone: DEF_STICK_DEAD_ZONE,
+            trigger_dead_zone: DEF_TRIGGER_DEAD_ZONE,
+            held: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            axes: HashMap::new(),
+            held_dirs: HashSet::new(),
+            pressed_dirs: HashSet::new(),
+            released_dirs: HashSet::new()
+        }
+    }
+
+    pub fn is_held(&self, button: Button) -> bool {
+        self.held.contains(&button)
+    }
+
+    pub fn just_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn just_released(&self, button: Button) -> bool {
+        self.released.contains(&button) && !self.held.contains(&button)
+    }
+
+    /// Raw axis value from -1 to 1 (0 to 1 for triggers) with no dead zone applied
+    pub fn axis(&self, axis: Axis) -> f64 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    /// Left stick from -1 to 1 on each axis, with the dead zone removed. Down is positive y
+    pub fn left_stick(&self) -> (f64, f64) {
+        self.stick(Axis::LeftX, Axis::LeftY)
+    }
+
+    pub fn right_stick(&self) -> (f64, f64) {
+        self.stick(Axis::RightX, Axis::RightY)
+    }
+
+    /// Left trigger from 0 to 1, with the dead zone removed
+    pub fn left_trigger(&self) -> f64 {
+        apply_dead_zone(self.axis(Axis::TriggerLeft), self.trigger_dead_zone)
+    }
+
+    pub fn right_trigger(&self) -> f64 {
+        apply_dead_zone(self.axis(Axis::TriggerRight), self.trigger_dead_zone)
+    }
+
+    /// How far an axis is pushed in one direction from 0 to 1, with the dead zone removed
+    pub fn axis_dir(&self, axis: Axis, positive: bool) -> f64 {
+        let val = self.axis(axis);
+        let val = if positive { val.max(0.0) } else { (-val).max(0.0) };
+        match axis {
+            Axis::TriggerLeft | Axis::TriggerRight => apply_dead_zone(val, self.trigger_dead_zone),
+            _ => apply_dead_zone(val, self.stick_dead_zone)
+        }
+    }
+
+    pub(crate) fn is_dir_held(&self, axis: Axis, positive: bool) -> bool {
+        self.held_dirs.contains(&(axis, positive))
+    }
+
+    pub(crate) fn dir_just_pressed(&self, axis: Axis, positive: bool) -> bool {
+        self.pressed_dirs.contains(&(axis, positive))
+    }
+
+    pub(crate) fn dir_just_released(&self, axis: Axis, positive: bool) -> bool {
+        self.released_dirs.contains(&(axis, positive)) && !self.is_dir_held(axis, positive)
+    }
+
+    fn stick(&self, x_axis: Axis, y_axis: Axis) -> (f64, f64) {
+        let (x, y) = (self.axis(x_axis), self.axis(y_axis));
+        let len = (x * x + y * y).sqrt();
+        if len <= self.stick_dead_zone {
+            return (0.0, 0.0);
+        }
+        let scale = apply_dead_zone(len.min(1.0), self.stick_dead_zone) / len;
+        (x * scale, y * scale)
+    }
+
+    pub(crate) fn handle_sdl_event(&mut self, event: &Event) {
+        match event {
+            Event::ControllerButtonDown { button, .. } => {
+                self.held.insert(*button);
+                self.pressed.insert(*button);
+            }, Event::ControllerButtonUp { button, .. } => {
+                self.held.remove(button);
+                self.released.insert(*button);
+            }, Event::ControllerAxisMotion { axis, value, .. } => {
+                let val = normalize_axis(*value);
+                self.axes.insert(*axis, val);
+                for positive in [ true, false ] {
+                    let dir = (*axis, positive);
+                    let pushed = if positive {
+                        val >= AXIS_PRESS_THRESHOLD
+                    } else {
+                        val <= -AXIS_PRESS_THRESHOLD
+                    };
+                    if pushed && self.held_dirs.insert(dir) {
+                        self.pressed_dirs.insert(dir);
+                    } else if !pushed && self.held_dirs.remove(&dir) {
+                        self.released_dirs.insert(dir);
+                    }
+                }
+            }, _ => {}
+        }
+    }
+
+    pub(crate) fn end_update(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.pressed_dirs.clear();
+        self.released_dirs.clear();
+    }
+}
+
+/// Convert SDL's raw axis value to -1 to 1
+pub(crate) fn normalize_axis(value: i16) -> f64 {
+    (value as f64 / i16::MAX as f64).clamp(-1.0, 1.0)
+}
+
+/// Remove the dead zone from a value and stretch what's left back out to the full range
+pub(crate) fn apply_dead_zone(value: f64, dead_zone: f64) -> f64 {
+    if value.abs() <= dead_zone {
+        0.0
+    } else {
+        value.signum() * ((value.abs() - dead_zone) / (1.0 - dead_zone)).min(1.0)
+    }
+}
+
+/// Owns SDL's game controller subsystem and the controllers that are currently open. The app
+/// makes one of these for you, but you can make your own to test with virtual controllers
+pub struct Controllers {
+    subsys: GameControllerSubsystem,
+    open: HashMap<u32, GameController>
+}
+
+impl Controllers {
+    pub fn new(ctx: &Sdl) -> Result<Self, String> {
+        Ok(Self {
+            subsys: ctx.game_controller()?,
+            open: HashMap::new()
+        })
+    }
+
+    /// Add SDL controller mappings, like the lines of gamecontrollerdb.txt. Blank lines and
+    /// comments starting with # are skipped
+    pub fn add_mappings(&self, mappings: &str) -> Result<(), String> {
+        for line in mappings.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            self.subsys.add_mapping(line).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Open controllers as they're connected and close them when they're removed, letting Input
+    /// know so it can hand out player indices
+    pub fn handle_sdl_event(&mut self, event: &Event, input: &mut Input) -> Result<(), String> {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                let ctlr = self.subsys.open(*which).map_err(|e| e.to_string())?;
+                // SDL can announce controllers that were already open at startup twice
+                if let Entry::Vacant(entry) = self.open.entry(ctlr.instance_id()) {
+                    input.connect_controller(ctlr.instance_id(), &ctlr.name());
+                    entry.insert(ctlr);
+                }
+            }, Event::ControllerDeviceRemoved { which, .. } => {
+                self.open.remove(which);
+                input.disconnect_controller(*which);
+            }, _ => {}
+        }
+        Ok(())
+    }
+
+    /// Plug in a fake controller. SDL sends the same events for it as for a real one, so it's a
+    /// way to test controller handling without any hardware
+    pub fn attach_virtual(&self) -> Result<VirtualController, String> {
+        let index = unsafe {
+            sys::SDL_JoystickAttachVirtual(
+                sys::SDL_JoystickType::SDL_JOYSTICK_TYPE_GAMECONTROLLER,
+                VIRTUAL_AXES.len() as i32, VIRTUAL_BUTTONS.len() as i32, 0
+            )
+        };
+        if index < 0 {
+            return Err(sdl2::get_error());
+        }
+        let guid = unsafe { sys::SDL_JoystickGetDeviceGUID(index) };
+        let mut guid_str = [0 as c_char; 33];
+        unsafe {
+            sys::SDL_JoystickGetGUIDString(guid, guid_str.as_mut_ptr(), guid_str.len() as i32);
+        }
+        let guid_str = guid_str.iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8 as char)
+            .collect::<String>();
+        let mapping = format!("{},Y-Craft Virtual Controller,{}", guid_str, VIRTUAL_MAPPING);
+        self.subsys.add_mapping(&mapping).map_err(|e| e.to_string())?;
+        let joystick = unsafe { sys::SDL_JoystickOpen(index) };
+        if joystick.is_null() {
+            return Err(sdl2::get_error());
+        }
+        Ok(VirtualController {
+            index,
+            joystick
+        })
+    }
+}
+
+/// A fake controller made by Controllers::attach_virtual. It's detached when dropped
+pub struct VirtualController {
+    index: i32,
+    joystick: *mut sys::SDL_Joystick
+}
+
+impl VirtualController {
+    /// Press or release a button. Like a real controller, the change shows up as an event the next
+    /// time SDL's events are polled
+    pub fn set_button(&self, button: Button, held: bool) -> Result<(), String> {
+        let idx = VIRTUAL_BUTTONS.iter().position(|other| *other == button)
+            .ok_or(format!("Virtual controllers don't have a {:?} button", button))?;
+        let res = unsafe {
+            sys::SDL_JoystickSetVirtualButton(self.joystick, idx as i32, held as u8)
+        };
+        if res < 0 { Err(sdl2::get_error()) } else { Ok(()) }
+    }
+
+    /// Move an axis. Values are the same as SDL's (-32768 to 32767)
+    pub fn set_axis(&self, axis: Axis, value: i16) -> Result<(), String> {
+        let idx = VIRTUAL_AXES.iter().position(|other| *other == axis)
+            .ok_or(format!("Virtual controllers don't have a {:?} axis", axis))?;
+        let res = unsafe {
+            sys::SDL_JoystickSetVirtualAxis(self.joystick, idx as i32, value)
+        };
+        if res < 0 { Err(sdl2::get_error()) } else { Ok(()) }
+    }
+}
+
+impl Drop for VirtualController {
+    fn drop(&mut self) {
+        unsafe {
+            sys::SDL_JoystickClose(self.joystick);
+            sys::SDL_JoystickDetachVirtual(self.index);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Build game objects from a kind name and a few properties, so levels made outside of Rust code
+//! can be turned into rooms
+
+use std::{
+    collections::HashMap,
+    path::Path
+};
+use sdl2::pixels::Color;
+use serde_json::Value;
+use crate::{
+    collision::CollisionShape,
+    obj::GameObject,
+    IndexRestriction
+};
+
+/// A value set on an object in a level editor or room file
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Color(Color),
+    Point((f64, f64)),
+    List(Vec<Property>),
+    Map(HashMap<String, Property>)
+}
+
+impl Property {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(val) => Some(*val),
+            _ => None
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(val) => Some(*val),
+            _ => None
+        }
+    }
+
+    /// Ints count too, since editors don't always keep track of which is which
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Float(val) => Some(*val),
+            Self::Int(val) => Some(*val as f64),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(val) => Some(val),
+            _ => None
+        }
+    }
+
+    pub fn as_color(&self) -> Option<Color> {
+        match self {
+            Self::Color(val) => Some(*val),
+            _ => None
+        }
+    }
+
+    pub fn as_point(&self) -> Option<(f64, f64)> {
+        match self {
+            Self::Point(val) => Some(*val),
+            _ => None
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Property]> {
+        match self {
+            Self::List(val) => Some(val),
+            _ => None
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&HashMap<String, Property>> {
+        match self {
+            Self::Map(val) => Some(val),
+            _ => None
+        }
+    }
+
+    /// Take a plain JSON value as is. Strings that look like colors stay strings, since there's no
+    /// way to tell. Nulls have no property
+    pub(crate) fn from_json(val: &Value) -> Option<Self> {
+        Some(match val {
+            Value::Null => return None,
+            Value::Bool(val) => Self::Bool(*val),
+            Value::Number(num) => match num.as_i64() {
+                Some(val) => Self::Int(val),
+                None => Self::Float(num.as_f64()?)
+            }, Value::String(val) => Self::String(val.clone()),
+            Value::Array(vals) => Self::List(vals.iter().filter_map(Self::from_json).collect()),
+            Value::Object(vals) => Self::Map(
+                vals.iter()
+                    .filter_map(|(key, val)| Some((key.clone(), Self::from_json(val)?)))
+                    .collect()
+            )
+        })
+    }
+}
+
+/// Read a "#RRGGBB" or "#AARRGGBB" color, the way Tiled writes them. The # is optional
+pub(crate) fn parse_color(color: &str) -> Option<Color> {
+    let hex = color.trim().trim_start_matches('#');
+    let val = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some(Color::RGB((val >> 16) as u8, (val >> 8) as u8, val as u8)),
+        8 => Some(Color::RGBA((val >> 16) as u8, (val >> 8) as u8, val as u8, (val >> 24) as u8)),
+        _ => None
+    }
+}
+
+/// Find something registered for a path, settling for a matching file name if the whole path isn't
+/// there, since paths in a level file are relative to wherever it was saved
+pub(crate) fn lookup<'a, T>(map: &'a HashMap<String, T>, source: &str) -> Option<&'a T> {
+    map.get(source).or_else(|| {
+        let file = Path::new(source).file_name()?;
+        map.iter().find(|(key, _)| Path::new(key).file_name() == Some(file)).map(|(_, val)| val)
+    })
+}
+
+/// Everything known about an object before it's built
+///
+/// - kind: String (which constructor in the factory builds it)
+/// - name: String
+/// - pos: (f64, f64) (where it goes in the room, the top left of its box in the editor)
+/// - size: (f64, f64) (size of its box in the editor, 0 if it didn't have one)
+/// - angle: f64 (rotation in degrees, clockwise)
+/// - depth: i32 (where it was in the draw order. Pass it to with_depth to keep that)
+/// - shape: Option<CollisionShape> (its outline in the editor relative to pos, e.g. to use as a
+///   collider)
+/// - props: HashMap<String, Property> (custom values set on it)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectDef {
+    pub kind: String,
+    pub name: String,
+    pub pos: (f64, f64),
+    pub size: (f64, f64),
+    pub angle: f64,
+    pub depth: i32,
+    pub shape: Option<CollisionShape>,
+    pub props: HashMap<String, Property>
+}
+
+impl ObjectDef {
+    pub fn new(kind: &str, pos: (f64, f64)) -> Self {
+        Self {
+            kind: kind.to_string(),
+            name: String::new(),
+            pos,
+            size: (0.0, 0.0),
+            angle: 0.0,
+            depth: 0,
+            shape: None,
+            props: HashMap::new()
+        }
+    }
+
+    pub fn prop(&self, name: &str) -> Option<&Property> {
+        self.props.get(name)
+    }
+}
+
+/// An object built from its definition, or what's wrong with it
+pub type BuildResult<Img, Snd, Fnt, Spr, Rm, Data> = Result<
+    GameObject<Img, Snd, Fnt, Spr, Rm, Data>, String
+>;
+
+/// Builds an object from its definition, or says what's wrong with it
+pub type ObjectConstructor<Img, Snd, Fnt, Spr, Rm, Data> = Box<
+    dyn Fn(&ObjectDef) -> BuildResult<Img, Snd, Fnt, Spr, Rm, Data>
+>;
+
+/// Constructors for every kind of object a level can place, looked up by kind name
+pub struct ObjectFactory<Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    pub ctors: HashMap<String, ObjectConstructor<Img, Snd, Fnt, Spr, Rm, Data>>
+}
+
+impl<Img, Snd, Fnt, Spr, Rm, Data> Default for ObjectFactory<Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Img, Snd, Fnt, Spr, Rm, Data> ObjectFactory<Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    pub fn new() -> Self {
+        Self {
+            ctors: HashMap::new()
+        }
+    }
+
+    /// Register how to build one kind of object. Registering a kind again replaces it
+    pub fn with_kind<F>(mut self, kind: &str, ctor: F) -> Self where
+            F: Fn(&ObjectDef) -> BuildResult<Img, Snd, Fnt, Spr, Rm, Data> + 'static {
+        self.ctors.insert(kind.to_string(), Box::new(ctor));
+        self
+    }
+
+    pub fn has_kind(&self, kind: &str) -> bool {
+        self.ctors.contains_key(kind)
+    }
+
+    /// Build an object, or None if nothing is registered for its kind
+    pub fn build(
+            &self, def: &ObjectDef) -> Option<BuildResult<Img, Snd, Fnt, Spr, Rm, Data>> {
+        let ctor = self.ctors.get(&def.kind)?;
+        Some(ctor(def).map_err(|err| {
+            format!("Failed to build '{}' ({}): {}", def.name, def.kind, err)
+        }))
+    }
+}
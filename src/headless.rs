@@ -0,0 +1,137 @@
+//! Run a game without a window or audio so rooms and objects can be tested on CI machines
+
+use std::collections::HashMap;
+use sdl2::{
+    event::Event,
+    EventPump, Sdl
+};
+use crate::{
+    app::Game,
+    controller::Controllers,
+    input::Input,
+    obj::{
+        ControlObject, GameObject
+    }, room::Room,
+    IndexRestriction
+};
+
+/// Drives the same update, collision, and room transition pipeline as app::run, but with no SDL
+/// window, renderer, mixer, or fonts. Nothing is ever rendered, so sprite animations don't advance.
+///
+/// Typical use in a test:
+///
+/// - Build it from the same rooms and control objects you'd pass to app::run
+/// - Queue up synthetic SDL events with push_event
+/// - Call step (or step_frames) with whatever delta you want
+/// - Check room() and objs() to see what happened
+///
+/// To test controllers, call enable_controllers and plug in a fake one with
+/// controllers().attach_virtual(). Its events are polled from SDL at the start of each step
+pub struct Headless<Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    game: Game<Img, Snd, Fnt, Spr, Rm, Data>,
+    events: Vec<Event>,
+    sdl: Option<(EventPump, Controllers)>
+}
+
+impl<Img, Snd, Fnt, Spr, Rm, Data> Headless<Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    pub fn new(
+            start_room: Rm, rooms: &HashMap<Rm, Room<Img, Snd, Fnt, Spr, Rm, Data>>,
+            ctl_objs: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>
+            ) -> Self {
+        Self {
+            game: Game::new(start_room, rooms, ctl_objs),
+            events: Vec::new(),
+            sdl: None
+        }
+    }
+
+    /// Start polling SDL for controller events. Only needs SDL itself, not a video or audio device
+    pub fn enable_controllers(&mut self, ctx: &Sdl) -> Result<(), String> {
+        self.sdl = Some((ctx.event_pump()?, Controllers::new(ctx)?));
+        Ok(())
+    }
+
+    /// Cameras don't know how big the screen is without a window, so set it here (usually to the
+    /// same size you'd give the app) before testing anything that uses them
+    pub fn set_view_size(&mut self, width: u32, height: u32) {
+        self.game.set_view_size((width as f64, height as f64));
+    }
+
+    /// Available after enable_controllers
+    pub fn controllers(&self) -> Option<&Controllers> {
+        self.sdl.as_ref().map(|(_, controllers)| controllers)
+    }
+
+    /// Queue an event to be handled at the start of the next step
+    pub fn push_event(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// Run one frame: handle queued events, update once with the given delta, then change rooms if
+    /// anything asked to.
+    ///
+    /// Gives back an error if a controller was plugged in but couldn't be opened. The frame still
+    /// runs without that controller first, the same as it would in the app
+    pub fn step(&mut self, delta: f64) -> Result<(), String> {
+        let mut res = Ok(());
+        if let Some((event_pump, controllers)) = self.sdl.as_mut() {
+            for event in event_pump.poll_iter() {
+                res = res.and(controllers.handle_sdl_event(&event, &mut self.game.input));
+                self.game.handle_sdl_event(&event);
+            }
+        }
+        for event in self.events.drain(..) {
+            self.game.handle_sdl_event(&event);
+        }
+        self.game.update(delta);
+        self.game.change_room();
+        res
+    }
+
+    /// Run several frames with the same delta, stopping after the first one that gives an error
+    pub fn step_frames(&mut self, frames: usize, delta: f64) -> Result<(), String> {
+        for _ in 0..frames {
+            self.step(delta)?;
+        }
+        Ok(())
+    }
+
+    /// The room that is currently active
+    pub fn room(&self) -> Rm {
+        self.game.room
+    }
+
+    /// The objects in the currently active room
+    pub fn objs(&self) -> &Vec<GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        &self.game.rooms[&self.game.room].objs
+    }
+
+    pub fn rooms(&self) -> &HashMap<Rm, Room<Img, Snd, Fnt, Spr, Rm, Data>> {
+        &self.game.rooms
+    }
+
+    pub fn rooms_mut(&mut self) -> &mut HashMap<Rm, Room<Img, Snd, Fnt, Spr, Rm, Data>> {
+        &mut self.game.rooms
+    }
+
+    /// Set up action bindings here before stepping
+    pub fn input_mut(&mut self) -> &mut Input {
+        &mut self.game.input
+    }
+
+    pub fn ctl_objs(&self) -> &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        &self.game.ctl_objs
+    }
+}
@@ -0,0 +1,322 @@
+//! Map your own action enum to keys, mouse buttons, and controller inputs so objects can ask about
+//! actions instead of raw SDL events
+
+use std::{
+    any::Any,
+    collections::HashSet
+};
+use sdl2::{
+    controller::{
+        Axis, Button
+    }, event::Event,
+    keyboard::Scancode,
+    mouse::MouseButton
+};
+use crate::{
+    controller::{
+        Controller, DEF_STICK_DEAD_ZONE, DEF_TRIGGER_DEAD_ZONE
+    }, IndexRestriction
+};
+
+/// A physical input that an action can be bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(Scancode),
+    Mouse(MouseButton),
+    Button(Button),
+
+    /// One direction of a controller axis, e.g. pushing the left stick left is
+    /// Axis { axis: Axis::LeftX, positive: false }. It counts as held while pushed at least halfway
+    Axis {
+        axis: Axis,
+        positive: bool
+    }
+}
+
+/// Lets Input store actions from any of your enums without knowing their type
+trait Action {
+    fn clone_box(&self) -> Box<dyn Action>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: IndexRestriction> Action for T {
+    fn clone_box(&self) -> Box<dyn Action> {
+        Box::new(*self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn Action> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Keeps track of what's held down and which bindings belong to which actions.
+///
+/// Actions are a custom enum defined by you (like your Img and Snd enums). Bind them once, then
+/// query them in update. Pressed and released are reset after every update, so each press is only
+/// seen by one update, even with a fixed timestep running several updates per frame.
+///
+/// Controller bindings react to every connected controller, and each controller keeps its own
+/// state: a button binding is held while any controller holds it, so one player letting go doesn't
+/// release it for another. Events from controllers that were never connected are ignored. To tell
+/// players apart, look at each player's controller directly with controller()
+#[derive(Clone)]
+pub struct Input {
+    bindings: Vec<(Box<dyn Action>, Vec<Binding>)>,
+    held: HashSet<Binding>,
+    pressed: HashSet<Binding>,
+    released: HashSet<Binding>,
+    mouse_pos: (i32, i32),
+    mouse_world_pos: (f64, f64),
+    controllers: Vec<Option<Controller>>,
+    stick_dead_zone: f64,
+    trigger_dead_zone: f64
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+            held: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            mouse_pos: (0, 0),
+            mouse_world_pos: (0.0, 0.0),
+            controllers: Vec::new(),
+            stick_dead_zone: DEF_STICK_DEAD_ZONE,
+            trigger_dead_zone: DEF_TRIGGER_DEAD_ZONE
+        }
+    }
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a binding to an action. An action can have as many bindings as you want
+    pub fn bind<Act: IndexRestriction>(&mut self, action: Act, binding: Binding) {
+        if let Some(bindings) = self.bindings_mut(action) {
+            if !bindings.contains(&binding) {
+                bindings.push(binding);
+            }
+        } else {
+            self.bindings.push((Box::new(action), vec![ binding ]));
+        }
+    }
+
+    /// Same as bind, but returns self so you can set up bindings in a chain
+    pub fn with_binding<Act: IndexRestriction>(mut self, action: Act, binding: Binding) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    pub fn unbind<Act: IndexRestriction>(&mut self, action: Act, binding: Binding) {
+        if let Some(bindings) = self.bindings_mut(action) {
+            bindings.retain(|other| *other != binding);
+        }
+    }
+
+    /// Replace every binding of an action, e.g. when the player remaps a control
+    pub fn rebind<Act: IndexRestriction>(&mut self, action: Act, bindings: &[Binding]) {
+        self.clear_bindings(action);
+        for binding in bindings.iter() {
+            self.bind(action, *binding);
+        }
+    }
+
+    pub fn clear_bindings<Act: IndexRestriction>(&mut self, action: Act) {
+        self.bindings.retain(|(other, _)| other.as_any().downcast_ref::<Act>() != Some(&action));
+    }
+
+    pub fn bindings<Act: IndexRestriction>(&self, action: Act) -> &[Binding] {
+        self.bindings.iter()
+            .find(|(other, _)| other.as_any().downcast_ref::<Act>() == Some(&action))
+            .map(|(_, bindings)| bindings.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn bindings_mut<Act: IndexRestriction>(&mut self, action: Act) -> Option<&mut Vec<Binding>> {
+        self.bindings.iter_mut()
+            .find(|(other, _)| other.as_any().downcast_ref::<Act>() == Some(&action))
+            .map(|(_, bindings)| bindings)
+    }
+
+    /// True the whole time any of the action's bindings are held down
+    pub fn is_held<Act: IndexRestriction>(&self, action: Act) -> bool {
+        self.bindings(action).iter().any(|binding| self.binding_held(binding))
+    }
+
+    /// True for the first update after one of the action's bindings is pressed
+    pub fn just_pressed<Act: IndexRestriction>(&self, action: Act) -> bool {
+        self.bindings(action).iter().any(|binding| self.binding_pressed(binding))
+    }
+
+    /// True for the first update after the action stops being held
+    pub fn just_released<Act: IndexRestriction>(&self, action: Act) -> bool {
+        !self.is_held(action)
+            && self.bindings(action).iter().any(|binding| self.binding_released(binding))
+    }
+
+    /// How strongly an action is held from 0 to 1. Keys and buttons are either 0 or 1, but axis
+    /// bindings give how far the axis is pushed in their direction (minus the dead zone), on
+    /// whichever controller pushes it furthest
+    pub fn value<Act: IndexRestriction>(&self, action: Act) -> f64 {
+        self.bindings(action).iter().map(|binding| match binding {
+            Binding::Axis { axis, positive } => self.controllers()
+                .map(|(_, ctlr)| ctlr.axis_dir(*axis, *positive))
+                .fold(0.0, f64::max),
+            _ => if self.binding_held(binding) { 1.0 } else { 0.0 }
+        }).fold(0.0, f64::max)
+    }
+
+    fn binding_held(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Button(button) => self.controllers().any(|(_, ctlr)| ctlr.is_held(*button)),
+            Binding::Axis { axis, positive } => self.controllers()
+                .any(|(_, ctlr)| ctlr.is_dir_held(*axis, *positive)),
+            _ => self.held.contains(binding)
+        }
+    }
+
+    fn binding_pressed(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Button(button) => self.controllers()
+                .any(|(_, ctlr)| ctlr.just_pressed(*button)),
+            Binding::Axis { axis, positive } => self.controllers()
+                .any(|(_, ctlr)| ctlr.dir_just_pressed(*axis, *positive)),
+            _ => self.pressed.contains(binding)
+        }
+    }
+
+    fn binding_released(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Button(button) => self.controllers()
+                .any(|(_, ctlr)| ctlr.just_released(*button)),
+            Binding::Axis { axis, positive } => self.controllers()
+                .any(|(_, ctlr)| ctlr.dir_just_released(*axis, *positive)),
+            _ => self.released.contains(binding)
+        }
+    }
+
+    /// Combine two opposite actions into a value from -1 to 1, e.g. axis(Act::Left, Act::Right)
+    pub fn axis<Act: IndexRestriction>(&self, negative: Act, positive: Act) -> f64 {
+        self.value(positive) - self.value(negative)
+    }
+
+    /// Last known mouse position in the game's logical coordinates
+    pub fn mouse_pos(&self) -> (i32, i32) {
+        self.mouse_pos
+    }
+
+    /// Where the mouse is in the current room, going through the room's camera (or the viewport
+    /// the mouse is over)
+    pub fn mouse_world_pos(&self) -> (f64, f64) {
+        self.mouse_world_pos
+    }
+
+    /// Called by the engine before every update so mouse_world_pos follows the camera
+    pub(crate) fn set_mouse_world_pos(&mut self, mouse_world_pos: (f64, f64)) {
+        self.mouse_world_pos = mouse_world_pos;
+    }
+
+    /// The controller for a player, if one is connected. Players are numbered from 0 in the order
+    /// their controllers were connected, and a player keeps their index until they disconnect
+    pub fn controller(&self, player: usize) -> Option<&Controller> {
+        self.controllers.get(player).and_then(|ctlr| ctlr.as_ref())
+    }
+
+    /// Every connected controller along with its player index
+    pub fn controllers(&self) -> impl Iterator<Item = (usize, &Controller)> {
+        self.controllers.iter().enumerate()
+            .filter_map(|(player, ctlr)| ctlr.as_ref().map(|ctlr| (player, ctlr)))
+    }
+
+    /// Set the dead zones used for every controller, both for axis bindings and Controller's
+    /// stick and trigger functions
+    pub fn set_dead_zones(&mut self, stick: f64, trigger: f64) {
+        self.stick_dead_zone = stick;
+        self.trigger_dead_zone = trigger;
+        for ctlr in self.controllers.iter_mut().flatten() {
+            ctlr.stick_dead_zone = stick;
+            ctlr.trigger_dead_zone = trigger;
+        }
+    }
+
+    /// Give a newly connected controller the lowest free player index and return it. The app calls
+    /// this when SDL opens a controller; call it yourself to fake one in a headless test
+    pub fn connect_controller(&mut self, instance_id: u32, name: &str) -> usize {
+        let mut ctlr = Controller::new(instance_id, name);
+        ctlr.stick_dead_zone = self.stick_dead_zone;
+        ctlr.trigger_dead_zone = self.trigger_dead_zone;
+        if let Some(player) = self.controllers.iter().position(|ctlr| ctlr.is_none()) {
+            self.controllers[player] = Some(ctlr);
+            player
+        } else {
+            self.controllers.push(Some(ctlr));
+            self.controllers.len() - 1
+        }
+    }
+
+    /// Free up a controller's player index
+    pub fn disconnect_controller(&mut self, instance_id: u32) {
+        for slot in self.controllers.iter_mut() {
+            if slot.as_ref().is_some_and(|ctlr| ctlr.instance_id == instance_id) {
+                *slot = None;
+            }
+        }
+    }
+
+    pub fn handle_sdl_event(&mut self, event: &Event) {
+        match event {
+            Event::ControllerButtonDown { which, .. }
+                    | Event::ControllerButtonUp { which, .. }
+                    | Event::ControllerAxisMotion { which, .. } => {
+                let ctlr = self.controllers.iter_mut().flatten()
+                    .find(|ctlr| ctlr.instance_id == *which);
+                if let Some(ctlr) = ctlr {
+                    ctlr.handle_sdl_event(event);
+                }
+            }, _ => {}
+        }
+        match event {
+            Event::KeyDown { scancode: Some(scancode), repeat: false, .. } => {
+                self.press(Binding::Key(*scancode));
+            }, Event::KeyUp { scancode: Some(scancode), .. } => {
+                self.release(Binding::Key(*scancode));
+            }, Event::MouseButtonDown { mouse_btn, x, y, .. } => {
+                self.mouse_pos = (*x, *y);
+                self.press(Binding::Mouse(*mouse_btn));
+            }, Event::MouseButtonUp { mouse_btn, x, y, .. } => {
+                self.mouse_pos = (*x, *y);
+                self.release(Binding::Mouse(*mouse_btn));
+            }, Event::MouseMotion { x, y, .. } => {
+                self.mouse_pos = (*x, *y);
+            }, _ => {}
+        }
+    }
+
+    /// Forget what was pressed and released. Called by the engine after every update
+    pub fn end_update(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        for ctlr in self.controllers.iter_mut().flatten() {
+            ctlr.end_update();
+        }
+    }
+
+    fn press(&mut self, binding: Binding) {
+        self.held.insert(binding);
+        self.pressed.insert(binding);
+    }
+
+    fn release(&mut self, binding: Binding) {
+        self.held.remove(&binding);
+        self.released.insert(binding);
+    }
+}
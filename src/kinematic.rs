@@ -0,0 +1,168 @@
+//! Move objects around without letting them pass through solid objects, like a platformer
+//! character walking on the ground and into walls
+
+use crate::{
+    collision::Contact,
+    obj::{
+        GameObjectState, ObjectId
+    }, query::Query,
+    IndexRestriction
+};
+
+/// How far down a contact normal has to point to count as standing on something (about 45°)
+const FLOOR_NORMAL_Y: f64 = 0.7;
+
+/// How many times a single step can get pushed out of things before giving up, e.g. when wedged
+/// into a corner
+const MAX_RESOLVES: usize = 4;
+
+/// What happened during move_and_slide or move_and_collide
+///
+/// - on_floor, on_wall, on_ceiling: bool (what the object touched while moving. Down is +y)
+/// - hits: Vec<(ObjectId, Contact)> (every solid object hit and how, normals point towards it.
+///   Solid tiles are all ObjectId::NONE)
+/// - triggers: Vec<ObjectId> (every trigger the object passed through, in the order it got to them)
+/// - remainder: (f64, f64) (how much of the motion didn't happen because something was in the way)
+#[derive(Clone, Debug, Default)]
+pub struct MoveResult {
+    pub on_floor: bool,
+    pub on_wall: bool,
+    pub on_ceiling: bool,
+    pub hits: Vec<(ObjectId, Contact)>,
+    pub triggers: Vec<ObjectId>,
+    pub remainder: (f64, f64)
+}
+
+impl MoveResult {
+    fn add_hit(&mut self, id: ObjectId, contact: Contact) {
+        if contact.normal.1 >= FLOOR_NORMAL_Y {
+            self.on_floor = true;
+        } else if contact.normal.1 <= -FLOOR_NORMAL_Y {
+            self.on_ceiling = true;
+        } else {
+            self.on_wall = true;
+        }
+        if let Some((_, other)) = self.hits.iter_mut().find(|(other_id, _)| *other_id == id) {
+            *other = contact;
+        } else {
+            self.hits.push((id, contact));
+        }
+    }
+}
+
+/// Move an object, sliding along anything solid it runs into instead of stopping.
+///
+/// Solids are the objects in the query with solid set and on a layer in the object's mask (the
+/// object itself is skipped, so passing the others given to update is fine). Touching counts as a
+/// hit, so an object resting on the ground is still on_floor. Zero the velocity along any normal
+/// you hit, e.g. vel.1 when on_floor. Triggers are passed through and listed instead. Solid tile
+/// layers in the query's tilemaps block the object too, if their collision_layer is in its mask
+pub fn move_and_slide<Img, Snd, Fnt, Spr, Rm, Data, Q>(
+        state: &mut GameObjectState<Img, Spr, Data>, motion: (f64, f64),
+        solids: &Q) -> MoveResult where
+            Spr: IndexRestriction,
+            Img: IndexRestriction,
+            Snd: IndexRestriction,
+            Fnt: IndexRestriction,
+            Rm: IndexRestriction,
+            Data: Clone,
+            Q: Query<Img, Snd, Fnt, Spr, Rm, Data> + ?Sized {
+    move_kinematic(state, motion, solids, true)
+}
+
+/// Move an object until it hits something solid and stop there. The remainder is what's left of
+/// the motion, in case you want to bounce it or carry on some other way
+pub fn move_and_collide<Img, Snd, Fnt, Spr, Rm, Data, Q>(
+        state: &mut GameObjectState<Img, Spr, Data>, motion: (f64, f64),
+        solids: &Q) -> MoveResult where
+            Spr: IndexRestriction,
+            Img: IndexRestriction,
+            Snd: IndexRestriction,
+            Fnt: IndexRestriction,
+            Rm: IndexRestriction,
+            Data: Clone,
+            Q: Query<Img, Snd, Fnt, Spr, Rm, Data> + ?Sized {
+    move_kinematic(state, motion, solids, false)
+}
+
+fn move_kinematic<Img, Snd, Fnt, Spr, Rm, Data, Q>(
+        state: &mut GameObjectState<Img, Spr, Data>, motion: (f64, f64),
+        solids: &Q, slide: bool) -> MoveResult where
+            Spr: IndexRestriction,
+            Img: IndexRestriction,
+            Snd: IndexRestriction,
+            Fnt: IndexRestriction,
+            Rm: IndexRestriction,
+            Data: Clone,
+            Q: Query<Img, Snd, Fnt, Spr, Rm, Data> + ?Sized {
+    let mut res = MoveResult::default();
+    let tilemaps = solids.tilemaps();
+    let others = solids.filter(|other| other.id != state.id && state.interacts_with(other));
+    let (solids, triggers): (Vec<_>, Vec<_>) = others.into_iter()
+        .filter(|other| other.state().solid || other.state().trigger)
+        .partition(|other| other.state().solid);
+
+    // Take steps no bigger than half the collider so fast objects can't skip through thin walls
+    let aabb = state.world_collider().aabb();
+    let max_step = ((aabb.max.0 - aabb.min.0).min(aabb.max.1 - aabb.min.1) as f64 / 2.0).max(1.0);
+    let steps = ((motion.0.abs().max(motion.1.abs()) / max_step).ceil() as usize).max(1);
+    let mut step = (motion.0 / steps as f64, motion.1 / steps as f64);
+    let (start, mask) = (state.pos, state.mask);
+
+    for _ in 0..steps {
+        state.pos.0 += step.0;
+        state.pos.1 += step.1;
+
+        let mut blocked = false;
+        for _ in 0..MAX_RESOLVES {
+            let collider = state.world_collider();
+            let tiles = tilemaps.iter()
+                .flat_map(|tilemap| tilemap.solids_overlapping_masked(&collider, mask));
+            let contacts = solids.iter()
+                .filter_map(|other| {
+                    let other = other.state();
+                    collider.contact(&other.world_collider()).map(|contact| (other.id, contact))
+                }).chain(tiles.filter_map(|(_, tile)| {
+                    collider.contact(&tile).map(|contact| (ObjectId::NONE, contact))
+                })).collect::<Vec<_>>();
+            let deepest = contacts.iter()
+                .filter(|(_, contact)| contact.depth > 0.0)
+                .max_by(|(_, a), (_, b)| a.depth.total_cmp(&b.depth))
+                .cloned();
+            let Some((id, contact)) = deepest else {
+                // Nothing left to push out of, so whatever's left is just touching
+                for (id, contact) in contacts {
+                    res.add_hit(id, contact);
+                }
+                break;
+            };
+            res.add_hit(id, contact.clone());
+
+            // Push back out along the normal, and stop moving into whatever was hit
+            state.pos.0 -= contact.normal.0 * contact.depth;
+            state.pos.1 -= contact.normal.1 * contact.depth;
+            blocked = true;
+            let into = step.0 * contact.normal.0 + step.1 * contact.normal.1;
+            if slide && into > 0.0 {
+                step.0 -= contact.normal.0 * into;
+                step.1 -= contact.normal.1 * into;
+            }
+        }
+
+        let collider = state.world_collider();
+        for trigger in triggers.iter().map(|trigger| trigger.state()) {
+            if !res.triggers.contains(&trigger.id)
+                    && collider.collides_with(&trigger.world_collider()) {
+                res.triggers.push(trigger.id);
+            }
+        }
+        if blocked && !slide {
+            break;
+        }
+    }
+    res.remainder = (
+        motion.0 - (state.pos.0 - start.0),
+        motion.1 - (state.pos.1 - start.1)
+    );
+    res
+}
@@ -0,0 +1,384 @@
+//! Load levels made in LDtk (https://ldtk.io) as rooms
+
+use std::collections::{
+    HashMap, HashSet
+};
+use sdl2::rect::Rect;
+use serde::Deserialize;
+use serde_json::Value;
+use crate::{
+    camera::Camera,
+    collision::CollisionShape,
+    factory::{
+        lookup, parse_color, ObjectDef, ObjectFactory, Property
+    }, room::{
+        Room, Rooms
+    },
+    tilemap::{
+        TileFlip, TileLayer, Tilemap, Tileset
+    }, IndexRestriction
+};
+
+/// Turns the levels of an LDtk project into rooms.
+///
+/// Every layer becomes a tilemap, drawn with the images registered for the tilesets' files. LDtk
+/// can stack tiles in one cell, so a layer's tilemap can have more than one TileLayer. IntGrid
+/// values go in the first layer of their tilemap, hidden, so tile() gives the value in a cell (0
+/// is None). Layers keep LDtk's draw order through their depth: the top layer is 0, the one below
+/// it 1, and so on. Flipped tiles keep their flips.
+///
+/// Entities are built by the factory using their identifier as the kind. Their name is their iid,
+/// which is what EntityRef fields hold, and their fields become properties. Point fields are the
+/// middle of the cell in the room. Entities with no constructor are skipped
+///
+/// - imgs: HashMap<String, Img> (image for each tileset file. Matched by the path in the project,
+///   or just the file name if that doesn't match)
+/// - levels: HashMap<String, Rm> (room for each level identifier. Other levels aren't loaded)
+/// - level_files: HashMap<String, Vec<u8>> (contents of levels saved in their own files)
+/// - solid_layers: HashSet<String> (identifiers of layers that block movement. For IntGrid layers,
+///   that's any cell with a value)
+pub struct LdtkLoader<Img, Rm> where Img: IndexRestriction, Rm: IndexRestriction {
+    pub imgs: HashMap<String, Img>,
+    pub levels: HashMap<String, Rm>,
+    pub level_files: HashMap<String, Vec<u8>>,
+    pub solid_layers: HashSet<String>
+}
+
+impl<Img, Rm> Default for LdtkLoader<Img, Rm> where Img: IndexRestriction, Rm: IndexRestriction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Img, Rm> LdtkLoader<Img, Rm> where Img: IndexRestriction, Rm: IndexRestriction {
+    pub fn new() -> Self {
+        Self {
+            imgs: HashMap::new(),
+            levels: HashMap::new(),
+            level_files: HashMap::new(),
+            solid_layers: HashSet::new()
+        }
+    }
+
+    pub fn with_image(mut self, source: &str, img: Img) -> Self {
+        self.imgs.insert(source.to_string(), img);
+        self
+    }
+
+    pub fn with_level(mut self, identifier: &str, room: Rm) -> Self {
+        self.levels.insert(identifier.to_string(), room);
+        self
+    }
+
+    /// Provide a level for projects saved with "separate level files" on
+    pub fn with_level_file(mut self, source: &str, bytes: &[u8]) -> Self {
+        self.level_files.insert(source.to_string(), bytes.to_vec());
+        self
+    }
+
+    pub fn with_solid_layer(mut self, identifier: &str) -> Self {
+        self.solid_layers.insert(identifier.to_string());
+        self
+    }
+
+    /// Build a room for every registered level in a project
+    pub fn load<Snd, Fnt, Spr, Data>(
+            &self, bytes: &[u8], factory: &ObjectFactory<Img, Snd, Fnt, Spr, Rm, Data>,
+            persistant: bool) -> Result<Rooms<Img, Snd, Fnt, Spr, Rm, Data>, String> where
+                Spr: IndexRestriction,
+                Snd: IndexRestriction,
+                Fnt: IndexRestriction,
+                Data: Clone {
+        let project: LdtkProject = serde_json::from_slice(bytes).map_err(|err| err.to_string())?;
+        let levels = project.levels.iter()
+            .chain(project.worlds.iter().flat_map(|world| world.levels.iter()))
+            .collect::<Vec<_>>();
+
+        let mut rooms = HashMap::new();
+        for (identifier, rm) in self.levels.iter() {
+            let level = levels.iter().find(|level| level.identifier == *identifier)
+                .ok_or(format!("No level '{}' in the project", identifier))?;
+            let external;
+            let layers = match (&level.layer_instances, &level.external_rel_path) {
+                (Some(layers), _) => layers,
+                (None, Some(source)) => {
+                    let bytes = lookup(&self.level_files, source)
+                        .ok_or(format!("No level file registered for '{}'", source))?;
+                    external = serde_json::from_slice::<LdtkLevel>(bytes)
+                        .map_err(|err| err.to_string())?;
+                    external.layer_instances.as_ref()
+                        .ok_or(format!("Level file '{}' has no layers", source))?
+                }, (None, None) => return Err(format!("Level '{}' has no layers", identifier))
+            };
+            let room = self.build_room(level, layers, &project.defs, factory, persistant)
+                .map_err(|err| format!("In level '{}': {}", identifier, err))?;
+            rooms.insert(*rm, room);
+        }
+        Ok(rooms)
+    }
+
+    fn build_room<Snd, Fnt, Spr, Data>(
+            &self, level: &LdtkLevel, layers: &[LdtkLayer], defs: &LdtkDefs,
+            factory: &ObjectFactory<Img, Snd, Fnt, Spr, Rm, Data>,
+            persistant: bool) -> Result<Room<Img, Snd, Fnt, Spr, Rm, Data>, String> where
+                Spr: IndexRestriction,
+                Snd: IndexRestriction,
+                Fnt: IndexRestriction,
+                Data: Clone {
+        let mut tilemaps = Vec::new();
+        let mut objs = Vec::new();
+        for (depth, layer) in layers.iter().enumerate() {
+            let depth = depth as i32;
+            let offset = (layer.px_total_offset_x, layer.px_total_offset_y);
+            let grid = layer.grid_size as f64;
+            let solid = self.solid_layers.contains(&layer.identifier);
+
+            if layer.kind == "Entities" {
+                for entity in layer.entity_instances.iter() {
+                    let size = (entity.width, entity.height);
+                    let def = ObjectDef {
+                        kind: entity.identifier.clone(),
+                        name: entity.iid.clone(),
+                        pos: (
+                            offset.0 + entity.px[0] - entity.pivot[0] * size.0,
+                            offset.1 + entity.px[1] - entity.pivot[1] * size.1
+                        ), size,
+                        angle: 0.0,
+                        depth,
+                        shape: Some(CollisionShape::Rect {
+                            center: (size.0 / 2.0, size.1 / 2.0),
+                            size
+                        }), props: entity.field_instances.iter()
+                            .filter_map(|field| Some((
+                                field.identifier.clone(),
+                                ldtk_field(&field.kind, &field.value, grid, offset)?
+                            ))).collect()
+                    };
+                    if let Some(obj) = factory.build(&def) {
+                        objs.push(obj?);
+                    }
+                }
+                continue;
+            }
+
+            let size = (layer.c_wid, layer.c_hei);
+            let mut tilemap = Tilemap::grid(size, (grid, grid)).with_pos(offset);
+            if let Some(uid) = layer.tileset_def_uid {
+                let tileset = defs.tilesets.iter().find(|tileset| tileset.uid == uid)
+                    .ok_or(format!("Layer '{}' uses a missing tileset", layer.identifier))?;
+                let source = tileset.rel_path.as_ref().ok_or(format!(
+                    "Tileset '{}' has no image file, which isn't supported", tileset.identifier
+                ))?;
+                let img = lookup(&self.imgs, source).copied()
+                    .ok_or(format!("No image registered for tileset image '{}'", source))?;
+                let tile_size = (tileset.tile_grid_size, tileset.tile_grid_size);
+                tilemap = tilemap.with_tileset(
+                    Tileset::new(img, tile_size, tileset.c_wid)
+                        .with_spacing(tileset.padding, tileset.spacing)
+                );
+            }
+            if layer.kind == "IntGrid" {
+                let values = layer.int_grid_csv.iter()
+                    .map(|val| (*val != 0).then_some(*val))
+                    .collect();
+                tilemap = tilemap.with_layer(
+                    TileLayer::new(&layer.identifier, values)
+                        .with_depth(depth)
+                        .with_solid(solid)
+                        .with_visible(false)
+                );
+            }
+
+            // Auto-layers fill in every layer type except Tiles, which LDtk keeps separately
+            let tiles = if layer.kind == "Tiles" {
+                &layer.grid_tiles
+            } else {
+                &layer.auto_layer_tiles
+            };
+            for (tiles, flips) in stack_tiles(tiles, size, grid) {
+                tilemap = tilemap.with_layer(
+                    TileLayer::new(&layer.identifier, tiles)
+                        .with_depth(depth)
+                        .with_solid(solid && layer.kind != "IntGrid")
+                        .with_visible(layer.visible.unwrap_or(true))
+                        .with_flips(flips)
+                );
+            }
+            if !tilemap.layers.is_empty() {
+                tilemaps.push(tilemap);
+            }
+        }
+
+        let mut room = Room::new(objs, persistant).with_camera(
+            Camera::new().with_bounds(Rect::new(0, 0, level.px_wid, level.px_hei))
+        );
+        room.tilemaps = tilemaps;
+        room.bg_color = level.bg_color.as_deref().and_then(parse_color);
+        Ok(room)
+    }
+}
+
+/// Put each tile in its cell, starting another layer on top whenever a cell is already taken, so
+/// tiles later in the list still draw over earlier ones. Each layer comes with its tiles' flips,
+/// or no flips if none of them are flipped
+fn stack_tiles(
+        tiles: &[LdtkTile], size: (u32, u32), grid: f64) -> Vec<(Vec<Option<u32>>, Vec<TileFlip>)> {
+    let count = (size.0 * size.1) as usize;
+    let mut layers: Vec<(Vec<Option<u32>>, Vec<TileFlip>)> = Vec::new();
+    for tile in tiles.iter() {
+        let cell = ((tile.px[0] / grid).floor() as i64, (tile.px[1] / grid).floor() as i64);
+        if cell.0 < 0 || cell.1 < 0 || cell.0 >= size.0 as i64 || cell.1 >= size.1 as i64 {
+            continue;
+        }
+        let idx = (cell.1 * size.0 as i64 + cell.0) as usize;
+        let layer = match layers.iter().position(|(layer, _)| layer[idx].is_none()) {
+            Some(layer) => layer,
+            None => {
+                layers.push((vec![ None; count ], vec![ TileFlip::default(); count ]));
+                layers.len() - 1
+            }
+        };
+        let (layer_tiles, flips) = &mut layers[layer];
+        layer_tiles[idx] = Some(tile.t);
+        flips[idx] = TileFlip::new(tile.f & 1 != 0, tile.f & 2 != 0);
+    }
+    for (_, flips) in layers.iter_mut() {
+        if flips.iter().all(|flip| *flip == TileFlip::default()) {
+            flips.clear();
+        }
+    }
+    layers
+}
+
+/// Convert a field value from how LDtk writes it
+fn ldtk_field(kind: &str, val: &Value, grid: f64, offset: (f64, f64)) -> Option<Property> {
+    if let Some(kind) = kind.strip_prefix("Array<").and_then(|kind| kind.strip_suffix('>')) {
+        return Some(Property::List(
+            val.as_array()?.iter().filter_map(|val| ldtk_field(kind, val, grid, offset)).collect()
+        ));
+    }
+    match (kind, val) {
+        ("Color", Value::String(color)) => Some(Property::Color(parse_color(color)?)),
+        ("Float", Value::Number(num)) => Some(Property::Float(num.as_f64()?)),
+        ("Point", Value::Object(point)) => Some(Property::Point((
+            offset.0 + (point.get("cx")?.as_f64()? + 0.5) * grid,
+            offset.1 + (point.get("cy")?.as_f64()? + 0.5) * grid
+        ))), ("EntityRef", Value::Object(entity)) => {
+            Some(Property::String(entity.get("entityIid")?.as_str()?.to_string()))
+        }, _ => Property::from_json(val)
+    }
+}
+
+/// The parts of LDtk's JSON format that get used
+#[derive(Deserialize)]
+struct LdtkProject {
+    defs: LdtkDefs,
+    #[serde(default)]
+    levels: Vec<LdtkLevel>,
+    #[serde(default)]
+    worlds: Vec<LdtkWorld>
+}
+
+#[derive(Deserialize)]
+struct LdtkWorld {
+    #[serde(default)]
+    levels: Vec<LdtkLevel>
+}
+
+#[derive(Deserialize)]
+struct LdtkDefs {
+    #[serde(default)]
+    tilesets: Vec<LdtkTileset>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkTileset {
+    uid: i64,
+    identifier: String,
+    rel_path: Option<String>,
+    tile_grid_size: u32,
+    #[serde(rename = "__cWid")]
+    c_wid: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    padding: u32
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkLevel {
+    identifier: String,
+    px_wid: u32,
+    px_hei: u32,
+    #[serde(rename = "__bgColor")]
+    bg_color: Option<String>,
+    external_rel_path: Option<String>,
+    layer_instances: Option<Vec<LdtkLayer>>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkLayer {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    kind: String,
+    #[serde(rename = "__cWid")]
+    c_wid: u32,
+    #[serde(rename = "__cHei")]
+    c_hei: u32,
+    #[serde(rename = "__gridSize")]
+    grid_size: u32,
+    #[serde(rename = "__pxTotalOffsetX", default)]
+    px_total_offset_x: f64,
+    #[serde(rename = "__pxTotalOffsetY", default)]
+    px_total_offset_y: f64,
+    #[serde(rename = "__tilesetDefUid")]
+    tileset_def_uid: Option<i64>,
+    visible: Option<bool>,
+    #[serde(default)]
+    int_grid_csv: Vec<u32>,
+    #[serde(default)]
+    auto_layer_tiles: Vec<LdtkTile>,
+    #[serde(default)]
+    grid_tiles: Vec<LdtkTile>,
+    #[serde(default)]
+    entity_instances: Vec<LdtkEntity>
+}
+
+/// f holds the tile's flips: 1 for x and 2 for y
+#[derive(Deserialize)]
+struct LdtkTile {
+    px: [f64; 2],
+    t: u32,
+    #[serde(default)]
+    f: u8
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkEntity {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__pivot", default)]
+    pivot: [f64; 2],
+    #[serde(default)]
+    iid: String,
+    width: f64,
+    height: f64,
+    px: [f64; 2],
+    #[serde(default)]
+    field_instances: Vec<LdtkField>
+}
+
+#[derive(Deserialize)]
+struct LdtkField {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    kind: String,
+    #[serde(rename = "__value")]
+    value: Value
+}
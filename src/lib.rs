@@ -5,8 +5,21 @@ use std::hash::Hash;
 pub mod res;
 pub mod obj;
 pub mod collision;
+pub mod kinematic;
+pub mod physics;
+pub mod input;
+pub mod controller;
 pub mod room;
+pub mod camera;
+pub mod tilemap;
+pub mod factory;
+pub mod tiled;
+pub mod ldtk;
+pub mod roomdef;
+pub mod aseprite;
+pub mod query;
 pub mod app;
+pub mod headless;
 pub mod util;
 
 /// Y-Craft generically uses hypothetical enums to index various things, and there are restrictions
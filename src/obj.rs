@@ -2,29 +2,55 @@
 
 use std::{
     collections::HashMap,
-    hash::Hash
+    hash::Hash,
+    iter::Chain,
+    slice::Iter
 };
 use sdl2::{
     event::Event,
     rect::Rect,
-    render::{
-        Canvas, TextureCreator
-    }, video::{
-        Window, WindowContext
-    }
+    render::Canvas,
+    video::Window
 };
 use crate::{
+    camera::Camera,
     collision::CollisionShape,
+    input::Input,
+    physics::Body,
     res::{
-        Font, Image, Sound
-    }, IndexRestriction
+        Image, Resources
+    }, tilemap::Tilemap,
+    util::lerp,
+    IndexRestriction
 };
 
+/// Identifies one object in a room. The room hands these out as objects are added, and an object
+/// keeps its id for as long as it's in the room (including across resets)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectId(pub u64);
+
+impl ObjectId {
+    /// What objects have before they're added to a room. Also used as the sender of messages that
+    /// don't come from an object
+    pub const NONE: Self = Self(0);
+}
+
 /// Every game object should have these parameters to return them via state()
 ///
+/// - id: ObjectId (filled in by the room, so just leave it as ObjectId::NONE)
 /// - name: String,
 /// - pos: (f64, f64)
-/// - collider: CollisionShape
+/// - prev_pos: (f64, f64) (where the object was before the last update, used for interpolation)
+/// - depth: i32 (like GameMaker, objects with a lower depth are drawn on top. Defaults to 0)
+/// - collider: CollisionShape (relative to pos, before it's scaled and rotated)
+/// - collider_scale: (f64, f64) and collider_angle: f64 (how to transform the collider. Ignored
+///   while collider_follows_sprite is on, which uses the current sprite's scale, angle, and flip)
+/// - solid: bool (blocks objects moved with kinematic::move_and_slide and physics bodies. Defaults
+///   to false)
+/// - trigger: bool (never blocks anything, but move_and_slide reports moving through it)
+/// - body: Option<Body> (simulated by the room's physics when set. Defaults to None)
+/// - layer: u32 (bits for which collision layers the object is on. Defaults to layer 1)
+/// - mask: u32 (bits for which layers the object collides with. Defaults to all of them)
 /// - cur_spr: SprId (a custom enum defined by you to distinguish between sprites)
 /// - sprs: HashMap<SprId, Sprite<ImgId>> (a mapping of sprite ids to sprites)
 /// - custom: Data (a custom enum containing data for all your objects)
@@ -33,14 +59,124 @@ pub struct GameObjectState<Img, Spr, Data> where
         Spr: IndexRestriction,
         Img: IndexRestriction,
         Data: Clone {
+    pub id: ObjectId,
     pub name: String,
     pub pos: (f64, f64),
+    pub prev_pos: (f64, f64),
+    pub depth: i32,
     pub collider: CollisionShape,
+    pub collider_scale: (f64, f64),
+    pub collider_angle: f64,
+    pub collider_follows_sprite: bool,
+    pub solid: bool,
+    pub trigger: bool,
+    pub body: Option<Body>,
+    pub layer: u32,
+    pub mask: u32,
     pub cur_spr: Spr,
     pub sprs: HashMap<Spr, Sprite<Img>>,
     pub custom: Data
 }
 
+impl<Img, Spr, Data> GameObjectState<Img, Spr, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Data: Clone {
+    pub fn new(
+            name: &str, pos: (f64, f64), collider: CollisionShape, cur_spr: Spr,
+            sprs: HashMap<Spr, Sprite<Img>>, custom: Data) -> Self {
+        Self {
+            id: ObjectId::NONE,
+            name: name.to_string(),
+            pos,
+            prev_pos: pos,
+            depth: 0,
+            collider,
+            collider_scale: (1.0, 1.0),
+            collider_angle: 0.0,
+            collider_follows_sprite: false,
+            solid: false,
+            trigger: false,
+            body: None,
+            layer: 1,
+            mask: u32::MAX,
+            cur_spr,
+            sprs,
+            custom
+        }
+    }
+
+    pub fn with_depth(mut self, depth: i32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Solid objects aren't triggers, so this turns trigger off when solid is on
+    pub fn with_solid(mut self, solid: bool) -> Self {
+        self.solid = solid;
+        self.trigger = self.trigger && !solid;
+        self
+    }
+
+    /// Triggers aren't solid, so this turns solid off when trigger is on
+    pub fn with_trigger(mut self, trigger: bool) -> Self {
+        self.trigger = trigger;
+        self.solid = self.solid && !trigger;
+        self
+    }
+
+    /// Let the room's physics move the object
+    pub fn with_body(mut self, body: Body) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// Put the object on some collision layers and pick which layers it reacts to. Objects only
+    /// get on_collision for others on a layer in their mask
+    pub fn with_layers(mut self, layer: u32, mask: u32) -> Self {
+        self.layer = layer;
+        self.mask = mask;
+        self
+    }
+
+    /// True if this object's mask includes one of the other object's layers
+    pub fn interacts_with(&self, other: &GameObjectState<Img, Spr, Data>) -> bool {
+        self.mask & other.layer != 0
+    }
+
+    /// Scale and rotate the collider around the object's origin
+    pub fn with_collider_transform(mut self, scale: (f64, f64), angle: f64) -> Self {
+        self.collider_scale = scale;
+        self.collider_angle = angle;
+        self
+    }
+
+    /// Keep the collider lined up with the current sprite as it's scaled, rotated, and flipped
+    pub fn with_collider_following_sprite(mut self) -> Self {
+        self.collider_follows_sprite = true;
+        self
+    }
+
+    /// The collider transformed and moved to where the object is in the room
+    pub fn world_collider(&self) -> CollisionShape {
+        let spr = self.sprs.get(&self.cur_spr).filter(|_| self.collider_follows_sprite);
+        let collider = if let Some(spr) = spr {
+            spr.transform_collider(&self.collider)
+        } else {
+            self.collider.scaled(self.collider_scale).rotated(self.collider_angle, (0.0, 0.0))
+        };
+        collider.moved(self.pos)
+    }
+
+    /// Position between prev_pos and pos. An alpha of 0 is the previous update, 1 is the latest
+    pub fn lerp_pos(&self, alpha: f64) -> (f64, f64) {
+        (
+            lerp(self.prev_pos.0, self.pos.0, alpha),
+            lerp(self.prev_pos.1, self.pos.1, alpha)
+        )
+    }
+}
+
 /// All game objects should implement these
 ///
 /// Note: the generic types refer to custom enums for indexing items:
@@ -59,9 +195,13 @@ pub trait GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>:
         Fnt: IndexRestriction,
         Rm: IndexRestriction,
         Data: Clone {
-    fn state(&self) -> GameObjectState<Img, Spr, Data>;
+    fn state(&self) -> &GameObjectState<Img, Spr, Data>;
+
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data>;
 
-    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>);
+    fn set_state(&mut self, new_state: &GameObjectState<Img, Spr, Data>) {
+        *self.state_mut() = new_state.clone();
+    }
 
     /// Let game objects reset their data on room load. If you return true, the object is removed
     /// from the room
@@ -69,33 +209,58 @@ pub trait GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>:
 
     /// Let game objects modify their state every loop. Return a room to change to and objects to
     /// add to the room.
+    ///
+    /// Input is mutable so bindings can be remapped at runtime, e.g. from a settings menu.
+    ///
+    /// Objects update one at a time in room order, so others earlier in the room have already
+    /// updated this loop and others later haven't yet
     fn update(
-            &mut self, _delta: f64,
-            _ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-            _others: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
-                Option<Rm>, Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
-            ) {
+            &mut self, _delta: f64, _input: &mut Input,
+            _ctl_objs: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+            _others: &Others<Img, Snd, Fnt, Spr, Rm, Data>
+            ) -> Update<Img, Snd, Fnt, Spr, Rm, Data> {
         (None, vec![])
     }
 
     fn handle_sdl_event(&mut self, _event: &Event) {}
 
-    fn on_collision(
-        &mut self,
-        _other: &Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>) {}
+    /// Called every update while this object overlaps another one on a layer in its mask
+    fn on_collision(&mut self, _other: &GameObject<Img, Snd, Fnt, Spr, Rm, Data>) {}
 
+    /// Called on the first update two objects overlap, right after on_collision
+    fn on_collision_enter(&mut self, _other: &GameObject<Img, Snd, Fnt, Spr, Rm, Data>) {}
+
+    /// Called on every later update they keep overlapping, right after on_collision
+    fn on_collision_stay(&mut self, _other: &GameObject<Img, Snd, Fnt, Spr, Rm, Data>) {}
+
+    /// Called on the first update two objects stop overlapping. Only the id is given since the
+    /// other object might have been removed
+    fn on_collision_exit(&mut self, _other: ObjectId) {}
+
+    /// Messages this object wants to send, as (recipient, message). The room calls this after every
+    /// update and delivers them, so just queue them up in update and hand them over here. Messages
+    /// from an object that's removed that update are still sent, but ones to it are dropped
+    fn take_messages(&mut self) -> Vec<(ObjectId, Data)> {
+        vec![]
+    }
+
+    /// Receive a message sent by another object (or Room::send)
+    fn on_message(&mut self, _from: ObjectId, _msg: &Data) {}
+
+    /// Draw the object. Alpha is how far the game is between the last update and the next one
+    /// (always 1 with a variable timestep), so objects can draw between their prev_pos and pos.
+    ///
+    /// Pass the camera along to Sprite::render to draw in the room, or Camera::new() to draw
+    /// straight onto the screen (e.g. for a HUD)
     fn render(
-            &mut self, cnv: &mut Canvas<Window>,
-            imgs: &HashMap<Img, Image>, _snds: &HashMap<Snd, Sound>,
-            _fonts: &HashMap<Fnt, Font>, _creator: &TextureCreator<WindowContext>,
-            elapsed: f64) -> Result<(), String> {
-        let mut state = self.state().clone();
-        let GameObjectState { ref mut sprs, ref mut cur_spr, pos, .. } = state;
+            &mut self, cnv: &mut Canvas<Window>, cam: &Camera, res: &Resources<Img, Snd, Fnt>,
+            elapsed: f64, alpha: f64) -> Result<(), String> {
+        let pos = self.state().lerp_pos(alpha);
+        let GameObjectState { sprs, cur_spr, .. } = self.state_mut();
         if let Some(spr) = sprs.get_mut(cur_spr) {
             spr.update(elapsed);
-            spr.render(cnv, imgs, (pos.0 as i32, pos.1 as i32))?;
+            spr.render(cnv, cam, res.imgs, (pos.0 as i32, pos.1 as i32))?;
         }
-        self.set_state(&state);
         Ok(())
     }
 
@@ -112,7 +277,7 @@ pub trait GameObjectBehaviorClone<Img, Snd, Fnt, Spr, Rm, Data> where
         Fnt: IndexRestriction,
         Rm: IndexRestriction,
         Data: Clone {
-    fn clone_box(&self) -> Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>;
+    fn clone_box(&self) -> GameObject<Img, Snd, Fnt, Spr, Rm, Data>;
 }
 
 impl<Img, Snd, Fnt, Spr, Rm, Data, T>
@@ -124,13 +289,13 @@ impl<Img, Snd, Fnt, Spr, Rm, Data, T>
         Fnt: IndexRestriction,
         Rm: IndexRestriction,
         Data: Clone {
-    fn clone_box(&self) -> Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>> {
+    fn clone_box(&self) -> GameObject<Img, Snd, Fnt, Spr, Rm, Data> {
         Box::new(self.clone())
     }
 }
 
 impl<Img, Snd, Fnt, Spr, Rm, Data> Clone
-    for Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>> where
+    for GameObject<Img, Snd, Fnt, Spr, Rm, Data> where
         Spr: IndexRestriction,
         Img: IndexRestriction,
         Snd: IndexRestriction,
@@ -142,6 +307,88 @@ impl<Img, Snd, Fnt, Spr, Rm, Data> Clone
     }
 }
 
+/// A game object the way rooms hold them
+pub type GameObject<Img, Snd, Fnt, Spr, Rm, Data> = Box<
+    dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>
+>;
+
+/// What update gives back: a room to change to (if any) and objects to add to the room
+pub type Update<Img, Snd, Fnt, Spr, Rm, Data> = (
+    Option<Rm>, Vec<GameObject<Img, Snd, Fnt, Spr, Rm, Data>>
+);
+
+/// Goes over Others without copying anything
+pub type OthersIter<'a, Img, Snd, Fnt, Spr, Rm, Data> = Chain<
+    Iter<'a, GameObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+    Iter<'a, GameObject<Img, Snd, Fnt, Spr, Rm, Data>>
+>;
+
+/// Every object in a room except the one that's updating, borrowed straight from the room instead
+/// of copied, plus the room's tilemaps. Search it with the Query trait or loop over it with iter()
+pub struct Others<'a, Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    before: &'a [GameObject<Img, Snd, Fnt, Spr, Rm, Data>],
+    after: &'a [GameObject<Img, Snd, Fnt, Spr, Rm, Data>],
+    pub(crate) tilemaps: &'a [Tilemap<Img>]
+}
+
+impl<'a, Img, Snd, Fnt, Spr, Rm, Data> Others<'a, Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    /// The objects on either side of the one updating. Handy for calling update yourself in tests
+    pub fn new(
+            before: &'a [GameObject<Img, Snd, Fnt, Spr, Rm, Data>],
+            after: &'a [GameObject<Img, Snd, Fnt, Spr, Rm, Data>]) -> Self {
+        Self {
+            before,
+            after,
+            tilemaps: &[]
+        }
+    }
+
+    pub fn with_tilemaps(mut self, tilemaps: &'a [Tilemap<Img>]) -> Self {
+        self.tilemaps = tilemaps;
+        self
+    }
+
+    pub fn iter(&self) -> OthersIter<'a, Img, Snd, Fnt, Spr, Rm, Data> {
+        self.before.iter().chain(self.after.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.before.len() + self.after.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a, Img, Snd, Fnt, Spr, Rm, Data> IntoIterator
+    for &Others<'a, Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    type Item = &'a GameObject<Img, Snd, Fnt, Spr, Rm, Data>;
+    type IntoIter = OthersIter<'a, Img, Snd, Fnt, Spr, Rm, Data>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 /// Control objects are basically game objects, but they are aware of the current room and do not
 /// possess colliders. They are the way for doing dynamic memory
 pub trait ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>:
@@ -156,20 +403,19 @@ pub trait ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>:
     
     fn handle_sdl_event(&mut self, _event: &Event) {}
 
+    /// Others is every other control object (not including this one). Like room objects, the ones
+    /// earlier in the list have already updated this loop
     fn update(
-            &mut self, _delta: f64, _cur_room: &Rm,
-            _others: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-            _room_objs: &Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>) -> (
-                Option<Rm>, Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
-            ) {
+            &mut self, _delta: f64, _input: &mut Input, _cur_room: &Rm,
+            _others: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+            _room_objs: &Vec<GameObject<Img, Snd, Fnt, Spr, Rm, Data>>
+            ) -> Update<Img, Snd, Fnt, Spr, Rm, Data> {
         (None, vec![])
     }
 
     fn render(
             &mut self, _cnv: &mut Canvas<Window>, _cur_room: &Rm,
-            _imgs: &HashMap<Img, Image>, _snds: &HashMap<Snd, Sound>,
-            _fonts: &HashMap<Fnt, Font>, _creator: &TextureCreator<WindowContext>,
-            _elapsed: f64) -> Result<(), String> {
+            _res: &Resources<Img, Snd, Fnt>, _elapsed: f64) -> Result<(), String> {
         Ok(())
     }
 }
@@ -181,7 +427,7 @@ pub trait ControlObjectBehaviorClone<Img, Snd, Fnt, Spr, Rm, Data> where
         Fnt: IndexRestriction,
         Rm: IndexRestriction,
         Data: Clone {
-    fn clone_box(&self) -> Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>;
+    fn clone_box(&self) -> ControlObject<Img, Snd, Fnt, Spr, Rm, Data>;
 }
 
 impl<Img, Snd, Fnt, Spr, Rm, Data, T>
@@ -193,13 +439,13 @@ impl<Img, Snd, Fnt, Spr, Rm, Data, T>
         Fnt: IndexRestriction,
         Rm: IndexRestriction,
         Data: Clone {
-    fn clone_box(&self) -> Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>> {
+    fn clone_box(&self) -> ControlObject<Img, Snd, Fnt, Spr, Rm, Data> {
         Box::new(self.clone())
     }
 }
 
 impl<Img, Snd, Fnt, Spr, Rm, Data> Clone
-    for Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>> where
+    for ControlObject<Img, Snd, Fnt, Spr, Rm, Data> where
         Spr: IndexRestriction,
         Img: IndexRestriction,
         Snd: IndexRestriction,
@@ -211,14 +457,21 @@ impl<Img, Snd, Fnt, Spr, Rm, Data> Clone
     }
 }
 
+/// A control object the way the app holds them
+pub type ControlObject<Img, Snd, Fnt, Spr, Rm, Data> = Box<
+    dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>
+>;
+
 /// A single frame of animation - where to clip an image and how to draw
 ///
-/// ImgId refers to an enum that distinguishes between all the image resources in your game
+/// ImgId refers to an enum that distinguishes between all the image resources in your game.
+/// Frames with a duration (in seconds) stay up that long instead of going by the sprite's anim_spd
 #[derive(Clone, Copy)]
 pub struct Frame<ImgId> where ImgId: Hash + Eq + Clone + Copy {
     src: ImgId,
     clip: Rect,
-    size: (i32, i32)
+    size: (i32, i32),
+    duration: Option<f64>
 }
 
 impl<ImgId> Frame<ImgId> where ImgId: Hash + Eq + Clone + Copy {
@@ -226,21 +479,34 @@ impl<ImgId> Frame<ImgId> where ImgId: Hash + Eq + Clone + Copy {
         Self {
             src,
             clip,
-            size
+            size,
+            duration: None
         }
     }
 
+    pub fn with_duration(mut self, duration: f64) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn duration(&self) -> Option<f64> {
+        self.duration
+    }
+
+    /// Draw the frame in the room, moved and zoomed by the camera
     pub fn render(
-            &self, cnv: &mut Canvas<Window>, imgs: &HashMap<ImgId, Image>,
-            pos: (i32, i32), origin: (i32, i32), scale: (f64, f64),
-            angle: f64, flip: (bool, bool)) -> Result<(), String> {
+            &self, cnv: &mut Canvas<Window>, cam: &Camera, imgs: &HashMap<ImgId, Image>,
+            transform: &FrameTransform) -> Result<(), String> {
+        let FrameTransform { pos, origin, scale, angle, flip } = *transform;
         let base_scale = (
             self.size.0 as f64 / self.clip.w as f64,
             self.size.1 as f64 / self.clip.h as f64
         );
+        let scale = (scale.0 * cam.zoom, scale.1 * cam.zoom);
+        let pos = cam.world_to_screen((pos.0 as f64, pos.1 as f64));
         let dest = Rect::new(
-            (pos.0 as f64 - origin.0 as f64 * base_scale.0 * scale.0) as i32,
-            (pos.1 as f64 - origin.1 as f64 * base_scale.1 * scale.1) as i32,
+            (pos.0 - origin.0 as f64 * base_scale.0 * scale.0) as i32,
+            (pos.1 - origin.1 as f64 * base_scale.1 * scale.1) as i32,
             (self.size.0 as f64 * scale.0) as u32,
             (self.size.1 as f64 * scale.1) as u32
         );
@@ -248,7 +514,37 @@ impl<ImgId> Frame<ImgId> where ImgId: Hash + Eq + Clone + Copy {
     }
 }
 
+/// Where and how to draw a frame
+///
+/// - pos: (i32, i32) (where the origin goes in the room)
+/// - origin: (i32, i32) (point in the frame that's placed at pos)
+/// - scale: (f64, f64)
+/// - angle: f64 (degrees clockwise)
+/// - flip: (bool, bool) (horizontally, vertically)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameTransform {
+    pub pos: (i32, i32),
+    pub origin: (i32, i32),
+    pub scale: (f64, f64),
+    pub angle: f64,
+    pub flip: (bool, bool)
+}
+
+/// Which way a sprite's animation plays through its frames
+///
+/// - Forward: first to last, then back to the first
+/// - Reverse: last to first, then back to the last
+/// - PingPong: first to last, then back down to the first, and so on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnimDirection {
+    Forward,
+    Reverse,
+    PingPong
+}
+
 /// A collection of different animation frames that can be moved around a screen
+///
+/// - anim_backwards: bool (whether a PingPong animation is on its way back down)
 #[derive(Clone)]
 pub struct Sprite<Img> where Img: IndexRestriction {
     pub frames: Vec<Frame<Img>>,
@@ -256,6 +552,8 @@ pub struct Sprite<Img> where Img: IndexRestriction {
     pub origin: (i32, i32),
     pub anim_idx: usize,
     pub anim_idx_smooth: f64,
+    pub anim_dir: AnimDirection,
+    pub anim_backwards: bool,
     pub scale: (f64, f64),
     pub angle: f64,
     pub flip: (bool, bool)
@@ -269,30 +567,104 @@ impl<Img> Sprite<Img> where Img: IndexRestriction {
             origin,
             anim_idx: 0,
             anim_idx_smooth: 0.0,
+            anim_dir: AnimDirection::Forward,
+            anim_backwards: false,
             scale: (1.0, 1.0),
             angle: 0.0,
             flip: (false, false)
         }
     }
 
+    /// Play the frames in a different order. Reverse starts from the last frame
+    pub fn with_anim_dir(mut self, anim_dir: AnimDirection) -> Self {
+        self.anim_dir = anim_dir;
+        self.anim_backwards = false;
+        self.anim_idx = match anim_dir {
+            AnimDirection::Reverse => self.frames.len().saturating_sub(1),
+            _ => 0
+        };
+        self
+    }
+
     pub fn update(&mut self, elapsed: f64) {
-        self.anim_idx_smooth += elapsed * self.anim_spd;
-        if self.anim_idx_smooth > 1.0 {
-            if self.anim_idx + 1 >= self.frames.len() {
-                self.anim_idx = 0;
-            } else {
-                self.anim_idx += 1;
+        let Some(duration) = self.frame_duration() else {
+            self.anim_idx_smooth += elapsed * self.anim_spd;
+            if self.anim_idx_smooth > 1.0 {
+                self.next_frame();
+                self.anim_idx_smooth = 0.0;
+            }
+            return;
+        };
+        self.anim_idx_smooth += elapsed / duration;
+        if self.anim_idx_smooth >= 1.0 {
+            // Carry the leftover time into the next frame so timed frames stay on schedule
+            let leftover = (self.anim_idx_smooth - 1.0) * duration;
+            self.next_frame();
+            let spd = self.frame_duration().map_or(self.anim_spd, |duration| 1.0 / duration);
+            self.anim_idx_smooth = (leftover * spd).min(1.0);
+        }
+    }
+
+    fn frame_duration(&self) -> Option<f64> {
+        self.frames.get(self.anim_idx)
+            .and_then(|frame| frame.duration)
+            .filter(|duration| *duration > 0.0)
+    }
+
+    fn next_frame(&mut self) {
+        let last = self.frames.len().saturating_sub(1);
+        match self.anim_dir {
+            AnimDirection::Forward => {
+                self.anim_idx = if self.anim_idx >= last { 0 } else { self.anim_idx + 1 };
+            }, AnimDirection::Reverse => {
+                self.anim_idx = if self.anim_idx == 0 { last } else { self.anim_idx - 1 };
+            }, AnimDirection::PingPong => {
+                if last == 0 {
+                    return;
+                }
+                if self.anim_backwards && self.anim_idx == 0 {
+                    self.anim_backwards = false;
+                } else if !self.anim_backwards && self.anim_idx >= last {
+                    self.anim_backwards = true;
+                }
+                if self.anim_backwards {
+                    self.anim_idx -= 1;
+                } else {
+                    self.anim_idx += 1;
+                }
             }
-            self.anim_idx_smooth = 0.0;
         }
     }
 
+    /// Scale, flip, and rotate a collider the same way the current frame would be drawn. SDL
+    /// flips and rotates around the middle of the frame, so the collider does too
+    pub fn transform_collider(&self, collider: &CollisionShape) -> CollisionShape {
+        let collider = collider.scaled(self.scale);
+        let Some(frame) = self.frames.get(self.anim_idx) else {
+            return collider;
+        };
+        let size = (frame.size.0 as f64 * self.scale.0, frame.size.1 as f64 * self.scale.1);
+        let base_scale = (
+            frame.size.0 as f64 / frame.clip.w as f64,
+            frame.size.1 as f64 / frame.clip.h as f64
+        );
+        let pivot = (
+            size.0 / 2.0 - self.origin.0 as f64 * base_scale.0 * self.scale.0,
+            size.1 / 2.0 - self.origin.1 as f64 * base_scale.1 * self.scale.1
+        );
+        collider.flipped(self.flip, pivot).rotated(self.angle, pivot)
+    }
+
     pub fn render(
-            &self, cnv: &mut Canvas<Window>, imgs: &HashMap<Img, Image>,
+            &self, cnv: &mut Canvas<Window>, cam: &Camera, imgs: &HashMap<Img, Image>,
             pos: (i32, i32)) -> Result<(), String> {
-        self.frames[self.anim_idx].render(
-            cnv, imgs, pos, self.origin, self.scale, self.angle, self.flip
-        )
+        self.frames[self.anim_idx].render(cnv, cam, imgs, &FrameTransform {
+            pos,
+            origin: self.origin,
+            scale: self.scale,
+            angle: self.angle,
+            flip: self.flip
+        })
     }
 }
 
@@ -0,0 +1,334 @@
+//! Optional rigid body physics. Give an object a Body and the room moves it for you, with gravity,
+//! bouncing, and friction against other bodies, solid objects, and solid tiles
+
+use std::collections::HashSet;
+use crate::{
+    collision::{
+        Contact, SpatialHash
+    }, obj::GameObject,
+    tilemap::Tilemap,
+    IndexRestriction
+};
+
+/// How many times the contacts are solved each step. More is stabler when things are stacked
+const SOLVER_ITERATIONS: usize = 8;
+
+/// Fraction of the overlap fixed each time bodies are pushed apart (instead of all at once, which
+/// jitters)
+const CORRECTION_PERCENT: f64 = 0.4;
+
+/// Overlap that's left alone so resting bodies don't jitter from pushing apart every step
+const CORRECTION_SLOP: f64 = 0.05;
+
+/// Bodies hitting slower than gravity can speed them up in one step (plus this much) don't bounce,
+/// so bouncy things can still come to rest
+const RESTING_SPEED: f64 = 1.0;
+
+/// How a body moves
+///
+/// - Static: never moves, like the ground
+/// - Dynamic: moved by gravity, forces, and collisions
+/// - Kinematic: moves by its velocity and pushes dynamic bodies, but nothing pushes back, like a
+///   moving platform
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BodyKind {
+    Static,
+    Dynamic,
+    Kinematic
+}
+
+/// Physical properties of an object. Set GameObjectState::body to have the room simulate it
+///
+/// - kind: BodyKind
+/// - vel: (f64, f64) (pixels per second)
+/// - mass: f64 (only matters relative to other bodies)
+/// - gravity_scale: f64 (how much of the room's gravity applies, 0 to float)
+/// - friction: f64 (0 is ice. Two bodies use the geometric mean of theirs)
+/// - restitution: f64 (bounciness, 0 stops dead and 1 bounces back at full speed. Two bodies use
+///   the bigger of theirs)
+/// - force: (f64, f64) (added up with apply_force and cleared after every step)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body {
+    pub kind: BodyKind,
+    pub vel: (f64, f64),
+    pub mass: f64,
+    pub gravity_scale: f64,
+    pub friction: f64,
+    pub restitution: f64,
+    pub force: (f64, f64)
+}
+
+impl Body {
+    pub fn new(kind: BodyKind) -> Self {
+        Self {
+            kind,
+            vel: (0.0, 0.0),
+            mass: 1.0,
+            gravity_scale: 1.0,
+            friction: 0.2,
+            restitution: 0.0,
+            force: (0.0, 0.0)
+        }
+    }
+
+    pub fn with_vel(mut self, vel: (f64, f64)) -> Self {
+        self.vel = vel;
+        self
+    }
+
+    pub fn with_mass(mut self, mass: f64) -> Self {
+        self.mass = mass;
+        self
+    }
+
+    pub fn with_gravity_scale(mut self, gravity_scale: f64) -> Self {
+        self.gravity_scale = gravity_scale;
+        self
+    }
+
+    pub fn with_friction(mut self, friction: f64) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f64) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    /// 0 for anything that can't be pushed around
+    pub fn inv_mass(&self) -> f64 {
+        if self.kind == BodyKind::Dynamic && self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            0.0
+        }
+    }
+
+    /// Instantly change the velocity, e.g. for a jump or an explosion
+    pub fn apply_impulse(&mut self, impulse: (f64, f64)) {
+        let inv_mass = self.inv_mass();
+        self.vel.0 += impulse.0 * inv_mass;
+        self.vel.1 += impulse.1 * inv_mass;
+    }
+
+    /// Push the body over the next step, e.g. for thrusters or wind
+    pub fn apply_force(&mut self, force: (f64, f64)) {
+        self.force.0 += force.0;
+        self.force.1 += force.1;
+    }
+}
+
+/// What the solver needs to know about a body, copied out so any two can be changed at once. Solid
+/// objects without a body and solid tiles are in here too as bodies that never move (idx is None
+/// for tiles, and layer is only used by tiles since objects have their own)
+struct SimBody {
+    idx: Option<usize>,
+    layer: u32,
+    pos: (f64, f64),
+    vel: (f64, f64),
+    inv_mass: f64,
+    friction: f64,
+    restitution: f64
+}
+
+/// Move every object with a body forward by delta and resolve the collisions between them and
+/// anything solid. The room calls this after its objects update, and everything happens in object
+/// order, so the same inputs always give the same results
+pub(crate) fn step<Img, Snd, Fnt, Spr, Rm, Data>(
+        objs: &mut [GameObject<Img, Snd, Fnt, Spr, Rm, Data>], tilemaps: &[Tilemap<Img>],
+        gravity: (f64, f64), delta: f64, cell_size: u32) where
+            Spr: IndexRestriction,
+            Img: IndexRestriction,
+            Snd: IndexRestriction,
+            Fnt: IndexRestriction,
+            Rm: IndexRestriction,
+            Data: Clone {
+    let mut bodies = Vec::new();
+    for (idx, obj) in objs.iter_mut().enumerate() {
+        let state = obj.state_mut();
+        let Some(body) = state.body.as_mut() else {
+            continue;
+        };
+        let inv_mass = body.inv_mass();
+        match body.kind {
+            BodyKind::Static => body.vel = (0.0, 0.0),
+            BodyKind::Dynamic => {
+                body.vel.0 += (gravity.0 * body.gravity_scale + body.force.0 * inv_mass) * delta;
+                body.vel.1 += (gravity.1 * body.gravity_scale + body.force.1 * inv_mass) * delta;
+            }, BodyKind::Kinematic => {}
+        }
+        body.force = (0.0, 0.0);
+        state.pos.0 += body.vel.0 * delta;
+        state.pos.1 += body.vel.1 * delta;
+        bodies.push(SimBody {
+            idx: Some(idx),
+            layer: state.layer,
+            pos: state.pos,
+            vel: body.vel,
+            inv_mass,
+            friction: body.friction,
+            restitution: body.restitution
+        });
+    }
+
+    let mut colliders = bodies.iter()
+        .filter_map(|body| body.idx)
+        .map(|idx| objs[idx].state().world_collider())
+        .collect::<Vec<_>>();
+
+    // Solid objects without a body and solid tiles act like static bodies with the default
+    // friction and bounciness
+    let solid = Body::new(BodyKind::Static);
+    let fixed = |idx: Option<usize>, layer: u32, pos: (f64, f64)| SimBody {
+        idx,
+        layer,
+        pos,
+        vel: (0.0, 0.0),
+        inv_mass: 0.0,
+        friction: solid.friction,
+        restitution: solid.restitution
+    };
+    for (idx, obj) in objs.iter().enumerate() {
+        let state = obj.state();
+        if state.body.is_none() && state.solid && !state.trigger {
+            bodies.push(fixed(Some(idx), state.layer, state.pos));
+            colliders.push(state.world_collider());
+        }
+    }
+    let mut tiles = HashSet::new();
+    for i in 0..bodies.len() {
+        let Some(idx) = bodies[i].idx.filter(|_| bodies[i].inv_mass > 0.0) else {
+            continue;
+        };
+        let state = objs[idx].state();
+        if state.trigger {
+            continue;
+        }
+        for (map, tilemap) in tilemaps.iter().enumerate() {
+            for (cell, collider) in tilemap.solids_overlapping_masked(&colliders[i], state.mask) {
+                if tiles.insert((map, cell)) {
+                    bodies.push(fixed(None, tilemap.solid_layers(cell), collider.center()));
+                    colliders.push(collider);
+                }
+            }
+        }
+    }
+
+    // Find the touching pairs where at least one side can be pushed
+    let mut grid = SpatialHash::new(cell_size);
+    for (i, collider) in colliders.iter().enumerate() {
+        grid.insert(i, &collider.aabb());
+    }
+    let mut pairs = grid.pairs().into_iter().collect::<Vec<_>>();
+    pairs.sort();
+    let mut contacts = Vec::new();
+    for (a, b) in pairs {
+        if bodies[a].inv_mass + bodies[b].inv_mass == 0.0 {
+            continue;
+        }
+        let states = (
+            bodies[a].idx.map(|idx| objs[idx].state()), bodies[b].idx.map(|idx| objs[idx].state())
+        );
+        let ignored = match states {
+            (Some(state_a), Some(state_b)) => state_a.trigger || state_b.trigger
+                || !(state_a.interacts_with(state_b) || state_b.interacts_with(state_a)),
+
+            // Tiles have no mask of their own, so it's up to the body
+            (Some(state), None) => state.trigger || state.mask & bodies[b].layer == 0,
+            (None, Some(state)) => state.trigger || state.mask & bodies[a].layer == 0,
+            (None, None) => true
+        };
+        if ignored {
+            continue;
+        }
+        if let Some(contact) = colliders[a].contact(&colliders[b]) {
+            contacts.push((a, b, contact));
+        }
+    }
+
+    let resting_speed = (gravity.0 * gravity.0 + gravity.1 * gravity.1).sqrt() * delta
+        + RESTING_SPEED;
+    for _ in 0..SOLVER_ITERATIONS {
+        for (a, b, contact) in contacts.iter() {
+            resolve_velocity(&mut bodies, *a, *b, contact, resting_speed);
+        }
+    }
+    // Push overlapping bodies apart, checking again each time since pushing one apart from
+    // something can push it into something else
+    let start_pos = bodies.iter().map(|body| body.pos).collect::<Vec<_>>();
+    for _ in 0..SOLVER_ITERATIONS {
+        for (a, b, _) in contacts.iter() {
+            let (a, b) = (*a, *b);
+            let moved = |i: usize| colliders[i].moved((
+                bodies[i].pos.0 - start_pos[i].0,
+                bodies[i].pos.1 - start_pos[i].1
+            ));
+            let Some(contact) = moved(a).contact(&moved(b)) else {
+                continue;
+            };
+            let (inv_a, inv_b) = (bodies[a].inv_mass, bodies[b].inv_mass);
+            let amount = (contact.depth - CORRECTION_SLOP).max(0.0) / (inv_a + inv_b)
+                * CORRECTION_PERCENT;
+            bodies[a].pos.0 -= contact.normal.0 * amount * inv_a;
+            bodies[a].pos.1 -= contact.normal.1 * amount * inv_a;
+            bodies[b].pos.0 += contact.normal.0 * amount * inv_b;
+            bodies[b].pos.1 += contact.normal.1 * amount * inv_b;
+        }
+    }
+
+    for sim in bodies {
+        let Some(idx) = sim.idx else {
+            continue;
+        };
+        let state = objs[idx].state_mut();
+        if let Some(body) = state.body.as_mut() {
+            body.vel = sim.vel;
+            state.pos = sim.pos;
+        }
+    }
+}
+
+/// Stop two bodies moving into each other with a bounce, then slow their sliding with friction
+fn resolve_velocity(
+        bodies: &mut [SimBody], a: usize, b: usize, contact: &Contact, resting_speed: f64) {
+    let (body_a, body_b) = (&bodies[a], &bodies[b]);
+    let total_inv_mass = body_a.inv_mass + body_b.inv_mass;
+    let normal = contact.normal;
+    let rel_vel = (body_b.vel.0 - body_a.vel.0, body_b.vel.1 - body_a.vel.1);
+    let vel_along_normal = rel_vel.0 * normal.0 + rel_vel.1 * normal.1;
+    if vel_along_normal > 0.0 {
+        // Already moving apart
+        return;
+    }
+    let restitution = if -vel_along_normal > resting_speed {
+        body_a.restitution.max(body_b.restitution)
+    } else {
+        0.0
+    };
+    let impulse = -(1.0 + restitution) * vel_along_normal / total_inv_mass;
+
+    let tangent = (
+        rel_vel.0 - normal.0 * vel_along_normal,
+        rel_vel.1 - normal.1 * vel_along_normal
+    );
+    let tangent_len = (tangent.0 * tangent.0 + tangent.1 * tangent.1).sqrt();
+    let friction = (body_a.friction * body_b.friction).sqrt();
+    let (friction_impulse, tangent) = if tangent_len > 0.0 {
+        let tangent = (tangent.0 / tangent_len, tangent.1 / tangent_len);
+        let slide = -(rel_vel.0 * tangent.0 + rel_vel.1 * tangent.1) / total_inv_mass;
+        (slide.clamp(-impulse * friction, impulse * friction), tangent)
+    } else {
+        (0.0, (0.0, 0.0))
+    };
+
+    let total = (
+        normal.0 * impulse + tangent.0 * friction_impulse,
+        normal.1 * impulse + tangent.1 * friction_impulse
+    );
+    let (inv_a, inv_b) = (body_a.inv_mass, body_b.inv_mass);
+    bodies[a].vel.0 -= total.0 * inv_a;
+    bodies[a].vel.1 -= total.1 * inv_a;
+    bodies[b].vel.0 += total.0 * inv_b;
+    bodies[b].vel.1 += total.1 * inv_b;
+}
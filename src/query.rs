@@ -0,0 +1,196 @@
+//! GameMaker-style ways to find objects (instance_nearest, instance_number, etc.) without looping
+//! over every object by hand
+
+use std::mem::discriminant;
+use sdl2::rect::Rect;
+use crate::{
+    collision::{
+        CollisionShape, Hit
+    },
+    obj::{
+        GameObject, GameObjectState, ObjectId, Others
+    }, room::Room,
+    tilemap::Tilemap,
+    IndexRestriction
+};
+
+/// What Query::query_iter gives back
+pub type QueryIter<'a, Img, Snd, Fnt, Spr, Rm, Data> = Box<
+    dyn Iterator<Item = &'a GameObject<Img, Snd, Fnt, Spr, Rm, Data>> + 'a
+>;
+
+/// An object a ray or shape ran into, along with where and how
+pub type ObjectHit<'a, Img, Snd, Fnt, Spr, Rm, Data> = (
+    &'a GameObject<Img, Snd, Fnt, Spr, Rm, Data>, Hit
+);
+
+/// Searches over a group of objects. Works on a Room, the others passed to update, and any slice
+/// of objects.
+///
+/// "Kind" means which variant of your custom Data enum an object has, ignoring what's inside it,
+/// so of_kind(&Data::Brick(0)) finds every brick no matter its number
+pub trait Query<Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    /// Every object the query searches through
+    fn query_iter(&self) -> QueryIter<'_, Img, Snd, Fnt, Spr, Rm, Data>;
+
+    /// Tilemaps to search along with the objects, if there are any
+    fn tilemaps(&self) -> &[Tilemap<Img>] {
+        &[]
+    }
+
+    fn find_id(&self, id: ObjectId) -> Option<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        self.query_iter().find(|obj| obj.state().id == id)
+    }
+
+    /// Every object whose state matches
+    fn filter<F>(
+            &self, pred: F) -> Vec<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> where
+                F: Fn(&GameObjectState<Img, Spr, Data>) -> bool {
+        self.query_iter().filter(|obj| pred(obj.state())).collect()
+    }
+
+    fn named(&self, name: &str) -> Vec<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        self.filter(|state| state.name == name)
+    }
+
+    fn of_kind(
+            &self, kind: &Data) -> Vec<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        let kind = discriminant(kind);
+        self.filter(|state| discriminant(&state.custom) == kind)
+    }
+
+    /// How many objects of a kind there are, like GameMaker's instance_number
+    fn count_kind(&self, kind: &Data) -> usize {
+        let kind = discriminant(kind);
+        self.query_iter().filter(|obj| discriminant(&obj.state().custom) == kind).count()
+    }
+
+    /// The closest object to a point out of the ones whose state matches
+    fn nearest<F>(
+            &self, pos: (f64, f64),
+            pred: F) -> Option<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> where
+                F: Fn(&GameObjectState<Img, Spr, Data>) -> bool {
+        self.query_iter()
+            .map(|obj| (obj, obj.state()))
+            .filter(|(_, state)| pred(state))
+            .map(|(obj, state)| {
+                let dist = (state.pos.0 - pos.0).powi(2) + (state.pos.1 - pos.1).powi(2);
+                (obj, dist)
+            }).min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(obj, _)| obj)
+    }
+
+    /// The closest object of a kind to a point, like GameMaker's instance_nearest
+    fn nearest_of_kind(
+            &self, pos: (f64, f64),
+            kind: &Data) -> Option<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        let kind = discriminant(kind);
+        self.nearest(pos, |state| discriminant(&state.custom) == kind)
+    }
+
+    /// Every object whose collider overlaps the shape (given in room coordinates)
+    fn overlapping(
+            &self, shape: &CollisionShape) -> Vec<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        self.filter(|state| state.world_collider().collides_with(shape))
+    }
+
+    /// The first object a ray hits out of the ones whose state matches, like GameMaker's
+    /// collision_line but with where and how it was hit. The ray starts at origin and goes up to
+    /// max_dist in dir (which doesn't need to be normalized)
+    fn raycast<F>(
+            &self, origin: (f64, f64), dir: (f64, f64), max_dist: f64,
+            pred: F) -> Option<ObjectHit<'_, Img, Snd, Fnt, Spr, Rm, Data>> where
+                F: Fn(&GameObjectState<Img, Spr, Data>) -> bool {
+        self.query_iter()
+            .filter(|obj| pred(obj.state()))
+            .filter_map(|obj| {
+                obj.state().world_collider().raycast(origin, dir, max_dist).map(|hit| (obj, hit))
+            }).min_by(|(_, a), (_, b)| a.dist.total_cmp(&b.dist))
+    }
+
+    /// The first object a shape would hit moving in a straight line from one point to another.
+    /// The shape is relative to the point, like an object's collider is to its position
+    fn shape_cast<F>(
+            &self, shape: &CollisionShape, from: (f64, f64), to: (f64, f64),
+            pred: F) -> Option<ObjectHit<'_, Img, Snd, Fnt, Spr, Rm, Data>> where
+                F: Fn(&GameObjectState<Img, Spr, Data>) -> bool {
+        let shape = shape.moved(from);
+        let motion = (to.0 - from.0, to.1 - from.1);
+        self.query_iter()
+            .filter(|obj| pred(obj.state()))
+            .filter_map(|obj| {
+                shape.cast(motion, &obj.state().world_collider()).map(|hit| (obj, hit))
+            }).min_by(|(_, a), (_, b)| a.dist.total_cmp(&b.dist))
+    }
+
+    /// Every object whose collider overlaps a rectangle, like GameMaker's collision_rectangle
+    fn in_rect(
+            &self, rect: Rect) -> Vec<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        self.overlapping(&CollisionShape::Rect {
+            center: (
+                rect.x() as f64 + rect.width() as f64 / 2.0,
+                rect.y() as f64 + rect.height() as f64 / 2.0
+            ), size: (rect.width() as f64, rect.height() as f64)
+        })
+    }
+
+    /// Every object whose collider overlaps a circle, like GameMaker's collision_circle
+    fn in_circle(
+            &self, center: (f64, f64),
+            radius: f64) -> Vec<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        self.overlapping(&CollisionShape::Circle { center, radius })
+    }
+}
+
+impl<Img, Snd, Fnt, Spr, Rm, Data> Query<Img, Snd, Fnt, Spr, Rm, Data>
+    for [GameObject<Img, Snd, Fnt, Spr, Rm, Data>] where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    fn query_iter(&self) -> QueryIter<'_, Img, Snd, Fnt, Spr, Rm, Data> {
+        Box::new(self.iter())
+    }
+}
+
+impl<Img, Snd, Fnt, Spr, Rm, Data> Query<Img, Snd, Fnt, Spr, Rm, Data>
+    for Room<Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    fn query_iter(&self) -> QueryIter<'_, Img, Snd, Fnt, Spr, Rm, Data> {
+        Box::new(self.objs.iter())
+    }
+
+    fn tilemaps(&self) -> &[Tilemap<Img>] {
+        &self.tilemaps
+    }
+}
+
+impl<Img, Snd, Fnt, Spr, Rm, Data> Query<Img, Snd, Fnt, Spr, Rm, Data>
+    for Others<'_, Img, Snd, Fnt, Spr, Rm, Data> where
+        Spr: IndexRestriction,
+        Img: IndexRestriction,
+        Snd: IndexRestriction,
+        Fnt: IndexRestriction,
+        Rm: IndexRestriction,
+        Data: Clone {
+    fn query_iter(&self) -> QueryIter<'_, Img, Snd, Fnt, Spr, Rm, Data> {
+        Box::new(self.iter())
+    }
+
+    fn tilemaps(&self) -> &[Tilemap<Img>] {
+        self.tilemaps
+    }
+}
@@ -1,7 +1,10 @@
 //! Load Images, Fonts, and Sounds (i.e. resources)
 
-use std::io::{
-    Read, Seek, SeekFrom
+use std::{
+    collections::HashMap,
+    io::{
+        Read, Seek, SeekFrom
+    }
 };
 use image::{
     ImageBuffer, Rgba
@@ -22,6 +25,14 @@ use sdl2::{
     }
 };
 
+/// Everything the app loaded, lent to render functions together
+pub struct Resources<'a, Img, Snd, Fnt> {
+    pub imgs: &'a HashMap<Img, Image<'a>>,
+    pub snds: &'a HashMap<Snd, Sound<'a>>,
+    pub fonts: &'a HashMap<Fnt, Font<'a, 'a>>,
+    pub creator: &'a TextureCreator<WindowContext>
+}
+
 /// Container for textures with functionality for drawing to screen. This is a "resource" and does
 /// not go with GameObjects
 pub struct Image<'a> {
@@ -72,6 +83,7 @@ impl<'a, 'b> Font<'a, 'b> {
         font.map_err(|e| e.to_string())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn render(
             &self, cnv: &mut Canvas<Window>, creator: &TextureCreator<WindowContext>,
             msg: &str, color: &Color, pos: (i32, i32), angle: f64,
@@ -1,23 +1,50 @@
 //! Collect game objects to maintain a type of game object
 
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{
+        HashMap, HashSet
+    }
+};
 use sdl2::{
     event::Event,
-    render::{
-        Canvas, TextureCreator
-    }, video::{
-        Window, WindowContext
-    }
+    pixels::Color,
+    rect::Rect,
+    render::Canvas,
+    video::Window
 };
 use crate::{
+    camera::{
+        Camera, Viewport
+    }, collision::SpatialHash,
     obj::{
-        ControlObjectBehavior, GameObjectBehavior
-    }, collision::CollisionShape,
-    res::{
-        Font, Image, Sound
-    }, IndexRestriction
+        ControlObject, GameObject, ObjectId, Others
+    }, input::Input,
+    physics,
+    res::Resources,
+    tilemap::Tilemap,
+    IndexRestriction
 };
 
+/// Every room in a game, looked up by your room enum
+pub type Rooms<Img, Snd, Fnt, Spr, Rm, Data> = HashMap<Rm, Room<Img, Snd, Fnt, Spr, Rm, Data>>;
+
+/// Default size of the collision grid cells
+const DEF_CELL_SIZE: u32 = 128;
+
+/// A "scene" of your game: the objects in it and how they're viewed.
+///
+/// Every object gets an ObjectId when it's added. Anything pushed onto objs directly gets one at
+/// the start of the next update.
+///
+/// With no viewports the room's camera fills the screen. Otherwise the room is drawn once per
+/// viewport instead, and the room's own camera is unused.
+///
+/// Objects are drawn from the highest depth to the lowest. With y_sort, objects at the same depth
+/// are also drawn from the top of the room down, so things lower on screen overlap things behind
+/// them like in a top-down game. Anything still tied is drawn in the order it's in objs.
+///
+/// A room with a bg_color fills the screen with it before drawing, instead of using the app's
 #[derive(Clone)]
 pub struct Room<Img, Snd, Fnt, Spr, Rm, Data> where
         Spr: IndexRestriction,
@@ -26,8 +53,17 @@ pub struct Room<Img, Snd, Fnt, Spr, Rm, Data> where
         Fnt: IndexRestriction,
         Rm: IndexRestriction,
         Data: Clone {
-    pub objs: Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
-    pub persistant: bool
+    pub objs: Vec<GameObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+    pub persistant: bool,
+    pub cam: Camera,
+    pub viewports: Vec<Viewport>,
+    pub tilemaps: Vec<Tilemap<Img>>,
+    pub y_sort: bool,
+    pub cell_size: u32,
+    pub gravity: (f64, f64),
+    pub bg_color: Option<Color>,
+    next_id: u64,
+    touching: HashSet<(ObjectId, ObjectId)>
 }
 
 impl<Img, Snd, Fnt, Spr, Rm, Data> Room<Img, Snd, Fnt, Spr, Rm, Data> where
@@ -38,11 +74,172 @@ impl<Img, Snd, Fnt, Spr, Rm, Data> Room<Img, Snd, Fnt, Spr, Rm, Data> where
         Rm: IndexRestriction,
         Data: Clone {
     pub fn new(
-            objs: Vec<Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>,
+            objs: Vec<GameObject<Img, Snd, Fnt, Spr, Rm, Data>>,
             persistant: bool) -> Self {
-        Self {
+        let mut room = Self {
             objs,
-            persistant
+            persistant,
+            cam: Camera::new(),
+            viewports: Vec::new(),
+            tilemaps: Vec::new(),
+            y_sort: false,
+            cell_size: DEF_CELL_SIZE,
+            gravity: (0.0, 0.0),
+            bg_color: None,
+            next_id: 1,
+            touching: HashSet::new()
+        };
+        room.assign_ids();
+        room
+    }
+
+    /// Add an object to the room, giving it a new id
+    pub fn add(
+            &mut self,
+            mut obj: GameObject<Img, Snd, Fnt, Spr, Rm, Data>) -> ObjectId {
+        let id = ObjectId(self.next_id);
+        self.next_id += 1;
+        obj.state_mut().id = id;
+        self.objs.push(obj);
+        id
+    }
+
+    pub fn get(
+            &self,
+            id: ObjectId) -> Option<&GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        self.position(id).map(|idx| &self.objs[idx])
+    }
+
+    pub fn get_mut(
+            &mut self, id: ObjectId) -> Option<
+                &mut GameObject<Img, Snd, Fnt, Spr, Rm, Data>
+            > {
+        self.position(id).map(|idx| &mut self.objs[idx])
+    }
+
+    /// Take an object out of the room, returning it if it was there
+    pub fn remove(
+            &mut self,
+            id: ObjectId) -> Option<GameObject<Img, Snd, Fnt, Spr, Rm, Data>> {
+        let idx = self.position(id)?;
+        Some(self.objs.remove(idx))
+    }
+
+    /// Where an object is in objs. Ids go up as objects are added, so they're normally in order
+    /// and can be binary searched. Only objects moved around in objs by hand need the slow way
+    fn position(&self, id: ObjectId) -> Option<usize> {
+        self.objs.binary_search_by_key(&id, |obj| obj.state().id).ok()
+            .or_else(|| self.objs.iter().position(|obj| obj.state().id == id))
+    }
+
+    /// Deliver a message to an object right away. Returns false if there's no object with that id
+    pub fn send(&mut self, from: ObjectId, to: ObjectId, msg: &Data) -> bool {
+        if let Some(obj) = self.get_mut(to) {
+            obj.on_message(from, msg);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Give an id to every object that doesn't have one yet
+    fn assign_ids(&mut self) {
+        for obj in self.objs.iter_mut() {
+            if obj.state().id == ObjectId::NONE {
+                obj.state_mut().id = ObjectId(self.next_id);
+                self.next_id += 1;
+            }
+        }
+    }
+
+    pub fn with_camera(mut self, cam: Camera) -> Self {
+        self.cam = cam;
+        self
+    }
+
+    pub fn with_y_sort(mut self, y_sort: bool) -> Self {
+        self.y_sort = y_sort;
+        self
+    }
+
+    /// Size of the grid cells used to find objects that are close enough to collide. Somewhere
+    /// around the size of a typical object works best
+    pub fn with_cell_size(mut self, cell_size: u32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    pub fn with_tilemap(mut self, tilemap: Tilemap<Img>) -> Self {
+        self.tilemaps.push(tilemap);
+        self
+    }
+
+    /// Acceleration applied to every dynamic body, in pixels per second squared. Down is +y
+    pub fn with_gravity(mut self, gravity: (f64, f64)) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    pub fn with_bg_color(mut self, bg_color: Color) -> Self {
+        self.bg_color = Some(bg_color);
+        self
+    }
+
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewports.push(viewport);
+        self
+    }
+
+    /// Where the object a camera follows is, if it's in the room
+    fn cam_target(&self, cam: &Camera) -> Option<(f64, f64)> {
+        self.get(cam.follow?).map(|obj| obj.state().pos)
+    }
+
+    /// Put the cameras right on their targets, skipping any lag
+    pub fn snap_camera(&mut self) {
+        let target = self.cam_target(&self.cam);
+        self.cam.snap_to(target);
+        for i in 0..self.viewports.len() {
+            let target = self.cam_target(&self.viewports[i].cam);
+            self.viewports[i].cam.snap_to(target);
+        }
+    }
+
+    fn update_cameras(&mut self, delta: f64) {
+        let target = self.cam_target(&self.cam);
+        self.cam.update(delta, target);
+        for i in 0..self.viewports.len() {
+            let target = self.cam_target(&self.viewports[i].cam);
+            self.viewports[i].cam.update(delta, target);
+        }
+    }
+
+    /// Indices into objs in the order they should be drawn
+    pub fn render_order(&self) -> Vec<usize> {
+        let keys = self.objs.iter().map(|obj| {
+            let state = obj.state();
+            (state.depth, state.pos.1)
+        }).collect::<Vec<_>>();
+        let mut order = (0..self.objs.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| {
+            let (a, b) = (keys[*a], keys[*b]);
+            let by_depth = b.0.cmp(&a.0);
+            if self.y_sort {
+                by_depth.then(a.1.total_cmp(&b.1))
+            } else {
+                by_depth
+            }
+        });
+        order
+    }
+
+    /// Convert a screen position into the room through whichever view it lands in
+    pub fn screen_to_world(&self, pos: (f64, f64)) -> (f64, f64) {
+        if self.viewports.is_empty() {
+            self.cam.screen_to_world(pos)
+        } else {
+            self.viewports.iter().rev().find_map(|viewport| viewport.screen_to_world(pos))
+                .unwrap_or_else(|| self.viewports[0].cam.screen_to_world(pos))
         }
     }
 
@@ -53,85 +250,163 @@ impl<Img, Snd, Fnt, Spr, Rm, Data> Room<Img, Snd, Fnt, Spr, Rm, Data> where
     }
 
     pub fn update(
-            &mut self, delta: f64,
-            ctl_objs: &Vec<Box<dyn ControlObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>>>
+            &mut self, delta: f64, input: &mut Input,
+            ctl_objs: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>
             ) -> Option<Rm> {
-        let others = self.objs.clone();
+        self.assign_ids();
+
+        // Remember where everything was so rendering can interpolate towards the new positions
+        for obj in self.objs.iter_mut() {
+            let state = obj.state_mut();
+            state.prev_pos = state.pos;
+        }
+
         let mut ret = None;
         let mut objs = Vec::new();
         let mut to_remove = Vec::new();
-        for (i, obj) in self.objs.iter_mut().enumerate() {
-            let check_ret = obj.update(delta, ctl_objs, &others);
-            if check_ret.0.is_some() && ret.is_none() && objs.len() < 1 {
-                (ret, objs) = check_ret;
-            } else if check_ret.1.len() > 0 && objs.len() < 1 && ret.is_none() {
+        for i in 0..self.objs.len() {
+            // Lend every other object to this one instead of copying them
+            let (before, rest) = self.objs.split_at_mut(i);
+            let (obj, after) = rest.split_first_mut().unwrap();
+            let others = Others::new(before, after).with_tilemaps(&self.tilemaps);
+            let check_ret = obj.update(delta, input, ctl_objs, &others);
+            if ret.is_none() && objs.is_empty()
+                    && (check_ret.0.is_some() || !check_ret.1.is_empty()) {
                 (ret, objs) = check_ret;
             }
             if obj.should_remove() {
                 to_remove.push(i);
             }
         }
-        let mut removed = 0;
-        for i in to_remove.iter() {
+
+        // Hand out messages now that everyone's done updating. They're collected before anything
+        // is removed so an object's last words still get delivered
+        let mut msgs = Vec::new();
+        for obj in self.objs.iter_mut() {
+            let from = obj.state().id;
+            for (to, msg) in obj.take_messages() {
+                msgs.push((from, to, msg));
+            }
+        }
+        for (removed, i) in to_remove.iter().enumerate() {
             self.objs.remove(*i - removed);
-            removed += 1;
         }
-        let others = self.objs.clone();
-        for obj in self.objs.iter_mut() {
-            let mut collider = obj.state().collider;
-
-            // Adjust collider for position
-            match collider {
-                CollisionShape::Rect { ref mut center, .. } => {
-                    center.0 += obj.state().pos.0 as i32;
-                    center.1 += obj.state().pos.1 as i32;
-                }, CollisionShape::Circle { ref mut center, .. } => {
-                    center.0 += obj.state().pos.0 as i32;
-                    center.1 += obj.state().pos.1 as i32;
-                }, CollisionShape::Polygon { ref mut center, .. } => {
-                    center.0 += obj.state().pos.0 as i32;
-                    center.1 += obj.state().pos.1 as i32;
-                }
+        for (from, to, msg) in msgs.iter() {
+            self.send(*from, *to, msg);
+        }
+
+        physics::step(&mut self.objs, &self.tilemaps, self.gravity, delta, self.cell_size);
+
+        // Find every collision first so objects reacting to one can't change what else is hit
+        let colliders = self.objs.iter()
+            .map(|obj| obj.state().world_collider())
+            .collect::<Vec<_>>();
+        let aabbs = colliders.iter().map(|collider| collider.aabb()).collect::<Vec<_>>();
+        let mut grid = SpatialHash::new(self.cell_size);
+        for (i, aabb) in aabbs.iter().enumerate() {
+            grid.insert(i, aabb);
+        }
+        let mut hits = Vec::new();
+        for (i, j) in grid.pairs() {
+            let (state, other) = (self.objs[i].state(), self.objs[j].state());
+            let (i_wants, j_wants) = (state.interacts_with(other), other.interacts_with(state));
+            if !(i_wants || j_wants) || !aabbs[i].overlaps(&aabbs[j]) {
+                continue;
+            }
+            if i_wants && colliders[i].collides_with(&colliders[j]) {
+                hits.push((i, j));
             }
+            if j_wants && colliders[j].collides_with(&colliders[i]) {
+                hits.push((j, i));
+            }
+        }
 
-            for other in others.iter() {
-                if obj.state().name == other.state().name {
-                    continue;
-                }
-
-                let mut other_collider = other.state().collider;
-
-                // Also adjust other collider
-                match other_collider {
-                    CollisionShape::Rect { ref mut center, .. } => {
-                        center.0 += other.state().pos.0 as i32;
-                        center.1 += other.state().pos.1 as i32;
-                    }, CollisionShape::Circle { ref mut center, .. } => {
-                        center.0 += other.state().pos.0 as i32;
-                        center.1 += other.state().pos.1 as i32;
-                    }, CollisionShape::Polygon { ref mut center, .. } => {
-                        center.0 += other.state().pos.0 as i32;
-                        center.1 += other.state().pos.1 as i32;
-                    }
-                }
-
-                if collider.collides_with(&other_collider) {
-                    obj.on_collision(&other);
-                }
+        // Same order as checking every object against every other one
+        hits.sort();
+        let mut touching = HashSet::new();
+        for (i, j) in hits {
+            let (obj, other) = pair_mut(&mut self.objs, i, j);
+            let ids = (obj.state().id, other.state().id);
+            obj.on_collision(other);
+            if self.touching.contains(&ids) {
+                obj.on_collision_stay(other);
+            } else {
+                obj.on_collision_enter(other);
+            }
+            touching.insert(ids);
+        }
+        let mut exits = self.touching.difference(&touching).copied().collect::<Vec<_>>();
+        exits.sort();
+        for (id, other) in exits {
+            if let Some(obj) = self.get_mut(id) {
+                obj.on_collision_exit(other);
             }
         }
-        self.objs.append(&mut objs);
+        self.touching = touching;
+        for obj in objs {
+            self.add(obj);
+        }
+        self.update_cameras(delta);
         ret
     }
 
     pub fn render(
-            &mut self, cnv: &mut Canvas<Window>,
-            imgs: &HashMap<Img, Image>, snds: &HashMap<Snd, Sound>,
-            fonts: &HashMap<Fnt, Font>, creator: &TextureCreator<WindowContext>,
-            elapsed: f64) -> Result<(), String> {
-        cnv.clear();
-        for obj in self.objs.iter_mut() {
-            obj.render(cnv, imgs, snds, fonts, creator, elapsed)?;
+            &mut self, cnv: &mut Canvas<Window>, res: &Resources<Img, Snd, Fnt>,
+            elapsed: f64, alpha: f64) -> Result<(), String> {
+        if let Some(bg_color) = self.bg_color {
+            let old_color = cnv.draw_color();
+            cnv.set_draw_color(bg_color);
+            cnv.clear();
+            cnv.set_draw_color(old_color);
+        } else {
+            cnv.clear();
+        }
+        if self.viewports.is_empty() {
+            let cam = self.cam.lerped(alpha);
+            return self.render_view(cnv, &cam, res, elapsed, alpha);
+        }
+        for i in 0..self.viewports.len() {
+            let viewport = &self.viewports[i];
+            let size = viewport.dest.size();
+            cnv.set_viewport(viewport.dest);
+            cnv.set_clip_rect(Rect::new(0, 0, size.0, size.1));
+            if let Some(bg_color) = viewport.bg_color {
+                let old_color = cnv.draw_color();
+                cnv.set_draw_color(bg_color);
+                cnv.fill_rect(None)?;
+                cnv.set_draw_color(old_color);
+            }
+
+            // Only let the first pass advance animations, otherwise they'd run once per viewport
+            let elapsed = if i == 0 { elapsed } else { 0.0 };
+            let cam = viewport.cam.lerped(alpha);
+            self.render_view(cnv, &cam, res, elapsed, alpha)?;
+        }
+        cnv.set_clip_rect(None);
+        cnv.set_viewport(None);
+        Ok(())
+    }
+
+    /// Draw the tilemaps and objects through one camera, with tile layers slotted in between
+    /// objects by depth (layers go behind objects at the same depth)
+    fn render_view(
+            &mut self, cnv: &mut Canvas<Window>, cam: &Camera, res: &Resources<Img, Snd, Fnt>,
+            elapsed: f64, alpha: f64) -> Result<(), String> {
+        let mut layers = self.tilemaps.iter().enumerate()
+            .flat_map(|(map, tilemap)| {
+                tilemap.layers.iter().enumerate().map(move |(layer, info)| (info.depth, map, layer))
+            }).collect::<Vec<_>>();
+        layers.sort_by_key(|(depth, ..)| Reverse(*depth));
+        let mut layers = layers.into_iter().peekable();
+        for idx in self.render_order() {
+            let depth = self.objs[idx].state().depth;
+            while let Some((_, map, layer)) = layers.next_if(|(behind, ..)| *behind >= depth) {
+                self.tilemaps[map].render_layer(cnv, cam, res.imgs, layer)?;
+            }
+            self.objs[idx].render(cnv, cam, res, elapsed, alpha)?;
+        }
+        for (_, map, layer) in layers {
+            self.tilemaps[map].render_layer(cnv, cam, res.imgs, layer)?;
         }
         Ok(())
     }
@@ -140,15 +415,32 @@ impl<Img, Snd, Fnt, Spr, Rm, Data> Room<Img, Snd, Fnt, Spr, Rm, Data> where
         if self.persistant {
             return;
         }
-        let mut new_obs = Vec::new();
-        for obj in self.objs.iter() {
-            let mut obj_clone = obj.clone();
-            let res = obj_clone.on_reset();
-            if !res {
-                new_obs.push(obj_clone);
+        self.touching.clear();
+        self.objs.retain_mut(|obj| {
+            // Objects keep their id even if on_reset rebuilds their whole state
+            let id = obj.state().id;
+            if obj.on_reset() {
+                return false;
             }
-        }
-        self.objs = new_obs.clone();
+            let state = obj.state_mut();
+            state.id = id;
+
+            // Don't interpolate from wherever the object was before the reset
+            state.prev_pos = state.pos;
+            true
+        });
+        self.snap_camera();
+    }
+}
+
+/// Borrow one item mutably and a different one immutably at the same time
+fn pair_mut<T>(items: &mut [T], i: usize, j: usize) -> (&mut T, &T) {
+    if i < j {
+        let (start, end) = items.split_at_mut(j);
+        (&mut start[i], &end[0])
+    } else {
+        let (start, end) = items.split_at_mut(i);
+        (&mut end[0], &start[j])
     }
 }
 
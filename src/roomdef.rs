@@ -0,0 +1,157 @@
+//! Describe rooms in JSON files instead of code, so levels can change without recompiling.
+//!
+//! A room file looks like this. Everything but an object's kind is optional:
+//!
+//! ```json
+//! {
+//!     "persistant": false,
+//!     "bg_color": "#202030",
+//!     "size": [ 1280, 720 ],
+//!     "gravity": [ 0, 980 ],
+//!     "y_sort": false,
+//!     "objects": [
+//!         { "kind": "Player", "name": "player", "pos": [ 256, 900 ] },
+//!         { "kind": "Brick", "pos": [ 96, 900 ], "depth": 1, "props": { "id": 0 } }
+//!     ]
+//! }
+//! ```
+
+use std::{
+    fs,
+    path::Path
+};
+use sdl2::{
+    pixels::Color,
+    rect::Rect
+};
+use serde::Deserialize;
+use serde_json::{
+    Map, Value
+};
+use crate::{
+    camera::Camera,
+    factory::{
+        parse_color, ObjectDef, ObjectFactory, Property
+    }, room::Room,
+    IndexRestriction
+};
+
+/// Everything in a room file, ready to build into a room
+///
+/// - persistant: bool (see Room::new)
+/// - bg_color: Option<Color> (written "#RRGGBB", or "#AARRGGBB" with the alpha first, the same
+///   way Tiled writes colors)
+/// - size: Option<(u32, u32)> (keeps the camera inside the room)
+/// - gravity: (f64, f64)
+/// - y_sort: bool
+/// - objs: Vec<ObjectDef> (built by the factory, by kind, in this order)
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomDef {
+    pub persistant: bool,
+    pub bg_color: Option<Color>,
+    pub size: Option<(u32, u32)>,
+    pub gravity: (f64, f64),
+    pub y_sort: bool,
+    pub objs: Vec<ObjectDef>
+}
+
+impl RoomDef {
+    /// Read a room file's contents, e.g. from include_bytes!
+    pub fn from_json(bytes: &[u8]) -> Result<Self, String> {
+        let room: JsonRoom = serde_json::from_slice(bytes).map_err(|err| err.to_string())?;
+        let bg_color = match &room.bg_color {
+            Some(color) => Some(parse_color(color).ok_or(format!("Bad bg_color '{}'", color))?),
+            None => None
+        };
+        Ok(Self {
+            persistant: room.persistant,
+            bg_color,
+            size: room.size,
+            gravity: room.gravity.unwrap_or((0.0, 0.0)),
+            y_sort: room.y_sort,
+            objs: room.objects.into_iter()
+                .map(|obj| ObjectDef {
+                    kind: obj.kind,
+                    name: obj.name,
+                    pos: obj.pos,
+                    size: obj.size,
+                    angle: obj.angle,
+                    depth: obj.depth,
+                    shape: None,
+                    props: obj.props.iter()
+                        .filter_map(|(name, val)| Some((name.clone(), Property::from_json(val)?)))
+                        .collect()
+                }).collect()
+        })
+    }
+
+    /// Read a room file while the game is running
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .map_err(|err| format!("Couldn't read '{}': {}", path.display(), err))?;
+        Self::from_json(&bytes)
+    }
+
+    /// Make the room, building every object with the factory. Unlike the Tiled and LDtk loaders,
+    /// an object with no constructor for its kind is an error, since it's most likely a typo
+    pub fn build<Img, Snd, Fnt, Spr, Rm, Data>(
+            &self,
+            factory: &ObjectFactory<Img, Snd, Fnt, Spr, Rm, Data>) -> Result<
+                Room<Img, Snd, Fnt, Spr, Rm, Data>, String
+            > where
+                Spr: IndexRestriction,
+                Img: IndexRestriction,
+                Snd: IndexRestriction,
+                Fnt: IndexRestriction,
+                Rm: IndexRestriction,
+                Data: Clone {
+        let mut objs = Vec::new();
+        for def in self.objs.iter() {
+            let obj = factory.build(def)
+                .ok_or(format!("No constructor registered for '{}'", def.kind))?;
+            objs.push(obj?);
+        }
+        let mut room = Room::new(objs, self.persistant)
+            .with_gravity(self.gravity)
+            .with_y_sort(self.y_sort);
+        if let Some(size) = self.size {
+            room = room.with_camera(Camera::new().with_bounds(Rect::new(0, 0, size.0, size.1)));
+        }
+        room.bg_color = self.bg_color;
+        Ok(room)
+    }
+}
+
+/// How a room file is laid out
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonRoom {
+    #[serde(default, alias = "persistent")]
+    persistant: bool,
+    bg_color: Option<String>,
+    size: Option<(u32, u32)>,
+    gravity: Option<(f64, f64)>,
+    #[serde(default)]
+    y_sort: bool,
+    #[serde(default)]
+    objects: Vec<JsonObject>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonObject {
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    pos: (f64, f64),
+    #[serde(default)]
+    size: (f64, f64),
+    #[serde(default)]
+    angle: f64,
+    #[serde(default)]
+    depth: i32,
+    #[serde(default)]
+    props: Map<String, Value>
+}
@@ -0,0 +1,750 @@
+//! Load maps made in the Tiled editor (https://www.mapeditor.org) as rooms, from either its TMX
+//! or JSON format
+
+use std::{
+    collections::HashMap,
+    io::Read
+};
+use base64::prelude::{
+    Engine, BASE64_STANDARD
+};
+use flate2::read::{
+    GzDecoder, ZlibDecoder
+};
+use roxmltree::{
+    Document, Node
+};
+use sdl2::{
+    pixels::Color,
+    rect::Rect
+};
+use serde::Deserialize;
+use serde_json::Value;
+use crate::{
+    camera::Camera,
+    collision::CollisionShape,
+    factory::{
+        lookup, parse_color, ObjectDef, ObjectFactory, Property
+    }, room::Room,
+    tilemap::{
+        TileFlip, TileLayer, Tilemap, Tileset
+    }, IndexRestriction
+};
+
+/// Tiled keeps flip flags in the top bits of every tile id
+const GID_MASK: u32 = 0x0fff_ffff;
+const FLIP_HORIZONTAL: u32 = 0x8000_0000;
+const FLIP_VERTICAL: u32 = 0x4000_0000;
+const FLIP_DIAGONAL: u32 = 0x2000_0000;
+
+/// How many corners the polygon standing in for a stretched ellipse gets, since colliders can't be
+/// ellipses
+const ELLIPSE_POINTS: usize = 16;
+
+/// Turns Tiled maps into rooms.
+///
+/// Tile layers become tilemaps, one for each tileset a layer uses, drawn with the images
+/// registered for the tilesets' image files. A tile layer with a bool "solid" property is solid.
+/// Layers keep Tiled's draw order through their depth (the top layer is 0, the one below it 1, and
+/// so on) unless they have an int "depth" property. Properties set on a group apply to the layers
+/// in it. Flipped and rotated tiles keep their flips.
+///
+/// Objects are built by the factory using their type (or class) as the kind. Objects with no
+/// constructor for their type are skipped, so they can be used for notes. Round ellipse objects get
+/// a circle for their shape, and stretched ones a polygon close to the ellipse. Only orthogonal,
+/// finite maps are supported
+///
+/// - imgs: HashMap<String, Img> (image for each tileset image file. Matched by the path in the map,
+///   or just the file name if that doesn't match)
+/// - tilesets: HashMap<String, Vec<u8>> (contents of tilesets saved in their own file, TSX or JSON)
+pub struct TiledLoader<Img> where Img: IndexRestriction {
+    pub imgs: HashMap<String, Img>,
+    pub tilesets: HashMap<String, Vec<u8>>
+}
+
+impl<Img> Default for TiledLoader<Img> where Img: IndexRestriction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Img> TiledLoader<Img> where Img: IndexRestriction {
+    pub fn new() -> Self {
+        Self {
+            imgs: HashMap::new(),
+            tilesets: HashMap::new()
+        }
+    }
+
+    pub fn with_image(mut self, source: &str, img: Img) -> Self {
+        self.imgs.insert(source.to_string(), img);
+        self
+    }
+
+    /// Provide an external tileset, e.g. with_tileset("tiles.tsx", include_bytes!("tiles.tsx"))
+    pub fn with_tileset(mut self, source: &str, bytes: &[u8]) -> Self {
+        self.tilesets.insert(source.to_string(), bytes.to_vec());
+        self
+    }
+
+    /// Build a room from a TMX map
+    pub fn load_tmx<Snd, Fnt, Spr, Rm, Data>(
+            &self, bytes: &[u8], factory: &ObjectFactory<Img, Snd, Fnt, Spr, Rm, Data>,
+            persistant: bool) -> Result<Room<Img, Snd, Fnt, Spr, Rm, Data>, String> where
+                Spr: IndexRestriction,
+                Snd: IndexRestriction,
+                Fnt: IndexRestriction,
+                Rm: IndexRestriction,
+                Data: Clone {
+        let text = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+        let doc = Document::parse(text).map_err(|err| err.to_string())?;
+        let map = doc.root_element();
+        if !map.has_tag_name("map") {
+            return Err("Not a TMX map".to_string());
+        }
+        let mut tilesets = Vec::new();
+        for node in map.children().filter(|node| node.has_tag_name("tileset")) {
+            let first_gid = xml_attr(node, "firstgid")?.unwrap_or(1);
+            tilesets.push(match node.attribute("source") {
+                Some(source) => self.external_tileset(source, first_gid)?,
+                None => xml_tileset(node, first_gid)?
+            });
+        }
+        let mut layers = Vec::new();
+        xml_layers(map, true, (0.0, 0.0), &HashMap::new(), &mut layers)?;
+        self.build_room(MapDef {
+            orientation: map.attribute("orientation").unwrap_or("orthogonal").to_string(),
+            infinite: xml_attr::<u8>(map, "infinite")?.unwrap_or(0) != 0,
+            bg_color: map.attribute("backgroundcolor").and_then(parse_color),
+            size: (xml_req(map, "width")?, xml_req(map, "height")?),
+            tile_size: (xml_req(map, "tilewidth")?, xml_req(map, "tileheight")?),
+            tilesets,
+            layers
+        }, factory, persistant)
+    }
+
+    /// Build a room from a JSON map
+    pub fn load_json<Snd, Fnt, Spr, Rm, Data>(
+            &self, bytes: &[u8], factory: &ObjectFactory<Img, Snd, Fnt, Spr, Rm, Data>,
+            persistant: bool) -> Result<Room<Img, Snd, Fnt, Spr, Rm, Data>, String> where
+                Spr: IndexRestriction,
+                Snd: IndexRestriction,
+                Fnt: IndexRestriction,
+                Rm: IndexRestriction,
+                Data: Clone {
+        let map: JsonMap = serde_json::from_slice(bytes).map_err(|err| err.to_string())?;
+        let mut tilesets = Vec::new();
+        for tileset in map.tilesets.iter() {
+            tilesets.push(match &tileset.source {
+                Some(source) => self.external_tileset(source, tileset.firstgid)?,
+                None => tileset.to_def(tileset.firstgid)?
+            });
+        }
+        let mut layers = Vec::new();
+        json_layers(&map.layers, true, (0.0, 0.0), &HashMap::new(), &mut layers)?;
+        self.build_room(MapDef {
+            orientation: map.orientation.unwrap_or("orthogonal".to_string()),
+            infinite: map.infinite,
+            bg_color: map.backgroundcolor.as_deref().and_then(parse_color),
+            size: (map.width, map.height),
+            tile_size: (map.tilewidth, map.tileheight),
+            tilesets,
+            layers
+        }, factory, persistant)
+    }
+
+    fn build_room<Snd, Fnt, Spr, Rm, Data>(
+            &self, mut map: MapDef, factory: &ObjectFactory<Img, Snd, Fnt, Spr, Rm, Data>,
+            persistant: bool) -> Result<Room<Img, Snd, Fnt, Spr, Rm, Data>, String> where
+                Spr: IndexRestriction,
+                Snd: IndexRestriction,
+                Fnt: IndexRestriction,
+                Rm: IndexRestriction,
+                Data: Clone {
+        if map.orientation != "orthogonal" {
+            return Err(format!("{} maps aren't supported, only orthogonal ones", map.orientation));
+        }
+        if map.infinite {
+            return Err("Infinite maps aren't supported".to_string());
+        }
+        map.tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+        let mut tilemaps = Vec::new();
+        let mut objs = Vec::new();
+        let count = map.layers.len();
+        for (i, layer) in map.layers.into_iter().enumerate() {
+            let depth = layer.props.get("depth")
+                .and_then(Property::as_int)
+                .map(|depth| depth as i32)
+                .unwrap_or((count - 1 - i) as i32);
+            match layer.content {
+                LayerContent::Tiles(gids) => {
+                    if gids.len() != (map.size.0 * map.size.1) as usize {
+                        return Err(format!(
+                            "Layer '{}' has {} tiles but the map has {} cells",
+                            layer.name, gids.len(), map.size.0 * map.size.1
+                        ));
+                    }
+                    let solid = layer.props.get("solid").and_then(Property::as_bool)
+                        .unwrap_or(false);
+                    let flips = gids.iter().map(|gid| {
+                        TileFlip::new(gid & FLIP_HORIZONTAL != 0, gid & FLIP_VERTICAL != 0)
+                            .with_diagonal(gid & FLIP_DIAGONAL != 0)
+                    }).collect::<Vec<_>>();
+                    let flips = if flips.iter().any(|flip| *flip != TileFlip::default()) {
+                        flips
+                    } else {
+                        vec![]
+                    };
+
+                    // Split the layer up by tileset, since a tilemap only draws from one
+                    for (j, tileset) in map.tilesets.iter().enumerate() {
+                        let end = map.tilesets.get(j + 1).map(|next| next.first_gid)
+                            .unwrap_or(u32::MAX);
+                        let tiles = gids.iter()
+                            .map(|gid| gid & GID_MASK)
+                            .map(|gid| {
+                                (gid >= tileset.first_gid && gid < end)
+                                    .then(|| gid - tileset.first_gid)
+                            }).collect::<Vec<_>>();
+                        if tiles.iter().all(Option::is_none) {
+                            continue;
+                        }
+                        let img = lookup(&self.imgs, &tileset.image).copied().ok_or(format!(
+                            "No image registered for tileset image '{}'", tileset.image
+                        ))?;
+                        let tilemap = Tilemap::new(
+                            Tileset::new(img, tileset.tile_size, tileset.columns)
+                                .with_spacing(tileset.margin, tileset.spacing),
+                            map.size
+                        ).with_pos(layer.offset)
+                            .with_tile_size((map.tile_size.0 as f64, map.tile_size.1 as f64))
+                            .with_layer(
+                                TileLayer::new(&layer.name, tiles)
+                                    .with_depth(depth)
+                                    .with_solid(solid)
+                                    .with_visible(layer.visible)
+                                    .with_flips(flips.clone())
+                            );
+                        tilemaps.push(tilemap);
+                    }
+                }, LayerContent::Objects(defs) => {
+                    for mut def in defs {
+                        def.depth = depth;
+                        def.pos.0 += layer.offset.0;
+                        def.pos.1 += layer.offset.1;
+                        if let Some(obj) = factory.build(&def) {
+                            objs.push(obj?);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut room = Room::new(objs, persistant).with_camera(
+            Camera::new().with_bounds(Rect::new(
+                0, 0, map.size.0 * map.tile_size.0, map.size.1 * map.tile_size.1
+            ))
+        );
+        room.tilemaps = tilemaps;
+        room.bg_color = map.bg_color;
+        Ok(room)
+    }
+
+    fn external_tileset(&self, source: &str, first_gid: u32) -> Result<TilesetDef, String> {
+        let bytes = lookup(&self.tilesets, source)
+            .ok_or(format!("No tileset registered for '{}'", source))?;
+        let text = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+        if text.trim_start().starts_with('<') {
+            let doc = Document::parse(text).map_err(|err| err.to_string())?;
+            xml_tileset(doc.root_element(), first_gid)
+        } else {
+            let tileset: JsonTileset = serde_json::from_str(text).map_err(|err| err.to_string())?;
+            tileset.to_def(first_gid)
+        }
+    }
+}
+
+/// What both formats are read into before building the room
+struct MapDef {
+    orientation: String,
+    infinite: bool,
+    bg_color: Option<Color>,
+    size: (u32, u32),
+    tile_size: (u32, u32),
+    tilesets: Vec<TilesetDef>,
+    layers: Vec<LayerDef>
+}
+
+struct TilesetDef {
+    first_gid: u32,
+    image: String,
+    tile_size: (u32, u32),
+    columns: u32,
+    margin: u32,
+    spacing: u32
+}
+
+impl TilesetDef {
+    /// Older versions of Tiled don't save columns, so it might have to come from the image size
+    fn new(
+            first_gid: u32, image: &str, tile_size: (u32, u32), columns: Option<u32>,
+            image_width: u32, margin: u32, spacing: u32) -> Self {
+        let columns = columns.filter(|columns| *columns > 0).unwrap_or(
+            (image_width.saturating_sub(margin * 2) + spacing) / (tile_size.0 + spacing).max(1)
+        );
+        Self {
+            first_gid,
+            image: image.to_string(),
+            tile_size,
+            columns,
+            margin,
+            spacing
+        }
+    }
+}
+
+/// A tile or object layer, with any groups it was in already applied
+struct LayerDef {
+    name: String,
+    visible: bool,
+    offset: (f64, f64),
+    props: HashMap<String, Property>,
+    content: LayerContent
+}
+
+enum LayerContent {
+    Tiles(Vec<u32>),
+    Objects(Vec<ObjectDef>)
+}
+
+/// The outline of an object in the editor
+enum Outline {
+    Rect,
+    Ellipse,
+    Polygon(Vec<(f64, f64)>),
+    None
+}
+
+/// Move an object to the top left of its box and give it the shape it has in the editor
+fn place_object(mut def: ObjectDef, is_tile: bool, outline: Outline) -> ObjectDef {
+    let (size, angle) = (def.size, def.angle);
+
+    // Tile objects are placed (and rotated) by their bottom left corner instead of the top left
+    if is_tile {
+        let rad = angle.to_radians();
+        def.pos = (def.pos.0 + size.1 * rad.sin(), def.pos.1 - size.1 * rad.cos());
+    }
+    let shape = match outline {
+        Outline::Rect if size.0 > 0.0 && size.1 > 0.0 => Some(CollisionShape::Rect {
+            center: (size.0 / 2.0, size.1 / 2.0),
+            size
+        }), Outline::Ellipse if size.0 == size.1 || size.0.min(size.1) <= 0.0 => {
+            Some(CollisionShape::Circle {
+                center: (size.0 / 2.0, size.1 / 2.0),
+                radius: size.0.min(size.1).max(0.0) / 2.0
+            })
+        }, Outline::Ellipse => Some(CollisionShape::Polygon {
+            center: (size.0 / 2.0, size.1 / 2.0),
+            points: (0..ELLIPSE_POINTS).map(|i| {
+                let angle = i as f64 * std::f64::consts::TAU / ELLIPSE_POINTS as f64;
+                (angle.cos() * size.0 / 2.0, angle.sin() * size.1 / 2.0)
+            }).collect()
+        }), Outline::Polygon(points) => Some(CollisionShape::Polygon {
+            center: (0.0, 0.0),
+            points
+        }), _ => None
+    };
+    def.shape = shape.map(|shape| shape.rotated(angle, (0.0, 0.0)));
+    def
+}
+
+/// Convert a property value from how Tiled writes it
+fn tiled_prop(kind: &str, val: &str) -> Result<Property, String> {
+    let bad = || format!("Bad {} property value '{}'", kind, val);
+    Ok(match kind {
+        "bool" => Property::Bool(val == "true"),
+        "int" | "object" => Property::Int(val.trim().parse().map_err(|_| bad())?),
+        "float" => Property::Float(val.trim().parse().map_err(|_| bad())?),
+        "color" => Property::Color(tiled_color(val)),
+        _ => Property::String(val.to_string())
+    })
+}
+
+/// Tiled saves unset colors as empty strings
+fn tiled_color(val: &str) -> Color {
+    parse_color(val).unwrap_or(Color::RGBA(0, 0, 0, 0))
+}
+
+/// Read base64 tile data, decompressing it first if needed
+fn decode_gids(data: &str, compression: Option<&str>) -> Result<Vec<u32>, String> {
+    let data = data.split_whitespace().collect::<String>();
+    let raw = BASE64_STANDARD.decode(data)
+        .map_err(|err| format!("Bad base64 tile data: {}", err))?;
+    let bytes = match compression.unwrap_or_default() {
+        "" => raw,
+        "zlib" => inflate(ZlibDecoder::new(raw.as_slice()))?,
+        "gzip" => inflate(GzDecoder::new(raw.as_slice()))?,
+        other => return Err(format!("Tile data compression '{}' isn't supported", other))
+    };
+    if bytes.len() % 4 != 0 {
+        return Err("Tile data isn't a whole number of tiles".to_string());
+    }
+    Ok(bytes.chunks_exact(4)
+        .map(|gid| u32::from_le_bytes([ gid[0], gid[1], gid[2], gid[3] ]))
+        .collect())
+}
+
+fn inflate(mut decoder: impl Read) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    decoder.read_to_end(&mut bytes)
+        .map_err(|err| format!("Couldn't decompress tile data: {}", err))?;
+    Ok(bytes)
+}
+
+fn parse_csv(data: &str) -> Result<Vec<u32>, String> {
+    data.split(',')
+        .map(str::trim)
+        .filter(|gid| !gid.is_empty())
+        .map(|gid| gid.parse().map_err(|_| format!("Bad tile '{}' in CSV tile data", gid)))
+        .collect()
+}
+
+fn xml_attr<T: std::str::FromStr>(node: Node, name: &str) -> Result<Option<T>, String> {
+    node.attribute(name)
+        .map(|val| val.trim().parse().map_err(|_| {
+            format!("Bad {} '{}' on <{}>", name, val, node.tag_name().name())
+        })).transpose()
+}
+
+fn xml_req<T: std::str::FromStr>(node: Node, name: &str) -> Result<T, String> {
+    xml_attr(node, name)?.ok_or(format!("<{}> has no {}", node.tag_name().name(), name))
+}
+
+fn xml_tileset(node: Node, first_gid: u32) -> Result<TilesetDef, String> {
+    let name = node.attribute("name").unwrap_or_default();
+    let img = node.children().find(|child| child.has_tag_name("image")).ok_or(format!(
+        "Tileset '{}' is a collection of images, which isn't supported", name
+    ))?;
+    Ok(TilesetDef::new(
+        first_gid,
+        img.attribute("source").unwrap_or_default(),
+        (xml_req(node, "tilewidth")?, xml_req(node, "tileheight")?),
+        xml_attr(node, "columns")?,
+        xml_attr(img, "width")?.unwrap_or(0),
+        xml_attr(node, "margin")?.unwrap_or(0),
+        xml_attr(node, "spacing")?.unwrap_or(0)
+    ))
+}
+
+fn xml_props(node: Node) -> Result<HashMap<String, Property>, String> {
+    let Some(props) = node.children().find(|child| child.has_tag_name("properties")) else {
+        return Ok(HashMap::new());
+    };
+    props.children()
+        .filter(|child| child.has_tag_name("property"))
+        .map(|prop| {
+            let name = prop.attribute("name").unwrap_or_default().to_string();
+            let val = match prop.attribute("type").unwrap_or("string") {
+                "class" => Property::Map(xml_props(prop)?),
+
+                // Strings with more than one line are saved as text instead of a value
+                kind => tiled_prop(
+                    kind, prop.attribute("value").or(prop.text()).unwrap_or_default()
+                )?
+            };
+            Ok((name, val))
+        }).collect()
+}
+
+/// Collect the layers in a map or group, flattening groups
+fn xml_layers(
+        parent: Node, visible: bool, offset: (f64, f64), props: &HashMap<String, Property>,
+        layers: &mut Vec<LayerDef>) -> Result<(), String> {
+    for node in parent.children().filter(Node::is_element) {
+        let tag = node.tag_name().name();
+        if !matches!(tag, "layer" | "objectgroup" | "group") {
+            continue;
+        }
+        let visible = visible && xml_attr::<u8>(node, "visible")?.unwrap_or(1) != 0;
+        let offset = (
+            offset.0 + xml_attr(node, "offsetx")?.unwrap_or(0.0),
+            offset.1 + xml_attr(node, "offsety")?.unwrap_or(0.0)
+        );
+        let mut props = props.clone();
+        props.extend(xml_props(node)?);
+        let content = match tag {
+            "group" => {
+                xml_layers(node, visible, offset, &props, layers)?;
+                continue;
+            }, "layer" => LayerContent::Tiles(xml_tile_data(node)?),
+            _ => LayerContent::Objects(xml_objects(node)?)
+        };
+        layers.push(LayerDef {
+            name: node.attribute("name").unwrap_or_default().to_string(),
+            visible,
+            offset,
+            props,
+            content
+        });
+    }
+    Ok(())
+}
+
+fn xml_tile_data(layer: Node) -> Result<Vec<u32>, String> {
+    let data = layer.children().find(|child| child.has_tag_name("data"))
+        .ok_or("Tile layer has no data".to_string())?;
+    if data.children().any(|child| child.has_tag_name("chunk")) {
+        return Err("Infinite maps aren't supported".to_string());
+    }
+    let text = data.text().unwrap_or_default();
+    match data.attribute("encoding") {
+        Some("csv") => parse_csv(text),
+        Some("base64") => decode_gids(text, data.attribute("compression")),
+        Some(other) => Err(format!("Tile data encoding '{}' isn't supported", other)),
+        None => data.children()
+            .filter(|child| child.has_tag_name("tile"))
+            .map(|tile| Ok(xml_attr(tile, "gid")?.unwrap_or(0)))
+            .collect()
+    }
+}
+
+fn xml_objects(group: Node) -> Result<Vec<ObjectDef>, String> {
+    group.children()
+        .filter(|child| child.has_tag_name("object"))
+        .map(|obj| {
+            let polygon = obj.children().find(|child| child.has_tag_name("polygon"));
+            let outline = if obj.children().any(|child| child.has_tag_name("ellipse")) {
+                Outline::Ellipse
+            } else if let Some(polygon) = polygon {
+                Outline::Polygon(parse_points(polygon.attribute("points").unwrap_or_default())?)
+            } else if obj.children().any(|child| {
+                child.has_tag_name("point") || child.has_tag_name("polyline")
+                    || child.has_tag_name("text")
+            }) {
+                Outline::None
+            } else {
+                Outline::Rect
+            };
+            let def = ObjectDef {
+                kind: obj.attribute("type").or(obj.attribute("class")).unwrap_or_default()
+                    .to_string(),
+                name: obj.attribute("name").unwrap_or_default().to_string(),
+                pos: (xml_attr(obj, "x")?.unwrap_or(0.0), xml_attr(obj, "y")?.unwrap_or(0.0)),
+                size: (
+                    xml_attr(obj, "width")?.unwrap_or(0.0),
+                    xml_attr(obj, "height")?.unwrap_or(0.0)
+                ), angle: xml_attr(obj, "rotation")?.unwrap_or(0.0),
+                depth: 0,
+                shape: None,
+                props: xml_props(obj)?
+            };
+            Ok(place_object(def, obj.attribute("gid").is_some(), outline))
+        }).collect()
+}
+
+/// Read polygon points written like "0,0 16,0 16,16"
+fn parse_points(points: &str) -> Result<Vec<(f64, f64)>, String> {
+    points.split_whitespace()
+        .map(|point| {
+            let (x, y) = point.split_once(',').ok_or(format!("Bad point '{}'", point))?;
+            Ok((
+                x.parse().map_err(|_| format!("Bad point '{}'", point))?,
+                y.parse().map_err(|_| format!("Bad point '{}'", point))?
+            ))
+        }).collect()
+}
+
+/// The parts of Tiled's JSON format that get used
+#[derive(Deserialize)]
+struct JsonMap {
+    orientation: Option<String>,
+    #[serde(default)]
+    infinite: bool,
+    backgroundcolor: Option<String>,
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    #[serde(default)]
+    firstgid: u32,
+    source: Option<String>,
+    #[serde(default)]
+    name: String,
+    image: Option<String>,
+    #[serde(default)]
+    imagewidth: u32,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    columns: Option<u32>,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32
+}
+
+impl JsonTileset {
+    fn to_def(&self, first_gid: u32) -> Result<TilesetDef, String> {
+        let img = self.image.as_ref().ok_or(format!(
+            "Tileset '{}' is a collection of images, which isn't supported", self.name
+        ))?;
+        Ok(TilesetDef::new(
+            first_gid, img, (self.tilewidth, self.tileheight), self.columns, self.imagewidth,
+            self.margin, self.spacing
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    visible: Option<bool>,
+    #[serde(default)]
+    offsetx: f64,
+    #[serde(default)]
+    offsety: f64,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+    data: Option<JsonData>,
+    encoding: Option<String>,
+    compression: Option<String>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonData {
+    Gids(Vec<u32>),
+    Encoded(String)
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    x: f64,
+    #[serde(default)]
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    #[serde(default)]
+    rotation: f64,
+    gid: Option<u32>,
+    #[serde(default)]
+    ellipse: bool,
+    #[serde(default)]
+    point: bool,
+    polygon: Option<Vec<JsonPoint>>,
+    polyline: Option<Vec<JsonPoint>>,
+    text: Option<Value>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>
+}
+
+#[derive(Deserialize)]
+struct JsonPoint {
+    x: f64,
+    y: f64
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    value: Value
+}
+
+fn json_props(props: &[JsonProperty]) -> HashMap<String, Property> {
+    props.iter()
+        .filter_map(|prop| {
+            let val = match (prop.kind.as_str(), &prop.value) {
+                ("color", Value::String(color)) => Property::Color(tiled_color(color)),
+                ("float", Value::Number(num)) => Property::Float(num.as_f64()?),
+                _ => Property::from_json(&prop.value)?
+            };
+            Some((prop.name.clone(), val))
+        }).collect()
+}
+
+/// Collect the layers in a map or group, flattening groups
+fn json_layers(
+        json: &[JsonLayer], visible: bool, offset: (f64, f64), props: &HashMap<String, Property>,
+        layers: &mut Vec<LayerDef>) -> Result<(), String> {
+    for layer in json.iter() {
+        let visible = visible && layer.visible.unwrap_or(true);
+        let offset = (offset.0 + layer.offsetx, offset.1 + layer.offsety);
+        let mut props = props.clone();
+        props.extend(json_props(&layer.properties));
+        let content = match layer.kind.as_str() {
+            "group" => {
+                json_layers(&layer.layers, visible, offset, &props, layers)?;
+                continue;
+            }, "tilelayer" => LayerContent::Tiles(match &layer.data {
+                Some(JsonData::Gids(gids)) => gids.clone(),
+                Some(JsonData::Encoded(data)) if layer.encoding.as_deref() == Some("base64") => {
+                    decode_gids(data, layer.compression.as_deref())?
+                }, Some(JsonData::Encoded(_)) => {
+                    return Err(format!(
+                        "Layer '{}' has tile data in an unknown encoding", layer.name
+                    ));
+                }, None => return Err("Infinite maps aren't supported".to_string())
+            }), "objectgroup" => LayerContent::Objects(layer.objects.iter()
+                .map(|obj| {
+                    let outline = if obj.ellipse {
+                        Outline::Ellipse
+                    } else if let Some(polygon) = &obj.polygon {
+                        Outline::Polygon(polygon.iter().map(|point| (point.x, point.y)).collect())
+                    } else if obj.point || obj.polyline.is_some() || obj.text.is_some() {
+                        Outline::None
+                    } else {
+                        Outline::Rect
+                    };
+                    let def = ObjectDef {
+                        kind: if obj.kind.is_empty() { &obj.class } else { &obj.kind }
+                            .to_string(),
+                        name: obj.name.clone(),
+                        pos: (obj.x, obj.y),
+                        size: (obj.width, obj.height),
+                        angle: obj.rotation,
+                        depth: 0,
+                        shape: None,
+                        props: json_props(&obj.properties)
+                    };
+                    place_object(def, obj.gid.is_some(), outline)
+                }).collect()
+            ), _ => continue
+        };
+        layers.push(LayerDef {
+            name: layer.name.clone(),
+            visible,
+            offset,
+            props,
+            content
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,393 @@
+//! Grids of tiles drawn from a tileset image, for level geometry that doesn't need to be made of
+//! individual objects
+
+use std::collections::HashMap;
+use sdl2::{
+    rect::Rect,
+    render::Canvas,
+    video::Window
+};
+use crate::{
+    camera::Camera,
+    collision::CollisionShape,
+    res::Image,
+    IndexRestriction
+};
+
+/// An image cut up into a grid of equally sized tiles, numbered left to right, top to bottom
+/// starting at 0
+///
+/// - img: Img (the image to cut up)
+/// - tile_size: (u32, u32) (size of a tile in the image, in pixels)
+/// - columns: u32 (how many tiles fit across the image)
+/// - margin: u32 (empty pixels around the edge of the image)
+/// - spacing: u32 (empty pixels between tiles)
+#[derive(Clone, Debug)]
+pub struct Tileset<Img> where Img: IndexRestriction {
+    pub img: Img,
+    pub tile_size: (u32, u32),
+    pub columns: u32,
+    pub margin: u32,
+    pub spacing: u32
+}
+
+impl<Img> Tileset<Img> where Img: IndexRestriction {
+    pub fn new(img: Img, tile_size: (u32, u32), columns: u32) -> Self {
+        Self {
+            img,
+            tile_size,
+            columns,
+            margin: 0,
+            spacing: 0
+        }
+    }
+
+    pub fn with_spacing(mut self, margin: u32, spacing: u32) -> Self {
+        self.margin = margin;
+        self.spacing = spacing;
+        self
+    }
+
+    /// Where a tile is in the image
+    pub fn clip(&self, tile: u32) -> Rect {
+        let (col, row) = (tile % self.columns.max(1), tile / self.columns.max(1));
+        Rect::new(
+            (self.margin + col * (self.tile_size.0 + self.spacing)) as i32,
+            (self.margin + row * (self.tile_size.1 + self.spacing)) as i32,
+            self.tile_size.0, self.tile_size.1
+        )
+    }
+}
+
+/// How a single tile is flipped, the same way Tiled and LDtk do it. Diagonal swaps the tile's x and
+/// y before the other two flips, which is how the 90° rotations are made (so those only look right
+/// with square tiles)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TileFlip {
+    pub horizontal: bool,
+    pub vertical: bool,
+    pub diagonal: bool
+}
+
+impl TileFlip {
+    pub fn new(horizontal: bool, vertical: bool) -> Self {
+        Self {
+            horizontal,
+            vertical,
+            diagonal: false
+        }
+    }
+
+    pub fn with_diagonal(mut self, diagonal: bool) -> Self {
+        self.diagonal = diagonal;
+        self
+    }
+
+    /// The angle and flip to draw the tile with, as Image::render takes them
+    pub fn render_params(&self) -> (f64, (bool, bool)) {
+        if self.diagonal {
+            // Swapping x and y is flipping vertically and then turning 90° clockwise. Flips done
+            // after the turn swap axes to happen before it
+            (90.0, (self.vertical, !self.horizontal))
+        } else {
+            (0.0, (self.horizontal, self.vertical))
+        }
+    }
+}
+
+/// One layer of tiles. Each cell holds a tile from the tileset or None if it's empty
+///
+/// - name: String
+/// - tiles: Vec<Option<u32>> (one per cell, row by row)
+/// - depth: i32 (drawn in between objects by depth, like GameObjectState::depth)
+/// - visible: bool
+/// - solid: bool (every tile on the layer blocks movement and shows up in collision checks)
+/// - collision_layer: u32 (bits for which collision layers the solid tiles are on, like
+///   GameObjectState::layer. Only objects with one of them in their mask run into the tiles.
+///   Defaults to layer 1)
+/// - flips: Vec<TileFlip> (how each cell's tile is flipped, row by row like tiles. Can be left
+///   empty when nothing is)
+#[derive(Clone, Debug)]
+pub struct TileLayer {
+    pub name: String,
+    pub tiles: Vec<Option<u32>>,
+    pub depth: i32,
+    pub visible: bool,
+    pub solid: bool,
+    pub collision_layer: u32,
+    pub flips: Vec<TileFlip>
+}
+
+impl TileLayer {
+    pub fn new(name: &str, tiles: Vec<Option<u32>>) -> Self {
+        Self {
+            name: name.to_string(),
+            tiles,
+            depth: 0,
+            visible: true,
+            solid: false,
+            collision_layer: 1,
+            flips: Vec::new()
+        }
+    }
+
+    /// A layer with nothing in it yet
+    pub fn empty(name: &str, size: (u32, u32)) -> Self {
+        Self::new(name, vec![ None; (size.0 * size.1) as usize ])
+    }
+
+    pub fn with_depth(mut self, depth: i32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn with_solid(mut self, solid: bool) -> Self {
+        self.solid = solid;
+        self
+    }
+
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn with_collision_layer(mut self, collision_layer: u32) -> Self {
+        self.collision_layer = collision_layer;
+        self
+    }
+
+    pub fn with_flips(mut self, flips: Vec<TileFlip>) -> Self {
+        self.flips = flips;
+        self
+    }
+}
+
+/// A grid of tiles placed in a room. Give it to a room with Room::with_tilemap, and objects can
+/// check it through the tilemaps() of the others passed to update.
+///
+/// - tileset: Option<Tileset<Img>> (None for maps that only hold values, like a collision grid)
+/// - pos: (f64, f64) (top left of the map in the room)
+/// - size: (u32, u32) (number of columns and rows)
+/// - tile_size: (f64, f64) (size of a cell in the room, which can differ from the tileset's)
+/// - layers: Vec<TileLayer>
+#[derive(Clone, Debug)]
+pub struct Tilemap<Img> where Img: IndexRestriction {
+    pub tileset: Option<Tileset<Img>>,
+    pub pos: (f64, f64),
+    pub size: (u32, u32),
+    pub tile_size: (f64, f64),
+    pub layers: Vec<TileLayer>
+}
+
+impl<Img> Tilemap<Img> where Img: IndexRestriction {
+    /// A map with no layers where each cell is the same size as a tile in the tileset
+    pub fn new(tileset: Tileset<Img>, size: (u32, u32)) -> Self {
+        let tile_size = (tileset.tile_size.0 as f64, tileset.tile_size.1 as f64);
+        Self::grid(size, tile_size).with_tileset(tileset)
+    }
+
+    /// A map with no layers and nothing to draw its tiles with. Its tiles are just numbers, e.g.
+    /// to mark which cells are walls
+    pub fn grid(size: (u32, u32), tile_size: (f64, f64)) -> Self {
+        Self {
+            tileset: None,
+            pos: (0.0, 0.0),
+            size,
+            tile_size,
+            layers: Vec::new()
+        }
+    }
+
+    pub fn with_tileset(mut self, tileset: Tileset<Img>) -> Self {
+        self.tileset = Some(tileset);
+        self
+    }
+
+    pub fn with_pos(mut self, pos: (f64, f64)) -> Self {
+        self.pos = pos;
+        self
+    }
+
+    pub fn with_tile_size(mut self, tile_size: (f64, f64)) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// Layers are drawn in the order they're added when they have the same depth
+    pub fn with_layer(mut self, layer: TileLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut TileLayer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    /// The tile in a cell of a layer. Cells outside the map are always empty
+    pub fn tile(&self, layer: usize, cell: (i32, i32)) -> Option<u32> {
+        let idx = self.cell_idx(cell)?;
+        self.layers.get(layer)?.tiles.get(idx).copied().flatten()
+    }
+
+    /// How the tile in a cell of a layer is flipped
+    pub fn flip(&self, layer: usize, cell: (i32, i32)) -> TileFlip {
+        self.cell_idx(cell)
+            .and_then(|idx| self.layers.get(layer)?.flips.get(idx).copied())
+            .unwrap_or_default()
+    }
+
+    /// Change a cell of a layer. Does nothing for cells outside the map
+    pub fn set_tile(&mut self, layer: usize, cell: (i32, i32), tile: Option<u32>) {
+        let Some(idx) = self.cell_idx(cell) else {
+            return;
+        };
+        if let Some(slot) = self.layers.get_mut(layer).and_then(|layer| layer.tiles.get_mut(idx)) {
+            *slot = tile;
+        }
+    }
+
+    /// Which cell a point in the room lands in. Can be outside the map
+    pub fn world_to_cell(&self, pos: (f64, f64)) -> (i32, i32) {
+        (
+            ((pos.0 - self.pos.0) / self.tile_size.0).floor() as i32,
+            ((pos.1 - self.pos.1) / self.tile_size.1).floor() as i32
+        )
+    }
+
+    /// Top left of a cell in the room
+    pub fn cell_to_world(&self, cell: (i32, i32)) -> (f64, f64) {
+        (
+            self.pos.0 + cell.0 as f64 * self.tile_size.0,
+            self.pos.1 + cell.1 as f64 * self.tile_size.1
+        )
+    }
+
+    /// A collider covering a cell, in room coordinates
+    pub fn cell_collider(&self, cell: (i32, i32)) -> CollisionShape {
+        let pos = self.cell_to_world(cell);
+        CollisionShape::Rect {
+            center: (pos.0 + self.tile_size.0 / 2.0, pos.1 + self.tile_size.1 / 2.0),
+            size: self.tile_size
+        }
+    }
+
+    /// True if any solid layer has a tile in the cell
+    pub fn is_solid(&self, cell: (i32, i32)) -> bool {
+        self.solid_layers(cell) != 0
+    }
+
+    /// The collision layers of every solid layer with a tile in the cell, or 0 if there aren't any
+    pub fn solid_layers(&self, cell: (i32, i32)) -> u32 {
+        let Some(idx) = self.cell_idx(cell) else {
+            return 0;
+        };
+        self.layers.iter()
+            .filter(|layer| layer.solid && layer.tiles.get(idx).copied().flatten().is_some())
+            .fold(0, |bits, layer| bits | layer.collision_layer)
+    }
+
+    /// True if there's a solid tile at a point in the room
+    pub fn is_solid_at(&self, pos: (f64, f64)) -> bool {
+        self.is_solid(self.world_to_cell(pos))
+    }
+
+    /// Every solid cell a shape overlaps, along with its collider
+    pub fn solids_overlapping(&self, shape: &CollisionShape) -> Vec<((i32, i32), CollisionShape)> {
+        self.solids_overlapping_masked(shape, u32::MAX)
+    }
+
+    /// Every cell a shape overlaps that's solid on one of the collision layers in mask
+    pub fn solids_overlapping_masked(
+            &self, shape: &CollisionShape, mask: u32) -> Vec<((i32, i32), CollisionShape)> {
+        let aabb = shape.aabb();
+        let min = self.world_to_cell((aabb.min.0 as f64, aabb.min.1 as f64));
+        let max = self.world_to_cell((aabb.max.0 as f64, aabb.max.1 as f64));
+        let (min, max) = (
+            (min.0.max(0), min.1.max(0)),
+            (max.0.min(self.size.0 as i32 - 1), max.1.min(self.size.1 as i32 - 1))
+        );
+        let mut solids = Vec::new();
+        for row in min.1..=max.1 {
+            for col in min.0..=max.0 {
+                if self.solid_layers((col, row)) & mask == 0 {
+                    continue;
+                }
+                let collider = self.cell_collider((col, row));
+                if collider.collides_with(shape) {
+                    solids.push(((col, row), collider));
+                }
+            }
+        }
+        solids
+    }
+
+    /// True if a shape (in room coordinates) touches any solid tile
+    pub fn collides_with(&self, shape: &CollisionShape) -> bool {
+        !self.solids_overlapping(shape).is_empty()
+    }
+
+    /// Draw one layer, skipping every tile that's off screen
+    pub fn render_layer(
+            &self, cnv: &mut Canvas<Window>, cam: &Camera, imgs: &HashMap<Img, Image>,
+            layer: usize) -> Result<(), String> {
+        let Some(tileset) = &self.tileset else {
+            return Ok(());
+        };
+        let Some(img) = imgs.get(&tileset.img) else {
+            return Ok(());
+        };
+        for (cell, tile, dest) in self.visible_tiles(cam, layer) {
+            let (angle, flip) = self.flip(layer, cell).render_params();
+            img.render(cnv, &tileset.clip(tile), &dest, angle, flip)?;
+        }
+        Ok(())
+    }
+
+    /// Every tile in a layer that's at least partly on screen, as (cell, tile, where it goes on the
+    /// screen). This is what render_layer draws, so a hidden layer has none
+    pub fn visible_tiles(&self, cam: &Camera, layer: usize) -> Vec<((i32, i32), u32, Rect)> {
+        let Some(layer) = self.layers.get(layer).filter(|layer| layer.visible) else {
+            return vec![];
+        };
+
+        // The view's exact edges, since view_rect rounds them to whole pixels
+        let view_size = cam.view_size();
+        let min = self.world_to_cell(cam.pos);
+        let max = self.world_to_cell((cam.pos.0 + view_size.0, cam.pos.1 + view_size.1));
+        let mut tiles = Vec::new();
+        for row in min.1.max(0)..=max.1.min(self.size.1 as i32 - 1) {
+            for col in min.0.max(0)..=max.0.min(self.size.0 as i32 - 1) {
+                let Some(tile) = layer.tiles.get((row * self.size.0 as i32 + col) as usize)
+                        .copied()
+                        .flatten() else {
+                    continue;
+                };
+
+                // Round both corners so neighboring tiles always meet with no gaps
+                let start = cam.world_to_screen(self.cell_to_world((col, row)));
+                let end = cam.world_to_screen(self.cell_to_world((col + 1, row + 1)));
+                let (x, y) = (start.0.floor() as i32, start.1.floor() as i32);
+                let dest = Rect::new(
+                    x, y,
+                    (end.0.floor() as i32 - x).max(1) as u32,
+                    (end.1.floor() as i32 - y).max(1) as u32
+                );
+                tiles.push(((col, row), tile, dest));
+            }
+        }
+        tiles
+    }
+
+    fn cell_idx(&self, cell: (i32, i32)) -> Option<usize> {
+        let (cols, rows) = (self.size.0 as i32, self.size.1 as i32);
+        if cell.0 < 0 || cell.1 < 0 || cell.0 >= cols || cell.1 >= rows {
+            None
+        } else {
+            Some((cell.1 as u32 * self.size.0 + cell.0 as u32) as usize)
+        }
+    }
+}
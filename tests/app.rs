@@ -0,0 +1,140 @@
+//! Parts of the game loop and window scaling that don't need a window
+
+use sdl2::rect::Rect;
+use ycraft::app::{
+    Clock, ScaleMode, Timestep
+};
+
+// A power of two so adding up ticks doesn't round
+const TICK: f64 = 1.0 / 64.0;
+
+/// Run one loop and return the deltas that were passed to update
+fn deltas(clock: &mut Clock, frame_time: f64) -> Vec<f64> {
+    let mut deltas = Vec::new();
+    let ran = clock.tick(frame_time, |delta| {
+        deltas.push(delta);
+        false
+    });
+    assert_eq!(ran, deltas.len());
+    deltas
+}
+
+#[test]
+fn variable_timestep_updates_once_with_the_frame_time() {
+    let mut clock = Clock::new(Timestep::Variable);
+    assert_eq!(deltas(&mut clock, 0.7), vec![ 0.7 ]);
+    assert_eq!(deltas(&mut clock, 0.0), vec![ 0.0 ]);
+    assert_eq!(clock.alpha(), 1.0);
+}
+
+#[test]
+fn fixed_timestep_runs_a_tick_for_every_delta_that_fits() {
+    let mut clock = Clock::new(Timestep::Fixed(64.0));
+    // (frame time, ticks that should run)
+    let frames = [ (TICK / 2.0, 0), (TICK / 2.0, 1), (TICK * 2.5, 2), (TICK / 2.0, 1), (0.0, 0) ];
+    for (frame_time, ticks) in frames {
+        let deltas = deltas(&mut clock, frame_time);
+        assert_eq!(deltas.len(), ticks, "{} after {}", clock.accumulator, frame_time);
+        assert!(deltas.iter().all(|delta| *delta == TICK));
+    }
+}
+
+#[test]
+fn fixed_timestep_drops_time_past_the_longest_frame() {
+    let mut clock = Clock::new(Timestep::Fixed(64.0));
+    // Only a quarter second (16 ticks) gets caught up on, however long the hitch was
+    assert_eq!(deltas(&mut clock, 10.0).len(), 16);
+    assert!(clock.accumulator < TICK);
+}
+
+#[test]
+fn alpha_stays_between_ticks() {
+    let mut clock = Clock::new(Timestep::Fixed(64.0));
+    for i in 0..1000 {
+        let frame_time = (i % 7) as f64 * 0.004 + 0.001;
+        deltas(&mut clock, frame_time);
+        let alpha = clock.alpha();
+        assert!((0.0..1.0).contains(&alpha), "alpha {} after frame {}", alpha, i);
+    }
+
+    clock.accumulator = TICK * 0.25;
+    assert!((clock.alpha() - 0.25).abs() < 1e-9);
+}
+
+#[test]
+fn ticks_stop_when_update_asks_to() {
+    let mut clock = Clock::new(Timestep::Fixed(64.0));
+    let mut calls = 0;
+    let ran = clock.tick(TICK * 5.5, |_| {
+        calls += 1;
+        calls == 2
+    });
+    assert_eq!((ran, calls), (2, 2));
+
+    // The ticks that were skipped don't come back the next loop
+    assert_eq!(deltas(&mut clock, 0.0).len(), 0);
+    assert!((clock.alpha() - 0.5).abs() < 1e-9);
+}
+
+const LOGICAL: (u32, u32) = (320, 180);
+
+#[test]
+fn scale_modes_fit_the_game_into_the_window() {
+    // (mode, window, where the game goes as (x, y, w, h))
+    let cases = [
+        (ScaleMode::Stretch, (1000, 700), (0, 0, 1000, 700)),
+        (ScaleMode::Letterbox, (1000, 700), (0, 68, 1000, 563)),
+        (ScaleMode::PixelPerfect, (1000, 700), (20, 80, 960, 540)),
+
+        // Odd sizes that are only just bigger than twice the game
+        (ScaleMode::Stretch, (641, 361), (0, 0, 641, 361)),
+        (ScaleMode::Letterbox, (641, 361), (0, 0, 641, 361)),
+        (ScaleMode::PixelPerfect, (641, 361), (0, 0, 640, 360)),
+        (ScaleMode::Letterbox, (333, 999), (0, 406, 333, 187)),
+        (ScaleMode::PixelPerfect, (333, 999), (6, 409, 320, 180)),
+
+        // Smaller than the game. Pixel perfect never goes below 1x, so it gets cut off
+        (ScaleMode::Stretch, (200, 100), (0, 0, 200, 100)),
+        (ScaleMode::Letterbox, (200, 100), (11, 0, 178, 100)),
+        (ScaleMode::PixelPerfect, (200, 100), (-60, -40, 320, 180))
+    ];
+    for (mode, window, (x, y, w, h)) in cases {
+        assert_eq!(
+            mode.dest_rect(LOGICAL, window), Rect::new(x, y, w, h), "{:?} in {:?}", mode, window
+        );
+    }
+}
+
+#[test]
+fn window_to_logical_undoes_the_scaling() {
+    for (mode, window) in [
+        (ScaleMode::Stretch, (1000, 700)),
+        (ScaleMode::Letterbox, (1000, 700)),
+        (ScaleMode::PixelPerfect, (1000, 700)),
+        (ScaleMode::Letterbox, (641, 361)),
+        (ScaleMode::PixelPerfect, (333, 999))
+    ] {
+        let dest = mode.dest_rect(LOGICAL, window);
+        let scale = (dest.w as f64 / LOGICAL.0 as f64, dest.h as f64 / LOGICAL.1 as f64);
+        for pos in [ (0, 0), (1, 1), (159, 90), (318, 5), (319, 179) ] {
+            // The middle of the logical pixel in the window
+            let on_window = (
+                (dest.x as f64 + (pos.0 as f64 + 0.5) * scale.0) as i32,
+                (dest.y as f64 + (pos.1 as f64 + 0.5) * scale.1) as i32
+            );
+            assert_eq!(
+                mode.window_to_logical(LOGICAL, window, on_window), pos,
+                "{:?} in {:?} at {:?}", mode, window, on_window
+            );
+        }
+
+        // The bars are outside the game
+        let before = mode.window_to_logical(LOGICAL, window, (dest.x - 1, dest.y - 1));
+        assert!(before.0 < 0 && before.1 < 0, "{:?} in {:?}: {:?}", mode, window, before);
+        let after = mode.window_to_logical(LOGICAL, window, (dest.right(), dest.bottom()));
+        assert!(
+            after.0 >= LOGICAL.0 as i32 && after.1 >= LOGICAL.1 as i32,
+            "{:?} in {:?}: {:?}", mode, window, after
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Cameras following objects, lagging behind, staying in bounds, and zooming
+
+mod common;
+
+use sdl2::rect::Rect;
+use ycraft::{
+    camera::Camera,
+    input::Input
+};
+use common::{
+    rect, state, TestRoom, Thing
+};
+
+fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+    (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+}
+
+fn sized(cam: Camera) -> Camera {
+    let mut cam = cam;
+    cam.size = (100.0, 50.0);
+    cam
+}
+
+#[test]
+fn no_lag_centers_right_on_the_target() {
+    let mut cam = sized(Camera::new());
+    cam.update(0.1, Some((200.0, 100.0)));
+    assert_eq!(cam.pos, (150.0, 75.0));
+    assert_eq!(cam.prev_pos, (0.0, 0.0));
+
+    // Nothing to follow leaves it where it is
+    cam.update(0.1, None);
+    assert_eq!((cam.pos, cam.prev_pos), ((150.0, 75.0), (150.0, 75.0)));
+}
+
+#[test]
+fn lag_is_how_much_distance_is_left_after_a_second() {
+    let mut cam = sized(Camera::new().with_pos((-50.0, -25.0)));
+    cam.lag = 0.25;
+    cam.update(1.0, Some((100.0, 0.0)));
+    assert!(close(cam.pos, (25.0, -25.0)), "{:?}", cam.pos);
+
+    // The same second split into smaller steps ends up in the same place
+    let mut cam = sized(Camera::new().with_pos((-50.0, -25.0)));
+    cam.lag = 0.25;
+    for _ in 0..10 {
+        cam.update(0.1, Some((100.0, 0.0)));
+    }
+    assert!(close(cam.pos, (25.0, -25.0)), "{:?}", cam.pos);
+
+    // Snapping skips the lag and the interpolation
+    cam.snap_to(Some((100.0, 0.0)));
+    assert_eq!((cam.pos, cam.prev_pos), ((50.0, -25.0), (50.0, -25.0)));
+}
+
+#[test]
+fn bounds_keep_the_view_inside_the_room() {
+    let mut cam = sized(Camera::new().with_bounds(Rect::new(0, 0, 400, 300)));
+    for (target, pos) in [
+        ((0.0, 0.0), (0.0, 0.0)),
+        ((200.0, 150.0), (150.0, 125.0)),
+        ((1000.0, 1000.0), (300.0, 250.0)),
+        ((-1000.0, 290.0), (0.0, 250.0))
+    ] {
+        cam.snap_to(Some(target));
+        assert_eq!(cam.pos, pos, "following {:?}", target);
+    }
+
+    // Bounds smaller than the view get centered instead
+    let mut cam = sized(Camera::new().with_bounds(Rect::new(10, 10, 40, 20)));
+    cam.update(0.1, Some((500.0, 500.0)));
+    assert_eq!(cam.pos, (-20.0, -5.0));
+}
+
+#[test]
+fn zoom_shrinks_the_view_around_the_target() {
+    let mut cam = sized(Camera::new().with_zoom(2.0).with_bounds(Rect::new(0, 0, 400, 300)));
+    assert_eq!(cam.view_size(), (50.0, 25.0));
+    cam.snap_to(Some((100.0, 100.0)));
+    assert_eq!(cam.pos, (75.0, 87.5));
+    assert_eq!(cam.view_rect(), Rect::new(75, 87, 50, 25));
+
+    assert_eq!(cam.world_to_screen((100.0, 100.0)), (50.0, 25.0));
+    assert_eq!(cam.screen_to_world((50.0, 25.0)), (100.0, 100.0));
+    assert_eq!(cam.screen_to_world(cam.world_to_screen((3.0, -7.0))), (3.0, -7.0));
+
+    // Zoomed in all the way to the edge, the bounds still hold
+    cam.snap_to(Some((400.0, 300.0)));
+    assert_eq!(cam.pos, (350.0, 275.0));
+}
+
+#[test]
+fn rooms_move_the_camera_to_the_object_it_follows() {
+    let square = rect((0.0, 0.0), (10.0, 10.0));
+    let mut room = TestRoom::new(vec![
+        Thing::new(state("player", (10.0, 10.0), square.clone(), ())),
+        Thing::new(state("player", (300.0, 200.0), square.clone(), ()))
+    ], false);
+
+    // Names repeat, but ids don't
+    let second = room.objs[1].state().id;
+    room.cam = sized(Camera::new().with_follow(second, 0.0));
+    room.snap_camera();
+    assert_eq!(room.cam.pos, (250.0, 175.0));
+
+    room.get_mut(second).unwrap().state_mut().pos = (400.0, 200.0);
+    room.update(0.1, &mut Input::new(), &vec![]);
+    assert_eq!(room.cam.pos, (350.0, 175.0));
+
+    // Once it's gone the camera stays put
+    room.remove(second);
+    room.update(0.1, &mut Input::new(), &vec![]);
+    assert_eq!(room.cam.pos, (350.0, 175.0));
+}
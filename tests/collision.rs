@@ -0,0 +1,335 @@
+//! Collision shapes and the broad phase that finds which ones to check
+
+mod common;
+
+use std::collections::HashSet;
+use rand::{
+    rngs::StdRng,
+    Rng, SeedableRng
+};
+use ycraft::collision::{
+    CollisionShape, SpatialHash
+};
+use common::{
+    circle, rect
+};
+
+const ROOM_SIZE: f64 = 1000.0;
+const SQUARE: [(f64, f64); 4] = [ (-10.0, -10.0), (10.0, -10.0), (10.0, 10.0), (-10.0, 10.0) ];
+
+fn random_shape(rng: &mut StdRng) -> CollisionShape {
+    let center = (rng.gen_range(-100.0..ROOM_SIZE), rng.gen_range(-100.0..ROOM_SIZE));
+    // Now and then make something huge so it skips the grid
+    let scale = if rng.gen_bool(0.05) { 20.0 } else { 1.0 };
+    match rng.gen_range(0..4) {
+        0 => CollisionShape::Circle { center, radius: rng.gen_range(0.0..40.0) * scale },
+        1 => CollisionShape::Rect {
+            center,
+            size: (rng.gen_range(0.0..80.0) * scale, rng.gen_range(0.0..80.0) * scale)
+        }, 2 => CollisionShape::Rect {
+            center,
+            size: (rng.gen_range(1.0..80.0), rng.gen_range(1.0..80.0))
+        }.rotated(rng.gen_range(0.0..360.0), center),
+        _ => {
+            let sides = rng.gen_range(3..8);
+            let radius = rng.gen_range(1.0..40.0) * scale;
+            let turn = rng.gen_range(0.0..360.0_f64).to_radians();
+            CollisionShape::Polygon {
+                center,
+                points: (0..sides).map(|i| {
+                    let angle = turn + i as f64 * std::f64::consts::TAU / sides as f64;
+                    (angle.cos() * radius, angle.sin() * radius)
+                }).collect()
+            }
+        }
+    }
+}
+
+fn brute_force_pairs(shapes: &[CollisionShape]) -> HashSet<(usize, usize)> {
+    let mut pairs = HashSet::new();
+    for i in 0..shapes.len() {
+        for j in i + 1..shapes.len() {
+            if shapes[i].collides_with(&shapes[j]) {
+                pairs.insert((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+fn grid_pairs(shapes: &[CollisionShape], cell_size: u32) -> HashSet<(usize, usize)> {
+    let aabbs = shapes.iter().map(|shape| shape.aabb()).collect::<Vec<_>>();
+    let mut grid = SpatialHash::new(cell_size);
+    for (i, aabb) in aabbs.iter().enumerate() {
+        grid.insert(i, aabb);
+    }
+    grid.pairs().into_iter()
+        .filter(|(i, j)| aabbs[*i].overlaps(&aabbs[*j]))
+        .filter(|(i, j)| shapes[*i].collides_with(&shapes[*j]))
+        .collect()
+}
+
+#[test]
+fn spatial_hash_finds_the_same_pairs_as_checking_everything() {
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let count = rng.gen_range(0..300);
+        let shapes = (0..count).map(|_| random_shape(&mut rng)).collect::<Vec<_>>();
+        let expected = brute_force_pairs(&shapes);
+        for cell_size in [ 1, 16, 64, 128, 2000 ] {
+            assert_eq!(
+                grid_pairs(&shapes, cell_size), expected, "seed {} cell size {}", seed, cell_size
+            );
+        }
+    }
+}
+
+fn polygon(center: (f64, f64), points: &[(f64, f64)]) -> CollisionShape {
+    CollisionShape::Polygon { center, points: points.to_vec() }
+}
+
+fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+    (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+}
+
+#[test]
+fn contacts_between_every_pair_of_shapes() {
+    let triangle = [ (0.0, -10.0), (10.0, 10.0), (-10.0, 10.0) ];
+    // (name, a, b, normal from a to b, depth, contact points)
+    let cases = [
+        (
+            "rect/rect", rect((0.0, 0.0), (20.0, 20.0)), rect((15.0, 0.0), (20.0, 20.0)),
+            (1.0, 0.0), 5.0, vec![ (5.0, -10.0), (5.0, 10.0) ]
+        ), (
+            "rect/circle", rect((0.0, 0.0), (20.0, 20.0)), circle((0.0, 15.0), 10.0),
+            (0.0, 1.0), 5.0, vec![ (0.0, 10.0) ]
+        ), (
+            "circle/rect", circle((0.0, 15.0), 10.0), rect((0.0, 0.0), (20.0, 20.0)),
+            (0.0, -1.0), 5.0, vec![ (0.0, 10.0) ]
+        ), (
+            "circle/circle", circle((0.0, 0.0), 10.0), circle((15.0, 0.0), 10.0),
+            (1.0, 0.0), 5.0, vec![ (5.0, 0.0) ]
+        ), (
+            "polygon/polygon", polygon((0.0, 0.0), &SQUARE), polygon((0.0, 18.0), &triangle),
+            (0.0, 1.0), 2.0, vec![ (0.0, 8.0) ]
+        ), (
+            "rotated rect/rect", rect((0.0, 0.0), (20.0, 20.0)).rotated(45.0, (0.0, 0.0)),
+            rect((20.0, 0.0), (20.0, 20.0)), (1.0, 0.0), 200.0_f64.sqrt() - 10.0,
+            vec![ (200.0_f64.sqrt(), 0.0) ]
+        ), (
+            "touching rects", rect((0.0, 0.0), (20.0, 20.0)), rect((20.0, 5.0), (20.0, 20.0)),
+            (1.0, 0.0), 0.0, vec![ (10.0, -5.0), (10.0, 10.0) ]
+        ), (
+            "touching circles", circle((0.0, 0.0), 10.0), circle((0.0, -20.0), 10.0),
+            (0.0, -1.0), 0.0, vec![ (0.0, -10.0) ]
+        ), (
+            "rect inside rect", rect((0.0, 0.0), (100.0, 100.0)), rect((10.0, 0.0), (10.0, 10.0)),
+            (1.0, 0.0), 45.0, vec![ (5.0, -5.0), (5.0, 5.0) ]
+        ), (
+            "circle inside circle", circle((0.0, 0.0), 20.0), circle((5.0, 0.0), 5.0),
+            (1.0, 0.0), 20.0, vec![ (0.0, 0.0) ]
+        ), (
+            "circle inside rect", rect((0.0, 0.0), (100.0, 100.0)), circle((30.0, 0.0), 5.0),
+            (1.0, 0.0), 25.0, vec![ (50.0, 0.0) ]
+        )
+    ];
+    for (name, a, b, normal, depth, points) in cases {
+        let contact = a.contact(&b).unwrap_or_else(|| panic!("{}: no contact", name));
+        assert!(close(contact.normal, normal), "{}: normal {:?}", name, contact.normal);
+        assert!((contact.depth - depth).abs() < 1e-9, "{}: depth {}", name, contact.depth);
+        assert_eq!(contact.points.len(), points.len(), "{}: points {:?}", name, contact.points);
+        for point in points {
+            assert!(
+                contact.points.iter().any(|other| close(*other, point)),
+                "{}: missing point {:?} in {:?}", name, point, contact.points
+            );
+        }
+
+        // From the other shape's side it's the same overlap pointing the other way
+        let flipped = b.contact(&a).unwrap();
+        assert!(close(flipped.normal, (-normal.0, -normal.1)), "{}: flipped normal", name);
+        assert!((flipped.depth - depth).abs() < 1e-9, "{}: flipped depth", name);
+        assert!(a.collides_with(&b) && b.collides_with(&a), "{}", name);
+    }
+}
+
+#[test]
+fn shapes_that_are_apart_dont_collide() {
+    let cases = [
+        ("rect/rect", rect((0.0, 0.0), (20.0, 20.0)), rect((21.0, 0.0), (20.0, 20.0))),
+        ("rect/circle", rect((0.0, 0.0), (20.0, 20.0)), circle((17.0, 17.0), 9.0)),
+        ("circle/circle", circle((0.0, 0.0), 10.0), circle((14.2, 14.2), 10.0)),
+        (
+            "rotated rect/rect", rect((0.0, 0.0), (20.0, 20.0)).rotated(45.0, (0.0, 0.0)),
+            rect((25.0, 0.0), (20.0, 20.0))
+        ), ("points", circle((0.0, 0.0), 0.0), polygon((1.0, 0.0), &[ (0.0, 0.0) ]))
+    ];
+    for (name, a, b) in cases {
+        assert!(a.contact(&b).is_none() && b.contact(&a).is_none(), "{}", name);
+        assert!(!a.collides_with(&b) && !b.collides_with(&a), "{}", name);
+    }
+}
+
+#[test]
+fn shapes_with_the_same_center_get_pushed_apart() {
+    // Any direction works, but it has to be far enough to actually separate them
+    let contact = circle((5.0, 5.0), 10.0).contact(&circle((5.0, 5.0), 4.0)).unwrap();
+    assert!(((contact.normal.0.powi(2) + contact.normal.1.powi(2)).sqrt() - 1.0).abs() < 1e-9);
+    assert_eq!(contact.depth, 14.0);
+
+    let contact = circle((5.0, 5.0), 10.0).contact(&circle((5.0, 5.0), 0.0)).unwrap();
+    assert_eq!(contact.depth, 10.0);
+
+    // Points can only touch
+    let contact = circle((5.0, 5.0), 0.0).contact(&polygon((5.0, 5.0), &[ (0.0, 0.0) ])).unwrap();
+    assert_eq!(contact.depth, 0.0);
+}
+
+// The narrow phase before SAT used the rect's height for its x extent
+#[test]
+fn rect_extents_use_the_right_side() {
+    let wide = rect((0.0, 0.0), (100.0, 10.0));
+    assert!(wide.collides_with(&rect((50.0, 0.0), (10.0, 10.0))));
+    let tall = rect((0.0, 0.0), (10.0, 100.0));
+    assert!(!tall.collides_with(&rect((30.0, 0.0), (10.0, 10.0))));
+}
+
+// It also added dy twice instead of squaring it when checking circles against polygon corners
+#[test]
+fn circle_distance_to_a_corner_is_squared() {
+    let square = polygon((0.0, 0.0), &SQUARE);
+    assert!(!square.collides_with(&circle((17.0, 17.0), 9.0)));
+    assert!(square.collides_with(&circle((16.0, 16.0), 9.0)));
+    assert!(!square.collides_with(&circle((-17.0, -17.0), 9.0)));
+}
+
+// And it only tried the first polygon's edges as separating axes
+#[test]
+fn both_polygons_edges_can_separate_them() {
+    let square = polygon((0.0, 0.0), &SQUARE);
+    let triangle = polygon((0.0, 0.0), &[ (8.0, 13.0), (13.0, 8.0), (30.0, 30.0) ]);
+    assert!(!square.collides_with(&triangle));
+    assert!(!triangle.collides_with(&square));
+}
+
+fn near(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+#[test]
+fn rays_hit_boxes_and_circles_where_they_enter() {
+    let square = rect((50.0, 0.0), (20.0, 20.0));
+    let hit = square.raycast((0.0, 5.0), (1.0, 0.0), 100.0).unwrap();
+    assert!(near(hit.dist, 40.0) && close(hit.point, (40.0, 5.0)), "{:?}", hit);
+    assert!(close(hit.normal, (-1.0, 0.0)), "{:?}", hit);
+
+    // Directions don't need to be normalized
+    let hit = square.raycast((50.0, 100.0), (0.0, -3.0), 100.0).unwrap();
+    assert!(near(hit.dist, 90.0) && close(hit.normal, (0.0, 1.0)), "{:?}", hit);
+
+    let ball = circle((0.0, 50.0), 10.0);
+    let hit = ball.raycast((0.0, 0.0), (0.0, 1.0), 100.0).unwrap();
+    assert!(near(hit.dist, 40.0) && close(hit.normal, (0.0, -1.0)), "{:?}", hit);
+    let hit = ball.raycast((-10.0, 0.0), (0.0, 1.0), 100.0).unwrap();
+    assert!(near(hit.dist, 50.0) && close(hit.normal, (-1.0, 0.0)), "{:?}", hit);
+}
+
+#[test]
+fn rays_miss_what_they_dont_reach() {
+    let square = rect((50.0, 0.0), (20.0, 20.0));
+    let ball = circle((0.0, 50.0), 10.0);
+    assert_eq!(square.raycast((0.0, 20.0), (1.0, 0.0), 100.0), None);
+    assert_eq!(square.raycast((0.0, 0.0), (-1.0, 0.0), 100.0), None);
+    assert_eq!(square.raycast((0.0, 0.0), (1.0, 0.0), 39.0), None);
+    assert_eq!(ball.raycast((11.0, 0.0), (0.0, 1.0), 100.0), None);
+    assert_eq!(ball.raycast((0.0, 0.0), (0.0, -1.0), 100.0), None);
+    assert_eq!(ball.raycast((0.0, 0.0), (0.0, 0.0), 100.0), None);
+}
+
+#[test]
+fn rays_starting_inside_hit_right_away() {
+    let shapes = [
+        rect((0.0, 0.0), (20.0, 20.0)), circle((0.0, 0.0), 10.0), polygon((0.0, 0.0), &SQUARE)
+    ];
+    for shape in shapes {
+        let hit = shape.raycast((2.0, 3.0), (0.0, 2.0), 100.0).unwrap();
+        let hit = (hit.point, hit.normal, hit.dist);
+        assert_eq!(hit, ((2.0, 3.0), (0.0, -1.0), 0.0), "{:?}", shape);
+    }
+}
+
+#[test]
+fn casts_stop_where_shapes_first_touch() {
+    let wall = rect((50.0, 0.0), (10.0, 100.0));
+    let hit = rect((0.0, 0.0), (10.0, 10.0)).cast((100.0, 20.0), &wall).unwrap();
+    let dist = 40.0 * (100.0_f64.powi(2) + 20.0_f64.powi(2)).sqrt() / 100.0;
+    assert!(near(hit.dist, dist) && close(hit.normal, (-1.0, 0.0)), "{:?}", hit);
+    assert!(near(hit.point.0, 45.0), "{:?}", hit);
+
+    let hit = circle((0.0, 0.0), 5.0).cast((0.0, 100.0), &circle((3.0, 50.0), 5.0)).unwrap();
+    let dist = 50.0 - (100.0_f64 - 9.0).sqrt();
+    assert!(near(hit.dist, dist), "{:?}", hit);
+    assert!(close(hit.normal, (-0.3, -(1.0_f64 - 0.09).sqrt())), "{:?}", hit);
+
+    // Around a corner, a ball hits the rounded-off corner instead of the box's sides
+    let hit = circle((0.0, 0.0), 5.0).cast((100.0, 100.0), &rect((50.0, 50.0), (20.0, 20.0)));
+    let hit = hit.unwrap();
+    let to_corner = 40.0 * 2.0_f64.sqrt() - 5.0;
+    assert!(near(hit.dist, to_corner), "{:?}", hit);
+    let diag = 1.0 / 2.0_f64.sqrt();
+    assert!(close(hit.normal, (-diag, -diag)) && close(hit.point, (40.0, 40.0)), "{:?}", hit);
+
+    assert_eq!(rect((0.0, 0.0), (10.0, 10.0)).cast((0.0, 100.0), &wall), None);
+    assert_eq!(rect((0.0, 0.0), (10.0, 10.0)).cast((30.0, 0.0), &wall), None);
+}
+
+#[test]
+fn casts_catch_zero_width_shapes() {
+    // A zero-width wall, a point-sized bullet, and a bullet sweeping far past a thin wall
+    let line = rect((50.0, 0.0), (0.0, 100.0));
+    let hit = rect((0.0, 0.0), (10.0, 10.0)).cast((1000.0, 0.0), &line).unwrap();
+    assert!(near(hit.dist, 45.0), "{:?}", hit);
+    let hit = circle((0.0, 0.0), 0.0).cast((1000.0, 0.0), &line).unwrap();
+    assert!(near(hit.dist, 50.0) && close(hit.point, (50.0, 0.0)), "{:?}", hit);
+    let hit = rect((0.0, 0.0), (0.1, 0.1)).cast((1000.0, 3.0), &rect((500.0, 0.0), (0.2, 50.0)));
+    assert!(hit.is_some());
+
+    // Two zero-width lines crossing each other
+    let hit = rect((0.0, 0.0), (0.0, 10.0)).cast((100.0, 0.0), &line).unwrap();
+    assert!(near(hit.dist, 50.0), "{:?}", hit);
+}
+
+#[test]
+fn casts_agree_with_contacts() {
+    let mut rng = StdRng::seed_from_u64(17);
+    let mut hits = 0;
+    for _ in 0..500 {
+        let (from, to) = (random_shape(&mut rng), random_shape(&mut rng));
+        // Aim roughly at the other shape so plenty of them hit
+        let (a, b) = (from.aabb(), to.aabb());
+        let aim = (
+            (b.min.0 + b.max.0 - a.min.0 - a.max.0) as f64 / 2.0,
+            (b.min.1 + b.max.1 - a.min.1 - a.max.1) as f64 / 2.0
+        );
+        let scale = rng.gen_range(0.2..2.0);
+        let motion = (
+            aim.0 * scale + rng.gen_range(-50.0..50.0), aim.1 * scale + rng.gen_range(-50.0..50.0)
+        );
+        let len = (motion.0 * motion.0 + motion.1 * motion.1).sqrt();
+        let at = |dist: f64| from.moved((motion.0 * dist / len, motion.1 * dist / len));
+        match from.cast(motion, &to) {
+            // Touching there, and not a bit before
+            Some(hit) => {
+                hits += 1;
+                assert!(at(hit.dist + 1e-6).collides_with(&to), "{:?} {:?} {:?}", from, to, hit);
+                if hit.dist > 1e-3 {
+                    let before = at(hit.dist - 1e-3);
+                    assert!(!before.collides_with(&to), "{:?} {:?} {:?}", from, to, hit);
+                }
+            }, None => for i in 0..=100 {
+                assert!(!at(len * i as f64 / 100.0).collides_with(&to), "{:?} {:?}", from, to);
+            }
+        }
+    }
+    assert!(hits > 200, "only {} hits", hits);
+}
@@ -0,0 +1,106 @@
+//! Resource enums and simple objects shared by the integration tests. Each test file only uses
+//! some of it
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use sdl2::{
+    event::Event,
+    keyboard::{
+        Mod, Scancode
+    }
+};
+use ycraft::{
+    collision::CollisionShape,
+    obj::{
+        GameObjectBehavior, GameObjectState
+    }, room::Room
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Img {
+    Sheet
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Snd {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Fnt {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Spr {
+    Blank
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Rm {
+    Level,
+    End
+}
+
+/// A room of the shared resources holding whatever Data the test needs
+pub type TestRoom<Data> = Room<Img, Snd, Fnt, Spr, Rm, Data>;
+
+pub fn rect(center: (f64, f64), size: (f64, f64)) -> CollisionShape {
+    CollisionShape::Rect { center, size }
+}
+
+pub fn circle(center: (f64, f64), radius: f64) -> CollisionShape {
+    CollisionShape::Circle { center, radius }
+}
+
+pub fn state<Data>(
+        name: &str, pos: (f64, f64), collider: CollisionShape,
+        custom: Data) -> GameObjectState<Img, Spr, Data> where Data: Clone {
+    GameObjectState::new(name, pos, collider, Spr::Blank, HashMap::new(), custom)
+}
+
+/// Does nothing on its own, for tests that only care about its state
+#[derive(Clone)]
+pub struct Thing<Data> where Data: Clone {
+    pub state: GameObjectState<Img, Spr, Data>
+}
+
+impl<Data> Thing<Data> where Data: Clone {
+    pub fn new(state: GameObjectState<Img, Spr, Data>) -> Box<Self> {
+        Box::new(Self { state })
+    }
+}
+
+impl<Data> GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Thing<Data> where
+        Data: Clone + 'static {
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
+    }
+
+    fn on_reset(&mut self) -> bool {
+        false
+    }
+}
+
+pub fn key(scancode: Scancode, down: bool) -> Event {
+    if down {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: None,
+            scancode: Some(scancode),
+            keymod: Mod::NOMOD,
+            repeat: false
+        }
+    } else {
+        Event::KeyUp {
+            timestamp: 0,
+            window_id: 0,
+            keycode: None,
+            scancode: Some(scancode),
+            keymod: Mod::NOMOD,
+            repeat: false
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Controller bindings, both with faked events and with a virtual controller through SDL
+
+mod common;
+
+use std::collections::HashMap;
+use sdl2::{
+    controller::{
+        Axis, Button
+    }, event::Event
+};
+use ycraft::{
+    headless::Headless,
+    input::{
+        Binding, Input
+    }, room::Room
+};
+use common::{
+    Fnt, Img, Rm, Snd, Spr
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Act {
+    Jump,
+    Right
+}
+
+fn button(which: u32, button: Button, down: bool) -> Event {
+    if down {
+        Event::ControllerButtonDown { timestamp: 0, which, button }
+    } else {
+        Event::ControllerButtonUp { timestamp: 0, which, button }
+    }
+}
+
+fn axis(which: u32, axis: Axis, value: i16) -> Event {
+    Event::ControllerAxisMotion { timestamp: 0, which, axis, value }
+}
+
+fn two_pads() -> Input {
+    let mut input = Input::new();
+    input.bind(Act::Jump, Binding::Button(Button::A));
+    input.bind(Act::Right, Binding::Axis { axis: Axis::LeftX, positive: true });
+    input.connect_controller(10, "first");
+    input.connect_controller(20, "second");
+    input
+}
+
+#[test]
+fn one_pad_letting_go_keeps_the_other_held() {
+    let mut input = two_pads();
+    input.handle_sdl_event(&button(10, Button::A, true));
+    input.handle_sdl_event(&button(20, Button::A, true));
+    assert!(input.just_pressed(Act::Jump));
+    input.end_update();
+
+    input.handle_sdl_event(&button(10, Button::A, false));
+    assert!(input.is_held(Act::Jump));
+    assert!(!input.just_released(Act::Jump));
+    assert!(!input.controller(0).unwrap().is_held(Button::A));
+    assert!(input.controller(1).unwrap().is_held(Button::A));
+    input.end_update();
+
+    input.handle_sdl_event(&button(20, Button::A, false));
+    assert!(!input.is_held(Act::Jump));
+    assert!(input.just_released(Act::Jump));
+}
+
+#[test]
+fn axis_bindings_use_the_furthest_pushed_pad() {
+    let mut input = two_pads();
+    input.handle_sdl_event(&axis(10, Axis::LeftX, i16::MAX));
+    input.handle_sdl_event(&axis(20, Axis::LeftX, i16::MAX / 4));
+    assert!(input.is_held(Act::Right));
+    assert_eq!(input.value(Act::Right), 1.0);
+    input.end_update();
+
+    // The second pad isn't pushed far enough to hold the binding on its own
+    input.handle_sdl_event(&axis(10, Axis::LeftX, 0));
+    assert!(!input.is_held(Act::Right));
+    assert!(input.just_released(Act::Right));
+    assert!(input.value(Act::Right) > 0.0 && input.value(Act::Right) < 0.5);
+}
+
+#[test]
+fn events_from_unknown_pads_are_ignored() {
+    let mut input = two_pads();
+    input.handle_sdl_event(&button(30, Button::A, true));
+    input.handle_sdl_event(&axis(30, Axis::LeftX, i16::MAX));
+    assert!(!input.is_held(Act::Jump));
+    assert_eq!(input.value(Act::Right), 0.0);
+}
+
+// SDL can only be started from one thread per process, so everything that needs it is one test
+#[test]
+fn virtual_controllers_drive_input() {
+    let ctx = sdl2::init().unwrap();
+    let rooms = HashMap::from([ (Rm::Level, Room::new(vec![], false)) ]);
+    let mut headless = Headless::<Img, Snd, Fnt, Spr, Rm, ()>::new(Rm::Level, &rooms, &vec![]);
+    headless.input_mut().bind(Act::Jump, Binding::Button(Button::A));
+    headless.enable_controllers(&ctx).unwrap();
+
+    let first = headless.controllers().unwrap().attach_virtual().unwrap();
+    let second = headless.controllers().unwrap().attach_virtual().unwrap();
+    headless.step(0.0).unwrap();
+    assert!(headless.input_mut().controller(0).is_some());
+    assert!(headless.input_mut().controller(1).is_some());
+    assert!(!headless.input_mut().is_held(Act::Jump));
+
+    first.set_button(Button::A, true).unwrap();
+    second.set_button(Button::A, true).unwrap();
+    headless.step(0.0).unwrap();
+    assert!(headless.input_mut().is_held(Act::Jump));
+
+    // Letting go on one controller doesn't release the other
+    first.set_button(Button::A, false).unwrap();
+    headless.step(0.0).unwrap();
+    assert!(headless.input_mut().is_held(Act::Jump));
+    assert!(!headless.input_mut().controller(0).unwrap().is_held(Button::A));
+
+    second.set_button(Button::A, false).unwrap();
+    headless.step(0.0).unwrap();
+    assert!(!headless.input_mut().is_held(Act::Jump));
+
+    drop(first);
+    headless.step(0.0).unwrap();
+    assert!(headless.input_mut().controller(0).is_none());
+    assert!(headless.input_mut().controller(1).is_some());
+}
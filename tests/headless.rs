@@ -0,0 +1,195 @@
+//! Drive rooms through the headless runner the same way a game's tests would
+
+mod common;
+
+use std::collections::HashMap;
+use sdl2::keyboard::Scancode;
+use ycraft::{
+    headless::Headless,
+    input::{
+        Binding, Input
+    }, obj::{
+        ControlObject, GameObject, GameObjectBehavior, GameObjectState, Others, Update
+    }, room::Room
+};
+use common::{
+    key, rect, state, Fnt, Img, Rm, Snd, Spr
+};
+
+const SPD: f64 = 100.0;
+const GOAL_X: f64 = 100.0;
+const DELTA: f64 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Act {
+    Left,
+    Right
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Data {
+    Runner,
+    Goal,
+    Resets(usize)
+}
+
+/// Moves with the Left and Right actions and goes to the end room once it reaches the goal
+#[derive(Clone)]
+struct Runner {
+    state: GameObjectState<Img, Spr, Data>,
+    at_goal: bool
+}
+
+impl Runner {
+    fn new() -> Self {
+        Self {
+            state: state("runner", (0.0, 0.0), rect((0.0, 0.0), (10.0, 10.0)), Data::Runner),
+            at_goal: false
+        }
+    }
+}
+
+impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Runner {
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
+    }
+
+    fn on_reset(&mut self) -> bool {
+        *self = Self::new();
+        false
+    }
+
+    fn update(
+            &mut self, delta: f64, input: &mut Input,
+            _ctl_objs: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+            _others: &Others<Img, Snd, Fnt, Spr, Rm, Data>
+            ) -> Update<Img, Snd, Fnt, Spr, Rm, Data> {
+        if self.at_goal {
+            return (Some(Rm::End), vec![]);
+        }
+        self.state.pos.0 += input.axis(Act::Left, Act::Right) * SPD * delta;
+        (None, vec![])
+    }
+
+    fn on_collision(&mut self, other: &GameObject<Img, Snd, Fnt, Spr, Rm, Data>) {
+        if other.state().custom == Data::Goal {
+            self.at_goal = true;
+        }
+    }
+}
+
+/// Sits still. Also used to count how many times the end room gets reset
+#[derive(Clone)]
+struct Marker {
+    state: GameObjectState<Img, Spr, Data>
+}
+
+impl Marker {
+    fn new(pos: (f64, f64), custom: Data) -> Self {
+        Self {
+            state: state("marker", pos, rect((0.0, 0.0), (10.0, 10.0)), custom)
+        }
+    }
+}
+
+impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Marker {
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
+    }
+
+    fn on_reset(&mut self) -> bool {
+        if let Data::Resets(count) = self.state.custom {
+            self.state.custom = Data::Resets(count + 1);
+        }
+        false
+    }
+}
+
+fn headless() -> Headless<Img, Snd, Fnt, Spr, Rm, Data> {
+    let rooms = HashMap::from([
+        (Rm::Level, Room::new(vec![
+            Box::new(Runner::new()) as GameObject<Img, Snd, Fnt, Spr, Rm, Data>,
+            Box::new(Marker::new((GOAL_X, 0.0), Data::Goal))
+        ], false)),
+        (Rm::End, Room::new(vec![
+            Box::new(Marker::new((0.0, 0.0), Data::Resets(0))) as GameObject<_, _, _, _, _, _>
+        ], false))
+    ]);
+    let mut headless = Headless::new(Rm::Level, &rooms, &vec![]);
+    headless.input_mut().bind(Act::Left, Binding::Key(Scancode::A));
+    headless.input_mut().bind(Act::Right, Binding::Key(Scancode::D));
+    headless
+}
+
+fn runner_x(headless: &Headless<Img, Snd, Fnt, Spr, Rm, Data>) -> f64 {
+    headless.objs().iter()
+        .find(|obj| obj.state().custom == Data::Runner)
+        .map(|obj| obj.state().pos.0)
+        .unwrap()
+}
+
+#[test]
+fn step_without_input_changes_nothing() {
+    let mut headless = headless();
+    headless.step(DELTA).unwrap();
+    assert_eq!(headless.room(), Rm::Level);
+    assert_eq!(runner_x(&headless), 0.0);
+    assert_eq!(headless.objs().len(), 2);
+}
+
+#[test]
+fn pushed_events_are_handled_on_the_next_step() {
+    let mut headless = headless();
+    headless.push_event(key(Scancode::D, true));
+    assert_eq!(runner_x(&headless), 0.0);
+    headless.step(DELTA).unwrap();
+    assert!((runner_x(&headless) - SPD * DELTA).abs() < 1e-9);
+
+    // Still held until it's released
+    headless.step(DELTA).unwrap();
+    assert!((runner_x(&headless) - 2.0 * SPD * DELTA).abs() < 1e-9);
+    headless.push_event(key(Scancode::D, false));
+    headless.step(DELTA).unwrap();
+    assert!((runner_x(&headless) - 2.0 * SPD * DELTA).abs() < 1e-9);
+
+    headless.push_event(key(Scancode::A, true));
+    headless.step(DELTA).unwrap();
+    assert!((runner_x(&headless) - SPD * DELTA).abs() < 1e-9);
+}
+
+#[test]
+fn step_frames_uses_the_same_delta_every_frame() {
+    let mut headless = headless();
+    headless.push_event(key(Scancode::D, true));
+    headless.step_frames(5, DELTA).unwrap();
+    assert!((runner_x(&headless) - 5.0 * SPD * DELTA).abs() < 1e-9);
+    headless.step_frames(2, DELTA / 2.0).unwrap();
+    assert!((runner_x(&headless) - 6.0 * SPD * DELTA).abs() < 1e-9);
+}
+
+#[test]
+fn reaching_the_goal_changes_rooms() {
+    let mut headless = headless();
+    headless.push_event(key(Scancode::D, true));
+
+    // The runner's box touches the goal's on the update it gets within 10 pixels of it, then it
+    // asks for the end room on the update after
+    let frames_to_goal = ((GOAL_X - 10.0) / (SPD * DELTA)).ceil() as usize;
+    headless.step_frames(frames_to_goal, DELTA).unwrap();
+    assert_eq!(headless.room(), Rm::Level);
+    headless.step(DELTA).unwrap();
+    assert_eq!(headless.room(), Rm::End);
+
+    // Changing to a room that isn't persistant resets it
+    assert_eq!(headless.objs().len(), 1);
+    assert_eq!(headless.objs()[0].state().custom, Data::Resets(1));
+    assert_eq!(headless.rooms()[&Rm::Level].objs.len(), 2);
+}
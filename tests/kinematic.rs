@@ -0,0 +1,155 @@
+//! Objects moved with move_and_slide against solid objects, solid tiles, and triggers
+
+mod common;
+
+use ycraft::{
+    collision::CollisionShape,
+    kinematic::move_and_slide,
+    obj::{
+        GameObjectState, ObjectId
+    }, tilemap::{
+        TileLayer, Tilemap
+    }
+};
+use common::{
+    circle, rect, state, Img, Spr, Thing
+};
+
+type TestRoom = common::TestRoom<()>;
+
+fn solid(room: &mut TestRoom, pos: (f64, f64), collider: CollisionShape) -> ObjectId {
+    room.add(Thing::new(state("solid", pos, collider, ()).with_solid(true)))
+}
+
+/// A 10x10 box that isn't in the room, so it needs an id of its own
+fn mover(pos: (f64, f64)) -> GameObjectState<Img, Spr, ()> {
+    let mut mover = state("mover", pos, rect((0.0, 0.0), (10.0, 10.0)), ());
+    mover.id = ObjectId(1000);
+    mover
+}
+
+fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+    (a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6
+}
+
+#[test]
+fn landing_on_a_floor_stops_the_fall_but_not_the_walk() {
+    let mut room = TestRoom::new(vec![], false);
+    let floor = solid(&mut room, (0.0, 50.0), rect((0.0, 0.0), (200.0, 10.0)));
+
+    let mut mover = mover((0.0, 30.0));
+    let res = move_and_slide(&mut mover, (10.0, 20.0), &room);
+    assert!(close(mover.pos, (10.0, 40.0)), "{:?}", mover.pos);
+    assert!(res.on_floor && !res.on_wall && !res.on_ceiling, "{:?}", res);
+    assert!(close(res.remainder, (0.0, 10.0)), "{:?}", res.remainder);
+    assert_eq!(res.hits.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![ floor ]);
+
+    // Standing still on it still counts
+    let res = move_and_slide(&mut mover, (0.0, 0.0), &room);
+    assert!(res.on_floor, "{:?}", res);
+    assert!(close(mover.pos, (10.0, 40.0)), "{:?}", mover.pos);
+}
+
+#[test]
+fn solid_tiles_are_floors_too() {
+    let floor = Tilemap::grid((4, 2), (16.0, 16.0)).with_pos((-32.0, 32.0))
+        .with_layer(TileLayer::new("floor", (0..8).map(|i| (i >= 4).then_some(0)).collect())
+            .with_solid(true));
+    let room = TestRoom::new(vec![], false).with_tilemap(floor);
+
+    // The tiles' top is at y = 48
+    let mut mover = mover((0.0, 0.0));
+    let res = move_and_slide(&mut mover, (0.0, 100.0), &room);
+    assert!(close(mover.pos, (0.0, 43.0)), "{:?}", mover.pos);
+    assert!(res.on_floor, "{:?}", res);
+    assert!(res.hits.iter().all(|(id, _)| *id == ObjectId::NONE));
+}
+
+#[test]
+fn tiles_only_block_objects_with_their_collision_layer_in_the_mask() {
+    let floor = Tilemap::grid((4, 2), (16.0, 16.0)).with_pos((-32.0, 32.0))
+        .with_layer(TileLayer::new("floor", (0..8).map(|i| (i >= 4).then_some(0)).collect())
+            .with_solid(true)
+            .with_collision_layer(2));
+    let room = TestRoom::new(vec![], false).with_tilemap(floor);
+    for (mask, end) in [ (2, 43.0), (1, 100.0) ] {
+        let mut mover = mover((0.0, 0.0)).with_layers(1, mask);
+        move_and_slide(&mut mover, (0.0, 100.0), &room);
+        assert!(close(mover.pos, (0.0, end)), "mask {}: {:?}", mask, mover.pos);
+    }
+}
+
+#[test]
+fn walls_stop_sideways_motion_only() {
+    let mut room = TestRoom::new(vec![], false);
+    solid(&mut room, (50.0, 0.0), rect((0.0, 0.0), (10.0, 200.0)));
+
+    let mut mover = mover((30.0, 0.0));
+    let res = move_and_slide(&mut mover, (20.0, 10.0), &room);
+    assert!(close(mover.pos, (40.0, 10.0)), "{:?}", mover.pos);
+    assert!(res.on_wall && !res.on_floor && !res.on_ceiling, "{:?}", res);
+    assert!(close(res.remainder, (10.0, 0.0)), "{:?}", res.remainder);
+}
+
+#[test]
+fn walking_into_a_slope_climbs_it() {
+    // Rises to the right, from (-50, 50) to (50, -50)
+    let mut room = TestRoom::new(vec![], false);
+    let slope = CollisionShape::Polygon {
+        center: (0.0, 0.0),
+        points: vec![ (-50.0, 50.0), (50.0, 50.0), (50.0, -50.0) ]
+    };
+    solid(&mut room, (0.0, 0.0), slope);
+
+    // A ball resting on the slope right above (-10, 10)
+    let offset = 5.0 / 2.0_f64.sqrt();
+    let start = (-10.0 - offset, 10.0 - offset);
+    let mut ball = state("ball", start, circle((0.0, 0.0), 5.0), ());
+    ball.id = ObjectId(1000);
+    let res = move_and_slide(&mut ball, (20.0, 0.0), &room);
+
+    // Only the part of the motion along the slope happens
+    let end = (start.0 + 10.0, start.1 - 10.0);
+    let off = (ball.pos.0 - end.0, ball.pos.1 - end.1);
+    assert!(off.0.abs() < 1e-3 && off.1.abs() < 1e-3, "{:?}", ball.pos);
+    assert!(res.on_floor && !res.on_wall, "{:?}", res);
+}
+
+#[test]
+fn ceilings_stop_jumps() {
+    let mut room = TestRoom::new(vec![], false);
+    solid(&mut room, (0.0, -50.0), rect((0.0, 0.0), (200.0, 10.0)));
+
+    let mut mover = mover((0.0, -30.0));
+    let res = move_and_slide(&mut mover, (5.0, -20.0), &room);
+    assert!(close(mover.pos, (5.0, -40.0)), "{:?}", mover.pos);
+    assert!(res.on_ceiling && !res.on_floor && !res.on_wall, "{:?}", res);
+    assert!(close(res.remainder, (0.0, -10.0)), "{:?}", res.remainder);
+}
+
+#[test]
+fn triggers_are_passed_through_and_reported() {
+    let mut room = TestRoom::new(vec![], false);
+    let trigger = room.add(Thing::new(
+        state("trigger", (30.0, 0.0), rect((0.0, 0.0), (10.0, 10.0)), ()).with_trigger(true)
+    ));
+
+    let mut mover = mover((0.0, 0.0));
+    let res = move_and_slide(&mut mover, (60.0, 0.0), &room);
+    assert!(close(mover.pos, (60.0, 0.0)), "{:?}", mover.pos);
+    assert_eq!(res.triggers, vec![ trigger ]);
+    assert!(res.hits.is_empty(), "{:?}", res.hits);
+    assert!(!res.on_floor && !res.on_wall && !res.on_ceiling);
+}
+
+#[test]
+fn fast_movers_dont_tunnel_through_thin_walls() {
+    let mut room = TestRoom::new(vec![], false);
+    let wall = solid(&mut room, (30.0, 0.0), rect((0.0, 0.0), (1.0, 100.0)));
+
+    let mut mover = mover((0.0, 0.0));
+    let res = move_and_slide(&mut mover, (500.0, 0.0), &room);
+    assert!(close(mover.pos, (24.5, 0.0)), "{:?}", mover.pos);
+    assert!(res.on_wall, "{:?}", res);
+    assert_eq!(res.hits[0].0, wall);
+}
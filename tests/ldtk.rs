@@ -0,0 +1,148 @@
+//! Loading an LDtk project, with one level saved in the project and one in its own file
+
+mod common;
+
+use std::collections::HashMap;
+use sdl2::{
+    pixels::Color,
+    rect::Rect
+};
+use ycraft::{
+    factory::{
+        BuildResult, ObjectDef, ObjectFactory, Property
+    }, ldtk::LdtkLoader,
+    tilemap::TileFlip
+};
+use common::{
+    rect, state, Fnt, Img, Rm, Snd, Spr, TestRoom, Thing
+};
+
+const PROJECT: &str = include_str!("fixtures/ldtk/project.ldtk");
+const LEVEL_1: &str = include_str!("fixtures/ldtk/Level_1.ldtkl");
+
+type Room = TestRoom<ObjectDef>;
+
+/// Builds objects that keep the definition they came from, so tests can look at it
+fn build_entity(def: &ObjectDef) -> BuildResult<Img, Snd, Fnt, Spr, Rm, ObjectDef> {
+    let shape = def.shape.clone().unwrap_or(rect((0.0, 0.0), (0.0, 0.0)));
+    Ok(Thing::new(state(&def.name, def.pos, shape, def.clone()).with_depth(def.depth)))
+}
+
+fn load() -> HashMap<Rm, Room> {
+    let factory = ObjectFactory::new()
+        .with_kind("Player", build_entity)
+        .with_kind("Chest", build_entity);
+    LdtkLoader::new()
+        .with_image("tiles.png", Img::Sheet)
+        .with_level("Level_0", Rm::Level)
+        .with_level("Level_1", Rm::End)
+        .with_level_file("Level_1.ldtkl", LEVEL_1.as_bytes())
+        .with_solid_layer("Walls")
+        .load(PROJECT.as_bytes(), &factory, false)
+        .unwrap()
+}
+
+fn entity<'a>(room: &'a Room, iid: &str) -> &'a ObjectDef {
+    &room.objs.iter().find(|obj| obj.state().name == iid).unwrap().state().custom
+}
+
+#[test]
+fn int_grid_values_are_tiles_and_make_cells_solid() {
+    let rooms = load();
+    let walls = &rooms[&Rm::Level].tilemaps[1];
+    assert_eq!(walls.layers[0].name, "Walls");
+    assert!(walls.layers[0].solid && !walls.layers[0].visible);
+
+    // tile() gives the value, with 0 as None
+    assert_eq!(walls.tile(0, (0, 0)), Some(1));
+    assert_eq!(walls.tile(0, (3, 0)), Some(2));
+    assert_eq!(walls.tile(0, (2, 0)), None);
+    assert!(walls.is_solid((1, 1)) && !walls.is_solid((1, 0)));
+
+    let found = walls.solids_overlapping(&rect((16.0, 16.0), (8.0, 8.0))).into_iter()
+        .map(|(cell, _)| cell)
+        .collect::<Vec<_>>();
+    assert_eq!(found, vec![ (0, 0), (0, 1), (1, 1) ]);
+
+    // The auto-layer tiles drawn over the values aren't solid themselves
+    assert_eq!(walls.layers[1].tiles[0], Some(1));
+    assert!(!walls.layers[1].solid && walls.layers[1].visible);
+    assert_eq!(walls.flip(1, (3, 0)), TileFlip::new(false, true));
+}
+
+#[test]
+fn stacked_tiles_get_layers_of_their_own_and_keep_their_flips() {
+    let rooms = load();
+    let deco = &rooms[&Rm::Level].tilemaps[0];
+    assert_eq!(deco.layers.len(), 2);
+    assert_eq!(deco.tile(0, (0, 0)), Some(5));
+    assert_eq!(deco.tile(0, (1, 1)), Some(7));
+    assert_eq!(deco.tile(1, (0, 0)), Some(6));
+    assert_eq!(deco.tile(1, (1, 1)), None);
+
+    // LDtk's f is 1 for x and 2 for y
+    assert_eq!(deco.flip(0, (0, 0)), TileFlip::default());
+    assert_eq!(deco.flip(0, (1, 1)), TileFlip::new(true, true));
+    assert_eq!(deco.flip(1, (0, 0)), TileFlip::new(true, false));
+}
+
+#[test]
+fn layers_keep_their_order_and_offsets() {
+    let rooms = load();
+    let room = &rooms[&Rm::Level];
+    let (deco, walls) = (&room.tilemaps[0], &room.tilemaps[1]);
+
+    // Entities are on top, then the deco above the walls. Stacked tiles share their layer's depth
+    assert!(room.objs.iter().all(|obj| obj.state().depth == 0));
+    assert_eq!(deco.layers.iter().map(|layer| layer.depth).collect::<Vec<_>>(), vec![ 1, 1 ]);
+    assert_eq!(walls.layers.iter().map(|layer| layer.depth).collect::<Vec<_>>(), vec![ 2, 2 ]);
+
+    assert_eq!(deco.pos, (0.0, -8.0));
+    assert_eq!(walls.pos, (0.0, 0.0));
+    assert_eq!(room.cam.bounds, Some(Rect::new(0, 0, 64, 32)));
+    assert_eq!(room.bg_color, Some(Color::RGB(0x10, 0x20, 0x30)));
+}
+
+#[test]
+fn entities_are_placed_by_their_pivot_and_get_their_fields() {
+    let rooms = load();
+    let room = &rooms[&Rm::Level];
+
+    // The marker has no constructor
+    let names = room.objs.iter().map(|obj| obj.state().name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec![ "p1", "c1" ]);
+
+    // The player's pivot is its bottom middle, and the layer is offset by (4, 2)
+    let player = entity(room, "p1");
+    assert_eq!(player.kind, "Player");
+    assert_eq!(player.pos, (15.0, 14.0));
+    assert_eq!(player.shape, Some(rect((5.0, 10.0), (10.0, 20.0))));
+    assert_eq!(entity(room, "c1").pos, (44.0, 10.0));
+
+    // Points are the middle of their cell in the room, and references are the iid of the entity
+    assert_eq!(player.prop("hp"), Some(&Property::Int(3)));
+    assert_eq!(player.prop("target"), Some(&Property::Point((44.0, 26.0))));
+    assert_eq!(player.prop("path"), Some(&Property::List(vec![ Property::Point((12.0, 10.0)) ])));
+    assert_eq!(player.prop("buddy"), Some(&Property::String("c1".to_string())));
+
+    // Unset fields are left out
+    assert_eq!(player.prop("nobody"), None);
+}
+
+#[test]
+fn levels_in_their_own_files_are_loaded_from_them() {
+    let rooms = load();
+    let room = &rooms[&Rm::End];
+    assert_eq!(room.cam.bounds, Some(Rect::new(0, 0, 32, 16)));
+    assert_eq!(room.tilemaps.len(), 1);
+    assert_eq!(room.tilemaps[0].layers[0].tiles, vec![ None, Some(3) ]);
+    assert!(room.tilemaps[0].is_solid((1, 0)));
+
+    // Without the file the project can't be loaded
+    let err = LdtkLoader::new()
+        .with_level("Level_1", Rm::End)
+        .load(PROJECT.as_bytes(), &ObjectFactory::<Img, Snd, Fnt, Spr, Rm, ()>::new(), false)
+        .err()
+        .unwrap();
+    assert!(err.contains("Level_1.ldtkl"), "{}", err);
+}
@@ -0,0 +1,183 @@
+//! Bodies moved by the room's physics against each other, solid objects, and solid tiles
+
+mod common;
+
+use rand::{
+    rngs::StdRng,
+    Rng, SeedableRng
+};
+use ycraft::{
+    collision::CollisionShape,
+    input::Input,
+    obj::ObjectId,
+    physics::{
+        Body, BodyKind
+    }, tilemap::{
+        TileLayer, Tilemap
+    }
+};
+use common::{
+    state, Img, Thing
+};
+
+const DELTA: f64 = 1.0 / 60.0;
+
+type TestRoom = common::TestRoom<()>;
+
+fn add(
+        room: &mut TestRoom, pos: (f64, f64), collider: CollisionShape,
+        body: Option<Body>) -> ObjectId {
+    let state = state("thing", pos, collider, ());
+    let state = match body {
+        Some(body) => state.with_body(body),
+        None => state.with_solid(true)
+    };
+    room.add(Thing::new(state))
+}
+
+fn run(room: &mut TestRoom, steps: usize) {
+    let mut input = Input::new();
+    for _ in 0..steps {
+        room.update(DELTA, &mut input, &vec![]);
+    }
+}
+
+fn body(room: &TestRoom, id: ObjectId) -> ((f64, f64), (f64, f64)) {
+    let state = room.get(id).unwrap().state();
+    (state.pos, state.body.as_ref().unwrap().vel)
+}
+
+/// A row of solid 16x16 tiles with its top at y = 64
+fn floor_tiles() -> Tilemap<Img> {
+    Tilemap::grid((8, 5), (16.0, 16.0))
+        .with_pos((-64.0, 0.0))
+        .with_layer(TileLayer::new("floor", (0..40).map(|i| (i >= 32).then_some(0)).collect())
+            .with_solid(true))
+}
+
+/// A pile of random bodies dropped into a box made of a solid floor object and tile walls
+fn pile(seed: u64) -> TestRoom {
+    let walls = Tilemap::grid((20, 15), (16.0, 16.0)).with_layer(TileLayer::new(
+        "walls", (0..300).map(|i| (i % 20 == 0 || i % 20 == 19).then_some(0)).collect()
+    ).with_solid(true));
+    let mut room = TestRoom::new(vec![], false)
+        .with_gravity((0.0, 500.0))
+        .with_tilemap(walls);
+    let floor = CollisionShape::Rect { center: (0.0, 0.0), size: (320.0, 16.0) };
+    add(&mut room, (160.0, 232.0), floor, None);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..40 {
+        let pos = (rng.gen_range(24.0..296.0), rng.gen_range(0.0..200.0));
+        let collider = if rng.gen_bool(0.5) {
+            CollisionShape::Circle { center: (0.0, 0.0), radius: rng.gen_range(3.0..10.0) }
+        } else {
+            CollisionShape::Rect {
+                center: (0.0, 0.0), size: (rng.gen_range(4.0..20.0), rng.gen_range(4.0..20.0))
+            }
+        };
+        let body = Body::new(BodyKind::Dynamic)
+            .with_vel((rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)))
+            .with_mass(rng.gen_range(0.5..4.0))
+            .with_friction(rng.gen_range(0.0..1.0))
+            .with_restitution(rng.gen_range(0.0..0.8));
+        add(&mut room, pos, collider, Some(body));
+    }
+    room
+}
+
+#[test]
+fn same_scene_steps_the_same_every_time() {
+    let (mut first, mut second) = (pile(7), pile(7));
+    run(&mut first, 180);
+    run(&mut second, 180);
+
+    let bits = |room: &TestRoom| room.objs.iter()
+        .map(|obj| {
+            let state = obj.state();
+            let vel = state.body.as_ref().map_or((0.0, 0.0), |body| body.vel);
+            [ state.pos.0, state.pos.1, vel.0, vel.1 ].map(f64::to_bits)
+        }).collect::<Vec<_>>();
+    assert_eq!(bits(&first), bits(&second));
+
+    // And nothing fell through the floor or the walls
+    for obj in first.objs.iter().skip(1) {
+        let pos = obj.state().pos;
+        assert!(pos.0 > 16.0 && pos.0 < 304.0 && pos.1 < 224.0, "escaped to {:?}", pos);
+    }
+}
+
+#[test]
+fn bodies_bounce_off_solid_tiles_by_their_restitution() {
+    for (restitution, bounce) in [ (1.0, -200.0), (0.5, -100.0), (0.0, 0.0) ] {
+        let mut room = TestRoom::new(vec![], false).with_tilemap(floor_tiles());
+        let ball = add(
+            &mut room, (0.0, 0.0), CollisionShape::Circle { center: (0.0, 0.0), radius: 8.0 },
+            Some(
+                Body::new(BodyKind::Dynamic).with_vel((0.0, 200.0)).with_restitution(restitution)
+            )
+        );
+        run(&mut room, 30);
+        let (pos, vel) = body(&room, ball);
+        assert!((vel.1 - bounce).abs() < 1e-9, "restitution {}: vel {:?}", restitution, vel);
+        // Resting bodies are allowed to sink in a tiny bit
+        assert!(pos.1 < 56.1, "restitution {}: sank to {:?}", restitution, pos);
+    }
+}
+
+#[test]
+fn friction_slows_bodies_sliding_on_solid_objects() {
+    let slide = |friction: f64| {
+        let mut room = TestRoom::new(vec![], false).with_gravity((0.0, 500.0));
+        add(
+            &mut room, (0.0, 64.0),
+            CollisionShape::Rect { center: (0.0, 0.0), size: (2000.0, 16.0) }, None
+        );
+        let crate_id = add(
+            &mut room, (0.0, 48.0),
+            CollisionShape::Rect { center: (0.0, 0.0), size: (16.0, 16.0) },
+            Some(Body::new(BodyKind::Dynamic).with_vel((100.0, 0.0)).with_friction(friction))
+        );
+        run(&mut room, 60);
+        body(&room, crate_id)
+    };
+
+    // The floor has the default friction, and a contact uses the geometric mean of both sides'
+    // frictions, so ice on anything slides
+    let (pos, vel) = slide(0.0);
+    assert!((vel.0 - 100.0).abs() < 1e-9, "ice slowed down to {:?}", vel);
+    assert!((pos.0 - 100.0).abs() < 1e-6 && pos.1 <= 48.0 + 0.1, "ice ended up at {:?}", pos);
+
+    let (pos, vel) = slide(1.0);
+    assert!(vel.0.abs() < 1e-9, "rough crate still moving at {:?}", vel);
+    assert!(pos.0 > 0.0 && pos.0 < 100.0, "rough crate ended up at {:?}", pos);
+}
+
+#[test]
+fn triggers_and_bodies_masking_out_the_tiles_fall_through() {
+    let floor = || floor_tiles().with_layer(
+        TileLayer::new("ledge", (0..40).map(|i| (i >= 32).then_some(0)).collect())
+            .with_solid(true)
+            .with_collision_layer(4)
+    );
+    let ball = || state("ball", (0.0, 0.0), CollisionShape::Circle {
+        center: (0.0, 0.0), radius: 8.0
+    }, ()).with_body(Body::new(BodyKind::Dynamic).with_vel((0.0, 200.0)));
+
+    // (ball, whether it should land)
+    let cases = [
+        (ball(), true),
+        (ball().with_layers(1, 1), true),
+        (ball().with_layers(1, 4), true),
+        (ball().with_layers(1, 2), false),
+        (ball().with_trigger(true), false)
+    ];
+    for (ball, lands) in cases {
+        let mut room = TestRoom::new(vec![], false).with_tilemap(floor());
+        let (mask, trigger) = (ball.mask, ball.trigger);
+        let id = room.add(Thing::new(ball));
+        run(&mut room, 30);
+        let (pos, _) = body(&room, id);
+        assert_eq!(pos.1 < 56.1, lands, "mask {:b}, trigger {}: ended at {:?}", mask, trigger, pos);
+    }
+}
@@ -0,0 +1,175 @@
+//! Finding objects with Query, both on a plain list of objects and on the others passed to update
+
+mod common;
+
+use sdl2::rect::Rect;
+use ycraft::{
+    collision::CollisionShape,
+    input::Input,
+    obj::{
+        ControlObject, GameObject, GameObjectBehavior, GameObjectState, ObjectId, Others, Update
+    }, query::Query
+};
+use common::{
+    circle, rect, Fnt, Img, Rm, Snd, Spr, Thing
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Data {
+    Coin(u32),
+    Enemy,
+    Wall,
+    /// How many other seekers it saw and which one was closest
+    Seeker(usize, Option<ObjectId>)
+}
+
+type Obj = GameObject<Img, Snd, Fnt, Spr, Rm, Data>;
+
+fn obj(name: &str, pos: (f64, f64), collider: CollisionShape, custom: Data) -> Obj {
+    Thing::new(common::state(name, pos, collider, custom))
+}
+
+/// Two coins, an enemy, and a wall:
+///
+/// - coin1: 10x10 box at (0, 0)
+/// - coin2: circle with radius 5 at (100, 0)
+/// - enemy: 20x20 box at (50, 50)
+/// - wall: 10x40 box at (-40, 0)
+fn objs() -> Vec<Obj> {
+    vec![
+        obj("coin1", (0.0, 0.0), rect((0.0, 0.0), (10.0, 10.0)), Data::Coin(1)),
+        obj("coin2", (100.0, 0.0), circle((0.0, 0.0), 5.0), Data::Coin(2)),
+        obj("enemy", (50.0, 50.0), rect((0.0, 0.0), (20.0, 20.0)), Data::Enemy),
+        obj("wall", (-40.0, 0.0), rect((0.0, 0.0), (10.0, 40.0)), Data::Wall)
+    ]
+}
+
+fn names(found: Vec<&Obj>) -> Vec<&str> {
+    found.iter().map(|obj| obj.state().name.as_str()).collect()
+}
+
+fn name(found: Option<&Obj>) -> Option<&str> {
+    found.map(|obj| obj.state().name.as_str())
+}
+
+#[test]
+fn slices_find_objects_by_kind() {
+    let objs = objs();
+    let objs = objs.as_slice();
+    // The number inside doesn't matter
+    assert_eq!(names(objs.of_kind(&Data::Coin(0))), vec![ "coin1", "coin2" ]);
+    assert_eq!(objs.count_kind(&Data::Coin(7)), 2);
+    assert_eq!(names(objs.of_kind(&Data::Wall)), vec![ "wall" ]);
+    assert!(objs.of_kind(&Data::Seeker(0, None)).is_empty());
+}
+
+#[test]
+fn slices_find_the_nearest_object() {
+    let objs = objs();
+    let objs = objs.as_slice();
+    assert_eq!(name(objs.nearest((90.0, 0.0), |_| true)), Some("coin2"));
+    assert_eq!(name(objs.nearest((90.0, 0.0), |state| state.name != "coin2")), Some("enemy"));
+    assert_eq!(name(objs.nearest_of_kind((40.0, 40.0), &Data::Coin(0))), Some("coin1"));
+    assert_eq!(name(objs.nearest((0.0, 0.0), |_| false)), None);
+    let none: &[Obj] = &[];
+    assert_eq!(name(none.nearest((0.0, 0.0), |_| true)), None);
+}
+
+#[test]
+fn slices_find_objects_in_an_area() {
+    let objs = objs();
+    let objs = objs.as_slice();
+    assert_eq!(names(objs.overlapping(&circle((0.0, 0.0), 6.0))), vec![ "coin1" ]);
+    assert_eq!(names(objs.overlapping(&circle((0.0, 30.0), 6.0))), Vec::<&str>::new());
+
+    assert_eq!(names(objs.in_rect(Rect::new(-50, -5, 20, 10))), vec![ "wall" ]);
+    assert_eq!(
+        names(objs.in_rect(Rect::new(0, 0, 100, 100))), vec![ "coin1", "coin2", "enemy" ]
+    );
+
+    assert_eq!(names(objs.in_circle((50.0, 50.0), 15.0)), vec![ "enemy" ]);
+    // Only reaching the circle's edge, not its center
+    assert_eq!(names(objs.in_circle((100.0, 12.0), 8.0)), vec![ "coin2" ]);
+}
+
+#[test]
+fn casts_find_the_first_object_in_the_way() {
+    let objs = objs();
+    let objs = objs.as_slice();
+    let (obj, hit) = objs.raycast((-100.0, 0.0), (1.0, 0.0), 500.0, |_| true).unwrap();
+    assert_eq!((obj.state().name.as_str(), hit.dist, hit.normal), ("wall", 55.0, (-1.0, 0.0)));
+    let (obj, hit) = objs.raycast((-100.0, 0.0), (1.0, 0.0), 500.0, |state| {
+        state.custom != Data::Wall
+    }).unwrap();
+    assert_eq!((obj.state().name.as_str(), hit.dist), ("coin1", 95.0));
+    assert!(objs.raycast((-100.0, 0.0), (1.0, 0.0), 50.0, |_| true).is_none());
+
+    let bullet = circle((0.0, 0.0), 1.0);
+    let (obj, hit) = objs.shape_cast(&bullet, (50.0, -100.0), (50.0, 100.0), |_| true).unwrap();
+    assert_eq!(obj.state().name, "enemy");
+    assert!((hit.dist - 139.0).abs() < 1e-6 && hit.normal == (0.0, -1.0), "{:?}", hit);
+}
+
+#[test]
+fn others_leave_out_the_object_updating() {
+    let objs = objs();
+    let others = Others::new(&objs[..0], &objs[1..]);
+    assert_eq!(others.len(), 3);
+    assert_eq!(names(others.of_kind(&Data::Coin(0))), vec![ "coin2" ]);
+    assert_eq!(others.count_kind(&Data::Coin(0)), 1);
+    assert_eq!(name(others.nearest((0.0, 0.0), |_| true)), Some("wall"));
+    assert_eq!(names(others.overlapping(&circle((0.0, 0.0), 6.0))), Vec::<&str>::new());
+    assert_eq!(names(others.in_rect(Rect::new(0, 0, 100, 100))), vec![ "coin2", "enemy" ]);
+    assert_eq!(names(others.in_circle((0.0, 0.0), 45.0)), vec![ "wall" ]);
+
+    // Objects on both sides are searched
+    let others = Others::new(&objs[..2], &objs[3..]);
+    assert_eq!(names(others.of_kind(&Data::Coin(0))), vec![ "coin1", "coin2" ]);
+    assert_eq!(name(others.nearest((50.0, 50.0), |_| true)), Some("coin1"));
+}
+
+/// Looks for other seekers every update
+#[derive(Clone)]
+struct Seeker {
+    state: GameObjectState<Img, Spr, Data>
+}
+
+impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Seeker {
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
+    }
+
+    fn on_reset(&mut self) -> bool {
+        false
+    }
+
+    fn update(
+            &mut self, _delta: f64, _input: &mut Input,
+            _ctl_objs: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+            others: &Others<Img, Snd, Fnt, Spr, Rm, Data>
+            ) -> Update<Img, Snd, Fnt, Spr, Rm, Data> {
+        let kind = Data::Seeker(0, None);
+        let nearest = others.nearest_of_kind(self.state.pos, &kind).map(|obj| obj.state().id);
+        self.state.custom = Data::Seeker(others.count_kind(&kind), nearest);
+        (None, vec![])
+    }
+}
+
+#[test]
+fn rooms_pass_others_without_the_object_updating() {
+    let seeker = |pos| Box::new(Seeker {
+        state: common::state("seeker", pos, rect((0.0, 0.0), (1.0, 1.0)), Data::Seeker(0, None))
+    });
+    let mut room = common::TestRoom::new(vec![], false);
+    let first = room.add(seeker((0.0, 0.0)));
+    room.add(obj("coin", (1.0, 0.0), circle((0.0, 0.0), 1.0), Data::Coin(0)));
+    let second = room.add(seeker((50.0, 0.0)));
+    room.update(0.1, &mut Input::new(), &vec![]);
+
+    assert_eq!(room.get(first).unwrap().state().custom, Data::Seeker(1, Some(second)));
+    assert_eq!(room.get(second).unwrap().state().custom, Data::Seeker(1, Some(first)));
+}
@@ -0,0 +1,284 @@
+//! Room updates without any of the app around them
+
+mod common;
+
+use ycraft::{
+    input::Input,
+    obj::{
+        ControlObject, GameObject, GameObjectBehavior, GameObjectState, ObjectId, Others, Update
+    }, physics::{
+        Body, BodyKind
+    }, room::Room
+};
+use common::{
+    rect, Fnt, Img, Rm, Snd, Spr
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Data {
+    Goodbye,
+    Heard(usize),
+    Touched(Touches)
+}
+
+/// How many of each collision callback an object got
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+struct Touches {
+    collisions: usize,
+    enters: usize,
+    stays: usize,
+    exits: usize
+}
+
+fn state(custom: Data) -> GameObjectState<Img, Spr, Data> {
+    common::state("obj", (0.0, 0.0), rect((0.0, 0.0), (1.0, 1.0)), custom)
+}
+
+/// Says goodbye to another object and leaves on its first update
+#[derive(Clone)]
+struct Leaver {
+    state: GameObjectState<Img, Spr, Data>,
+    to: ObjectId,
+    msgs: Vec<(ObjectId, Data)>,
+    gone: bool
+}
+
+impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Leaver {
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
+    }
+
+    fn on_reset(&mut self) -> bool {
+        false
+    }
+
+    fn update(
+            &mut self, _delta: f64, _input: &mut Input,
+            _ctl_objs: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+            _others: &Others<Img, Snd, Fnt, Spr, Rm, Data>
+            ) -> Update<Img, Snd, Fnt, Spr, Rm, Data> {
+        self.msgs.push((self.to, Data::Goodbye));
+        self.gone = true;
+        (None, vec![])
+    }
+
+    fn should_remove(&self) -> bool {
+        self.gone
+    }
+
+    fn take_messages(&mut self) -> Vec<(ObjectId, Data)> {
+        std::mem::take(&mut self.msgs)
+    }
+}
+
+/// Counts the goodbyes it gets
+#[derive(Clone)]
+struct Listener {
+    state: GameObjectState<Img, Spr, Data>
+}
+
+impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Listener {
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
+    }
+
+    fn on_reset(&mut self) -> bool {
+        false
+    }
+
+    fn on_message(&mut self, _from: ObjectId, msg: &Data) {
+        if let (Data::Goodbye, Data::Heard(count)) = (msg, self.state.custom) {
+            self.state.custom = Data::Heard(count + 1);
+        }
+    }
+}
+
+/// Moves by the same step every update and counts what it collides with
+#[derive(Clone)]
+struct Toucher {
+    state: GameObjectState<Img, Spr, Data>,
+    step: (f64, f64)
+}
+
+impl Toucher {
+    fn new(pos: (f64, f64), size: (f64, f64), step: (f64, f64)) -> Self {
+        let touches = Data::Touched(Touches::default());
+        Self {
+            state: common::state("toucher", pos, rect((0.0, 0.0), size), touches),
+            step
+        }
+    }
+
+    fn touches(&mut self) -> &mut Touches {
+        match &mut self.state.custom {
+            Data::Touched(touches) => touches,
+            _ => unreachable!()
+        }
+    }
+}
+
+impl GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data> for Toucher {
+    fn state(&self) -> &GameObjectState<Img, Spr, Data> {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut GameObjectState<Img, Spr, Data> {
+        &mut self.state
+    }
+
+    fn on_reset(&mut self) -> bool {
+        false
+    }
+
+    fn update(
+            &mut self, _delta: f64, _input: &mut Input,
+            _ctl_objs: &Vec<ControlObject<Img, Snd, Fnt, Spr, Rm, Data>>,
+            _others: &Others<Img, Snd, Fnt, Spr, Rm, Data>
+            ) -> Update<Img, Snd, Fnt, Spr, Rm, Data> {
+        self.state.pos.0 += self.step.0;
+        self.state.pos.1 += self.step.1;
+        (None, vec![])
+    }
+
+    fn on_collision(&mut self, _other: &GameObject<Img, Snd, Fnt, Spr, Rm, Data>) {
+        self.touches().collisions += 1;
+    }
+
+    fn on_collision_enter(&mut self, _other: &GameObject<Img, Snd, Fnt, Spr, Rm, Data>) {
+        self.touches().enters += 1;
+    }
+
+    fn on_collision_stay(&mut self, _other: &GameObject<Img, Snd, Fnt, Spr, Rm, Data>) {
+        self.touches().stays += 1;
+    }
+
+    fn on_collision_exit(&mut self, _other: ObjectId) {
+        self.touches().exits += 1;
+    }
+}
+
+fn touched(room: &Room<Img, Snd, Fnt, Spr, Rm, Data>, id: ObjectId) -> Touches {
+    match room.get(id).unwrap().state().custom {
+        Data::Touched(touches) => touches,
+        _ => unreachable!()
+    }
+}
+
+#[test]
+fn messages_from_removed_objects_are_delivered() {
+    let mut room = Room::<Img, Snd, Fnt, Spr, Rm, Data>::new(vec![], false);
+    let listener = room.add(Box::new(Listener { state: state(Data::Heard(0)) }));
+    let leaver = room.add(Box::new(Leaver {
+        state: state(Data::Goodbye),
+        to: listener,
+        msgs: vec![],
+        gone: false
+    }));
+
+    room.update(0.1, &mut Input::new(), &vec![]);
+    assert!(room.get(leaver).is_none());
+    assert_eq!(room.get(listener).unwrap().state().custom, Data::Heard(1));
+}
+
+fn placed(depth: i32, y: f64) -> Box<dyn GameObjectBehavior<Img, Snd, Fnt, Spr, Rm, Data>> {
+    let square = rect((0.0, 0.0), (1.0, 1.0));
+    common::Thing::new(common::state("obj", (0.0, y), square, Data::Heard(0)).with_depth(depth))
+}
+
+#[test]
+fn render_order_draws_deepest_first() {
+    let room = Room::<Img, Snd, Fnt, Spr, Rm, Data>::new(vec![
+        placed(0, 0.0), placed(10, 50.0), placed(-5, 20.0), placed(10, 10.0)
+    ], false);
+    // Without y_sort, ties stay in the order they're in objs
+    assert_eq!(room.render_order(), vec![ 1, 3, 0, 2 ]);
+}
+
+#[test]
+fn y_sort_breaks_depth_ties_from_the_top_down() {
+    let room = Room::<Img, Snd, Fnt, Spr, Rm, Data>::new(vec![
+        placed(0, 30.0), placed(0, -10.0), placed(1, 100.0), placed(0, 5.0), placed(-1, -50.0)
+    ], false).with_y_sort(true);
+    assert_eq!(room.render_order(), vec![ 2, 1, 3, 0, 4 ]);
+}
+
+#[test]
+fn equal_keys_keep_their_order() {
+    for y_sort in [ false, true ] {
+        let room = Room::<Img, Snd, Fnt, Spr, Rm, Data>::new(
+            (0..20).map(|i| placed(i % 2, 8.0)).collect(), false
+        ).with_y_sort(y_sort);
+        let odd = (1..20).step_by(2);
+        let even = (0..20).step_by(2);
+        assert_eq!(room.render_order(), odd.chain(even).collect::<Vec<_>>(), "y_sort {}", y_sort);
+    }
+}
+
+#[test]
+fn objects_on_layers_outside_each_others_masks_pass_through() {
+    for (floor_layer, lands) in [ (4, false), (2, true) ] {
+        let mut room = Room::<Img, Snd, Fnt, Spr, Rm, Data>::new(vec![], false)
+            .with_gravity((0.0, 500.0));
+        let mut floor = Toucher::new((0.0, 50.0), (200.0, 10.0), (0.0, 0.0));
+        floor.state = floor.state.with_solid(true).with_layers(floor_layer, floor_layer);
+        let floor = room.add(Box::new(floor));
+        let mut ball = Toucher::new((0.0, 30.0), (10.0, 10.0), (0.0, 0.0));
+        ball.state = ball.state.with_body(Body::new(BodyKind::Dynamic)).with_layers(2, 2);
+        let ball = room.add(Box::new(ball));
+
+        for _ in 0..60 {
+            room.update(1.0 / 60.0, &mut Input::new(), &vec![]);
+        }
+        let y = room.get(ball).unwrap().state().pos.1;
+        if lands {
+            assert!(y < 40.1, "fell to {}", y);
+            assert!(touched(&room, ball).collisions > 0);
+            assert!(touched(&room, floor).collisions > 0);
+        } else {
+            assert!(y > 100.0, "stopped at {}", y);
+            assert_eq!(touched(&room, ball), Touches::default());
+            assert_eq!(touched(&room, floor), Touches::default());
+        }
+    }
+}
+
+#[test]
+fn passing_through_an_object_enters_stays_and_exits_once() {
+    let mut room = Room::<Img, Snd, Fnt, Spr, Rm, Data>::new(vec![], false);
+    let mover = room.add(Box::new(Toucher::new((0.0, 0.0), (10.0, 10.0), (4.0, 0.0))));
+    let post = room.add(Box::new(Toucher::new((30.0, 0.0), (10.0, 10.0), (0.0, 0.0))));
+
+    // Touching from x = 20 through x = 40 is six updates
+    for _ in 0..15 {
+        room.update(0.1, &mut Input::new(), &vec![]);
+    }
+    let touches = Touches { collisions: 6, enters: 1, stays: 5, exits: 1 };
+    assert_eq!(touched(&room, mover), touches);
+    assert_eq!(touched(&room, post), touches);
+}
+
+#[test]
+fn removing_either_object_while_touching_exits() {
+    for remove_first in [ false, true ] {
+        let mut room = Room::<Img, Snd, Fnt, Spr, Rm, Data>::new(vec![], false);
+        let first = room.add(Box::new(Toucher::new((0.0, 0.0), (10.0, 10.0), (0.0, 0.0))));
+        let second = room.add(Box::new(Toucher::new((5.0, 0.0), (10.0, 10.0), (0.0, 0.0))));
+        room.update(0.1, &mut Input::new(), &vec![]);
+        room.update(0.1, &mut Input::new(), &vec![]);
+
+        let (gone, left) = if remove_first { (first, second) } else { (second, first) };
+        room.remove(gone);
+        room.update(0.1, &mut Input::new(), &vec![]);
+        room.update(0.1, &mut Input::new(), &vec![]);
+        let touches = Touches { collisions: 2, enters: 1, stays: 1, exits: 1 };
+        assert_eq!(touched(&room, left), touches, "removing the first: {}", remove_first);
+    }
+}
@@ -0,0 +1,104 @@
+//! Rooms described in JSON, read into a RoomDef and built with a factory
+
+mod common;
+
+use sdl2::{
+    pixels::Color,
+    rect::Rect
+};
+use ycraft::{
+    factory::{
+        BuildResult, ObjectDef, ObjectFactory, Property
+    }, roomdef::RoomDef
+};
+use common::{
+    rect, state, Fnt, Img, Rm, Snd, Spr, Thing
+};
+
+const ROOM: &str = r##"{
+    "persistent": true,
+    "bg_color": "#80102030",
+    "size": [ 640, 360 ],
+    "gravity": [ 0, 980 ],
+    "y_sort": true,
+    "objects": [
+        { "kind": "Player", "name": "player", "pos": [ 32, 300 ] },
+        {
+            "kind": "Brick", "pos": [ 96, 300 ], "size": [ 16, 8 ], "angle": 90, "depth": 1,
+            "props": { "hits": 2, "color": "red", "nothing": null }
+        }
+    ]
+}"##;
+
+/// Builds objects that keep the definition they came from, so tests can look at it
+fn build(def: &ObjectDef) -> BuildResult<Img, Snd, Fnt, Spr, Rm, ObjectDef> {
+    Ok(Thing::new(state(&def.name, def.pos, rect((0.0, 0.0), def.size), def.clone())))
+}
+
+fn factory() -> ObjectFactory<Img, Snd, Fnt, Spr, Rm, ObjectDef> {
+    ObjectFactory::new().with_kind("Player", build).with_kind("Brick", build)
+}
+
+#[test]
+fn room_files_build_their_objects_in_order() {
+    let def = RoomDef::from_json(ROOM.as_bytes()).unwrap();
+    assert!(def.persistant && def.y_sort);
+    assert_eq!(def.gravity, (0.0, 980.0));
+
+    // The alpha comes first, like in Tiled
+    assert_eq!(def.bg_color, Some(Color::RGBA(0x10, 0x20, 0x30, 0x80)));
+
+    let room = def.build(&factory()).unwrap();
+    assert!(room.persistant && room.y_sort);
+    assert_eq!(room.gravity, (0.0, 980.0));
+    assert_eq!(room.bg_color, def.bg_color);
+    assert_eq!(room.cam.bounds, Some(Rect::new(0, 0, 640, 360)));
+
+    let objs = room.objs.iter().map(|obj| &obj.state().custom).collect::<Vec<_>>();
+    assert_eq!(objs.len(), 2);
+    assert_eq!((objs[0].kind.as_str(), objs[0].name.as_str()), ("Player", "player"));
+    assert_eq!(objs[0].pos, (32.0, 300.0));
+
+    // Everything left out gets its default, and null props are dropped
+    assert_eq!((objs[0].size, objs[0].angle, objs[0].depth), ((0.0, 0.0), 0.0, 0));
+    assert!(objs[0].props.is_empty());
+    assert_eq!((objs[1].size, objs[1].angle, objs[1].depth), ((16.0, 8.0), 90.0, 1));
+    assert_eq!(objs[1].prop("hits"), Some(&Property::Int(2)));
+    assert_eq!(objs[1].prop("color"), Some(&Property::String("red".to_string())));
+    assert_eq!(objs[1].prop("nothing"), None);
+}
+
+#[test]
+fn rooms_without_a_size_have_no_camera_bounds() {
+    let def = RoomDef::from_json(br#"{ "objects": [] }"#).unwrap();
+    assert!(!def.persistant && def.bg_color.is_none() && def.size.is_none());
+    let room = def.build(&factory()).unwrap();
+    assert!(room.objs.is_empty());
+    assert_eq!(room.cam.bounds, None);
+}
+
+#[test]
+fn typos_are_errors() {
+    // In the room and in an object
+    assert!(RoomDef::from_json(br#"{ "gravty": [ 0, 10 ] }"#).is_err());
+    let err = RoomDef::from_json(br#"{ "objects": [ { "kind": "Brick", "postion": [ 0, 0 ] } ] }"#)
+        .unwrap_err();
+    assert!(err.contains("postion"), "{}", err);
+
+    // Objects need a kind, and colors have to be colors
+    assert!(RoomDef::from_json(br#"{ "objects": [ { "pos": [ 0, 0 ] } ] }"#).is_err());
+    assert!(RoomDef::from_json(br#"{ "bg_color": "blue" }"#).is_err());
+}
+
+#[test]
+fn objects_with_no_constructor_fail_the_build() {
+    let def = RoomDef::from_json(ROOM.as_bytes()).unwrap();
+    let factory = ObjectFactory::new().with_kind("Player", build);
+    let err = def.build(&factory).err().unwrap();
+    assert!(err.contains("Brick"), "{}", err);
+
+    // Constructors failing fail it too
+    let factory = factory.with_kind("Brick", |_| Err("out of bricks".to_string()));
+    let err = def.build(&factory).err().unwrap();
+    assert!(err.contains("out of bricks"), "{}", err);
+}
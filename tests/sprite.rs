@@ -0,0 +1,90 @@
+//! Sprite animation timing and sprites loaded from Aseprite sheets
+
+mod common;
+
+use sdl2::rect::Rect;
+use ycraft::{
+    aseprite::AsepriteLoader,
+    obj::{
+        Frame, Sprite
+    }
+};
+use common::{
+    Img, Spr
+};
+
+fn frame(x: i32) -> Frame<Img> {
+    Frame::new(Img::Sheet, Rect::new(x, 0, 16, 16), (16, 16))
+}
+
+#[test]
+fn untimed_frames_wait_past_a_whole_frame_then_start_over() {
+    let mut spr = Sprite::new(vec![ frame(0), frame(16) ], 4.0, (0, 0));
+    spr.update(0.25);
+    assert_eq!((spr.anim_idx, spr.anim_idx_smooth), (0, 1.0));
+    spr.update(0.3);
+    assert_eq!((spr.anim_idx, spr.anim_idx_smooth), (1, 0.0));
+}
+
+#[test]
+fn timed_frames_carry_leftover_time_into_the_next_frame() {
+    let mut spr = Sprite::new(
+        vec![ frame(0).with_duration(0.1), frame(16).with_duration(0.4), frame(32) ], 2.0, (0, 0)
+    );
+
+    // 0.05s past the first frame is an eighth of the second one
+    spr.update(0.15);
+    assert_eq!(spr.anim_idx, 1);
+    assert!((spr.anim_idx_smooth - 0.125).abs() < 1e-9, "{}", spr.anim_idx_smooth);
+
+    // Going into an untimed frame, the leftover goes by anim_spd instead
+    spr.update(0.4);
+    assert_eq!(spr.anim_idx, 2);
+    assert!((spr.anim_idx_smooth - 0.1).abs() < 1e-9, "{}", spr.anim_idx_smooth);
+}
+
+#[test]
+fn hash_sheets_keep_aseprites_frame_order() {
+    let frames = (0..12)
+        .map(|i| format!(
+            r#""walk {}.aseprite": {{
+                "frame": {{ "x": {}, "y": 0, "w": 16, "h": 16 }}, "duration": 100
+            }}"#, i, i * 16
+        )).collect::<Vec<_>>()
+        .join(",");
+    let sheet = format!(r#"{{ "frames": {{ {} }}, "meta": {{}} }}"#, frames);
+    let sprs = AsepriteLoader::new(Img::Sheet).with_all_frames(Spr::Blank)
+        .load(sheet.as_bytes())
+        .unwrap();
+
+    let mut spr = sprs[&Spr::Blank].clone();
+    for i in 0..12 {
+        assert_eq!(spr.anim_idx, i);
+        spr.update(0.1);
+    }
+    assert_eq!(spr.anim_idx, 0);
+}
+
+#[test]
+fn hash_sheets_with_any_frame_names_keep_the_files_order() {
+    // A single frame has no number, and --filename-format can make names that sort any which way.
+    // Each frame lasts a bit longer than the one before, to tell them apart
+    for names in [ vec![ "player.aseprite" ], vec![ "walk-c", "walk-a", "idle", "walk-b" ] ] {
+        let frames = names.iter().enumerate()
+            .map(|(i, name)| format!(
+                r#""{}": {{ "frame": {{ "x": 0, "y": 0, "w": 16, "h": 16 }}, "duration": {} }}"#,
+                name, (i + 1) * 100
+            )).collect::<Vec<_>>()
+            .join(",");
+        let sheet = format!(r#"{{ "frames": {{ {} }}, "meta": {{}} }}"#, frames);
+        let sprs = AsepriteLoader::new(Img::Sheet).with_all_frames(Spr::Blank)
+            .load(sheet.as_bytes())
+            .unwrap();
+
+        let durations = sprs[&Spr::Blank].frames.iter()
+            .map(|frame| frame.duration().unwrap())
+            .collect::<Vec<_>>();
+        let expected = (1..=names.len()).map(|i| i as f64 / 10.0).collect::<Vec<_>>();
+        assert_eq!(durations, expected, "{:?}", names);
+    }
+}
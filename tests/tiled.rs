@@ -0,0 +1,261 @@
+//! Loading the same Tiled map from TMX and JSON, in every way Tiled can save its tiles
+
+mod common;
+
+use std::io::Write;
+use base64::prelude::{
+    Engine, BASE64_STANDARD
+};
+use flate2::{
+    write::{
+        GzEncoder, ZlibEncoder
+    }, Compression
+};
+use sdl2::{
+    pixels::Color,
+    rect::Rect
+};
+use serde_json::Value;
+use ycraft::{
+    collision::{
+        Aabb, CollisionShape
+    }, factory::{
+        BuildResult, ObjectDef, ObjectFactory, Property
+    }, tiled::TiledLoader,
+    tilemap::TileFlip
+};
+use common::{
+    rect, state, Fnt, Img, Rm, Snd, Spr, TestRoom, Thing
+};
+
+const TMX: &str = include_str!("fixtures/tiled/map.tmx");
+const JSON: &str = include_str!("fixtures/tiled/map.json");
+const TSX: &str = include_str!("fixtures/tiled/props.tsx");
+
+/// The ground layer as saved in the TMX fixture
+const GROUND_CSV: &str = "<data encoding=\"csv\">
+1,2,9,0,
+2147483651,1073741828,536870913,3758096394
+</data>";
+
+/// The ground layer's tiles, with Tiled's flip flags in the top bits
+const GROUND: [u32; 8] = [
+    1, 2, 9, 0,
+    3 | 0x8000_0000, 4 | 0x4000_0000, 1 | 0x2000_0000, 10 | 0xe000_0000
+];
+
+type Room = TestRoom<ObjectDef>;
+
+/// Builds objects that keep the definition they came from, so tests can look at it
+fn build_crate(def: &ObjectDef) -> BuildResult<Img, Snd, Fnt, Spr, Rm, ObjectDef> {
+    let shape = def.shape.clone().unwrap_or(rect((0.0, 0.0), (0.0, 0.0)));
+    Ok(Thing::new(state(&def.name, def.pos, shape, def.clone()).with_depth(def.depth)))
+}
+
+fn factory() -> ObjectFactory<Img, Snd, Fnt, Spr, Rm, ObjectDef> {
+    ObjectFactory::new().with_kind("crate", build_crate)
+}
+
+fn loader() -> TiledLoader<Img> {
+    TiledLoader::new()
+        .with_image("terrain.png", Img::Sheet)
+        .with_image("props.png", Img::Sheet)
+        .with_tileset("props.tsx", TSX.as_bytes())
+}
+
+fn load_tmx(tmx: &str) -> Room {
+    loader().load_tmx(tmx.as_bytes(), &factory(), false).unwrap()
+}
+
+fn load_json(json: &str) -> Room {
+    loader().load_json(json.as_bytes(), &factory(), false).unwrap()
+}
+
+/// The ground layer's tiles packed the way Tiled does for base64, compressed or not
+fn encoded(compression: &str) -> String {
+    let bytes = GROUND.iter().flat_map(|gid| gid.to_le_bytes()).collect::<Vec<_>>();
+    let bytes = match compression {
+        "zlib" => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&bytes).unwrap();
+            encoder.finish().unwrap()
+        }, "gzip" => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&bytes).unwrap();
+            encoder.finish().unwrap()
+        }, _ => bytes
+    };
+    BASE64_STANDARD.encode(bytes)
+}
+
+/// The TMX fixture with its ground layer saved as base64 instead of CSV
+fn tmx_with(compression: &str) -> String {
+    let attr = if compression.is_empty() {
+        String::new()
+    } else {
+        format!(" compression=\"{}\"", compression)
+    };
+    let data = format!("<data encoding=\"base64\"{}>\n{}\n</data>", attr, encoded(compression));
+    assert!(TMX.contains(GROUND_CSV));
+    TMX.replacen(GROUND_CSV, &data, 1)
+}
+
+/// The JSON fixture with its ground layer saved as base64 instead of an array
+fn json_with(compression: &str) -> String {
+    let mut map: Value = serde_json::from_str(JSON).unwrap();
+    let ground = &mut map["layers"][0];
+    ground["data"] = Value::String(encoded(compression));
+    ground["encoding"] = Value::String("base64".to_string());
+    ground["compression"] = Value::String(compression.to_string());
+    map.to_string()
+}
+
+/// Every tile in the layer with the given name, whichever tilemap it ended up in, along with the
+/// columns of the tileset it's from
+fn tiles(room: &Room, name: &str) -> Vec<Option<(u32, u32)>> {
+    let mut tiles = vec![ None; 8 ];
+    for tilemap in room.tilemaps.iter().filter(|tilemap| tilemap.layers[0].name == name) {
+        let columns = tilemap.tileset.as_ref().unwrap().columns;
+        for (i, tile) in tilemap.layers[0].tiles.iter().enumerate() {
+            if let Some(tile) = tile {
+                assert!(tiles[i].is_none(), "two tiles in cell {}", i);
+                tiles[i] = Some((columns, *tile));
+            }
+        }
+    }
+    tiles
+}
+
+fn obj<'a>(room: &'a Room, name: &str) -> &'a ObjectDef {
+    &room.objs.iter().find(|obj| obj.state().name == name).unwrap().state().custom
+}
+
+#[test]
+fn every_tile_encoding_reads_the_same_tiles() {
+    // Terrain has 4 columns and props 2, which tells the tilesets apart
+    let expected = vec![
+        Some((4, 0)), Some((4, 1)), Some((2, 0)), None,
+        Some((4, 2)), Some((4, 3)), Some((4, 0)), Some((2, 1))
+    ];
+    assert_eq!(tiles(&load_tmx(TMX), "ground"), expected);
+    assert_eq!(tiles(&load_json(JSON), "ground"), expected);
+    for compression in [ "", "zlib", "gzip" ] {
+        let tmx = load_tmx(&tmx_with(compression));
+        assert_eq!(tiles(&tmx, "ground"), expected, "TMX with '{}'", compression);
+        let json = load_json(&json_with(compression));
+        assert_eq!(tiles(&json, "ground"), expected, "JSON with '{}'", compression);
+    }
+}
+
+#[test]
+fn layers_are_split_by_tileset_and_keep_their_flips() {
+    for room in [ load_tmx(TMX), load_json(JSON) ] {
+        // Ground uses both tilesets, deco only props
+        let layers = room.tilemaps.iter()
+            .map(|tilemap| {
+                (tilemap.layers[0].name.as_str(), tilemap.tileset.as_ref().unwrap().columns)
+            }).collect::<Vec<_>>();
+        assert_eq!(layers, vec![ ("ground", 4), ("ground", 2), ("deco", 2) ]);
+
+        let (terrain, props) = (&room.tilemaps[0], &room.tilemaps[1]);
+        assert_eq!(terrain.tile(0, (1, 0)), Some(1));
+        assert_eq!(terrain.tile(0, (2, 0)), None);
+        assert_eq!(props.tile(0, (2, 0)), Some(0));
+        assert_eq!(room.tilemaps[2].tile(0, (3, 1)), Some(3));
+
+        assert_eq!(terrain.flip(0, (0, 0)), TileFlip::default());
+        assert_eq!(terrain.flip(0, (0, 1)), TileFlip::new(true, false));
+        assert_eq!(terrain.flip(0, (1, 1)), TileFlip::new(false, true));
+        assert_eq!(terrain.flip(0, (2, 1)), TileFlip::new(false, false).with_diagonal(true));
+        assert_eq!(props.flip(0, (3, 1)), TileFlip::new(true, true).with_diagonal(true));
+
+        // Nothing in deco is flipped, so it doesn't keep any
+        assert!(room.tilemaps[2].layers[0].flips.is_empty());
+    }
+}
+
+#[test]
+fn flips_draw_tiles_turned_the_way_tiled_shows_them() {
+    assert_eq!(TileFlip::default().render_params(), (0.0, (false, false)));
+    assert_eq!(TileFlip::new(true, true).render_params(), (0.0, (true, true)));
+
+    // Diagonal then horizontal is a quarter turn clockwise, diagonal then vertical one the other
+    // way, and all three together a quarter turn of the horizontal flip
+    let diagonal = TileFlip::new(false, false).with_diagonal(true);
+    assert_eq!(diagonal.render_params(), (90.0, (false, true)));
+    assert_eq!(TileFlip { horizontal: true, ..diagonal }.render_params(), (90.0, (false, false)));
+    assert_eq!(TileFlip { vertical: true, ..diagonal }.render_params(), (90.0, (true, true)));
+    let all = TileFlip::new(true, true).with_diagonal(true);
+    assert_eq!(all.render_params(), (90.0, (true, false)));
+}
+
+#[test]
+fn groups_are_flattened_with_their_offsets_and_properties() {
+    for room in [ load_tmx(TMX), load_json(JSON) ] {
+        let (ground, deco) = (&room.tilemaps[0], &room.tilemaps[2]);
+        assert_eq!(ground.pos, (0.0, 0.0));
+        assert_eq!(ground.layers[0].depth, 2);
+        assert!(ground.layers[0].solid);
+
+        // The group's offset adds to the layer's own, and its depth overrides the draw order
+        assert_eq!(deco.pos, (10.0, 5.0));
+        assert_eq!(deco.layers[0].depth, -5);
+        assert!(!deco.layers[0].solid);
+        assert_eq!(obj(&room, "box").pos, (18.0, 24.0));
+        assert_eq!(obj(&room, "box").depth, -5);
+    }
+}
+
+#[test]
+fn objects_get_the_shapes_they_have_in_the_editor() {
+    for room in [ load_tmx(TMX), load_json(JSON) ] {
+        let box_ = obj(&room, "box");
+        assert_eq!(box_.shape, Some(rect((8.0, 4.0), (16.0, 8.0))));
+        assert_eq!(box_.prop("coins"), Some(&Property::Int(3)));
+
+        // Round ellipses are circles
+        assert_eq!(obj(&room, "ball").shape, Some(common::circle((5.0, 5.0), 5.0)));
+
+        // Stretched ones are polygons around the middle of their box
+        let Some(CollisionShape::Polygon { center, points }) = &obj(&room, "egg").shape else {
+            panic!("egg isn't a polygon");
+        };
+        assert_eq!(*center, (10.0, 5.0));
+        assert_eq!(points[0], (10.0, 0.0));
+        let quarter = points[points.len() / 4];
+        assert!(quarter.0.abs() < 1e-9 && (quarter.1 - 5.0).abs() < 1e-9, "{:?}", quarter);
+
+        let ramp = obj(&room, "ramp");
+        assert_eq!(ramp.pos, (13.0, 9.0));
+        assert_eq!(ramp.shape, Some(CollisionShape::Polygon {
+            center: (0.0, 0.0),
+            points: vec![ (0.0, 0.0), (10.0, 0.0), (0.0, 10.0) ]
+        }));
+
+        // Tile objects sit on their bottom left corner and turn around it, so a quarter turn puts
+        // the box left of the corner it's placed by
+        let sign = obj(&room, "sign");
+        assert_eq!(sign.pos, (56.0, 36.0));
+        assert_eq!(sign.angle, 90.0);
+        assert_eq!(sign.shape.as_ref().unwrap().aabb(), Aabb { min: (-16, 0), max: (0, 16) });
+    }
+}
+
+#[test]
+fn objects_are_built_by_the_factory_for_their_kind() {
+    for room in [ load_tmx(TMX), load_json(JSON) ] {
+        // The note has no constructor, so it's left out
+        let names = room.objs.iter().map(|obj| obj.state().name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec![ "box", "ball", "egg", "ramp", "sign" ]);
+        assert!(room.objs.iter().all(|obj| obj.state().custom.kind == "crate"));
+        assert_eq!(room.objs[0].state().depth, -5);
+
+        assert_eq!(room.cam.bounds, Some(Rect::new(0, 0, 64, 32)));
+        assert_eq!(room.bg_color, Some(Color::RGBA(0x10, 0x20, 0x30, 0x80)));
+    }
+
+    // A constructor failing fails the whole map
+    let factory = factory().with_kind("comment", |_| Err("no notes allowed".to_string()));
+    let err = loader().load_tmx(TMX.as_bytes(), &factory, false).err().unwrap();
+    assert!(err.contains("note") && err.contains("no notes allowed"), "{}", err);
+}
@@ -0,0 +1,163 @@
+//! Which tiles shapes run into and which tiles get drawn, wherever the map and camera are
+
+mod common;
+
+use rand::{
+    rngs::StdRng,
+    Rng, SeedableRng
+};
+use sdl2::rect::Rect;
+use ycraft::{
+    camera::Camera,
+    collision::CollisionShape,
+    tilemap::{
+        TileLayer, Tilemap
+    }
+};
+use common::{
+    circle, rect, Img
+};
+
+/// A 4x3 map of 16x16 tiles placed at pos, where only the layer named "solid" is solid and every
+/// third cell is empty
+fn map(pos: (f64, f64)) -> Tilemap<Img> {
+    let tiles = (0..12).map(|i| (i % 3 != 2).then_some(i)).collect::<Vec<_>>();
+    Tilemap::grid((4, 3), (16.0, 16.0))
+        .with_pos(pos)
+        .with_layer(TileLayer::new("deco", vec![ Some(0); 12 ]))
+        .with_layer(TileLayer::new("solid", tiles).with_solid(true))
+}
+
+fn cells(found: Vec<((i32, i32), CollisionShape)>) -> Vec<(i32, i32)> {
+    found.into_iter().map(|(cell, _)| cell).collect()
+}
+
+#[test]
+fn shapes_find_the_solid_cells_they_overlap() {
+    // Covers -32..32 across and -16..32 down
+    let map = map((-32.0, -16.0));
+    assert_eq!(cells(map.solids_overlapping(&rect((-8.0, -8.0), (8.0, 8.0)))), vec![ (1, 0) ]);
+    // Across four cells, two of them empty on the solid layer
+    assert_eq!(
+        cells(map.solids_overlapping(&rect((0.0, 0.0), (20.0, 20.0)))), vec![ (1, 0), (2, 1) ]
+    );
+
+    // (0, 1) is solid but (2, 0) isn't, and only the solid layer counts
+    assert!(map.is_solid((0, 1)) && !map.is_solid((2, 0)));
+    assert!(cells(map.solids_overlapping(&rect((8.0, -8.0), (4.0, 4.0)))).is_empty());
+}
+
+#[test]
+fn shapes_at_the_edges_only_find_cells_inside_the_map() {
+    let map = map((-32.0, -16.0));
+
+    // Poking in past the top left corner, touching it, and missing it
+    assert_eq!(cells(map.solids_overlapping(&circle((-34.0, -18.0), 4.0))), vec![ (0, 0) ]);
+    assert_eq!(cells(map.solids_overlapping(&rect((-36.0, 8.0), (8.0, 8.0)))), vec![ (0, 1) ]);
+    assert!(map.solids_overlapping(&rect((-40.0, 0.0), (8.0, 8.0))).is_empty());
+
+    // Covering the whole map and then some still only gives cells in it
+    let everything = cells(map.solids_overlapping(&rect((0.0, 0.0), (500.0, 500.0))));
+    let expected = (0..3).flat_map(|row| (0..4).map(move |col| (col, row)))
+        .filter(|cell| map.is_solid(*cell))
+        .collect::<Vec<_>>();
+    assert_eq!(everything, expected);
+
+    // Past the bottom right
+    assert!(map.solids_overlapping(&rect((40.0, 40.0), (8.0, 8.0))).is_empty());
+}
+
+#[test]
+fn solids_overlapping_matches_checking_every_cell() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..200 {
+        let map = map((rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)));
+        let center = (rng.gen_range(-150.0..150.0), rng.gen_range(-150.0..150.0));
+        let shape = if rng.gen_bool(0.5) {
+            circle(center, rng.gen_range(0.0..40.0))
+        } else {
+            rect(center, (rng.gen_range(0.0..60.0), rng.gen_range(0.0..60.0)))
+        };
+        let expected = (0..3).flat_map(|row| (0..4).map(move |col| (col, row)))
+            .filter(|cell| map.is_solid(*cell) && map.cell_collider(*cell).collides_with(&shape))
+            .collect::<Vec<_>>();
+        let found = cells(map.solids_overlapping(&shape));
+        assert_eq!(found, expected, "{:?} at {:?}", shape, map.pos);
+    }
+}
+
+fn camera(pos: (f64, f64), size: (f64, f64), zoom: f64) -> Camera {
+    let mut cam = Camera::new().with_pos(pos).with_zoom(zoom);
+    cam.size = size;
+    cam
+}
+
+fn drawn(map: &Tilemap<Img>, cam: &Camera, layer: usize) -> Vec<(i32, i32)> {
+    map.visible_tiles(cam, layer).into_iter().map(|(cell, _, _)| cell).collect()
+}
+
+#[test]
+fn only_tiles_on_screen_are_drawn() {
+    let map = map((-32.0, -16.0));
+
+    // The view covers -40..-20 across, so only the first column, and none of the empty cells
+    let cam = camera((-40.0, -20.0), (20.0, 100.0), 1.0);
+    assert_eq!(drawn(&map, &cam, 0), vec![ (0, 0), (0, 1), (0, 2) ]);
+    assert_eq!(drawn(&map, &cam, 1), vec![ (0, 0), (0, 1) ]);
+
+    // Where they go on screen, with the view starting 8 pixels left of the map
+    let dests = map.visible_tiles(&cam, 0).into_iter()
+        .map(|(_, _, dest)| dest)
+        .collect::<Vec<_>>();
+    assert_eq!(dests[0], Rect::new(8, 4, 16, 16));
+    assert_eq!(dests[2], Rect::new(8, 36, 16, 16));
+
+    // Entirely off either side
+    assert!(drawn(&map, &camera((-100.0, 0.0), (50.0, 50.0), 1.0), 0).is_empty());
+    assert!(drawn(&map, &camera((33.0, 0.0), (50.0, 50.0), 1.0), 0).is_empty());
+
+    // Hidden layers aren't drawn at all
+    let mut hidden = map.clone();
+    hidden.layers[0].visible = false;
+    assert!(drawn(&hidden, &cam, 0).is_empty());
+}
+
+#[test]
+fn a_sliver_of_a_tile_at_the_view_edge_is_still_drawn() {
+    // The view ends at 16.5, a quarter pixel into the third column (which starts at 16.25).
+    // Rounding the view to whole pixels would lose it
+    let map = map((-15.75, 0.0));
+    let cam = camera((-15.5, 0.0), (32.0, 8.0), 1.0);
+    assert_eq!(drawn(&map, &cam, 0), vec![ (0, 0), (1, 0), (2, 0) ]);
+}
+
+#[test]
+fn culling_keeps_every_tile_on_screen_and_nothing_far_off() {
+    let mut rng = StdRng::seed_from_u64(9);
+    for _ in 0..300 {
+        let map = map((rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)));
+        let cam = camera(
+            (rng.gen_range(-150.0..100.0), rng.gen_range(-150.0..100.0)),
+            (rng.gen_range(1.0..120.0), rng.gen_range(1.0..120.0)), rng.gen_range(0.5..3.0)
+        );
+        let view = cam.view_size();
+        let (min, max) = (cam.pos, (cam.pos.0 + view.0, cam.pos.1 + view.1));
+        let drawn = drawn(&map, &cam, 0);
+        for row in 0..3 {
+            for col in 0..4 {
+                let start = map.cell_to_world((col, row));
+                let end = map.cell_to_world((col + 1, row + 1));
+                let inside = |min: (f64, f64), max: (f64, f64)| {
+                    start.0 < max.0 && end.0 > min.0 && start.1 < max.1 && end.1 > min.1
+                };
+                let on_screen = inside(min, max);
+                // Touching the view's edge is fine too
+                let near = inside((min.0 - 1e-9, min.1 - 1e-9), (max.0 + 1e-9, max.1 + 1e-9));
+                let was_drawn = drawn.contains(&(col, row));
+                let at = (col, row, map.pos, &cam);
+                assert!(!on_screen || was_drawn, "missing {:?}", at);
+                assert!(near || !was_drawn, "drawn {:?}", at);
+            }
+        }
+    }
+}
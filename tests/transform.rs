@@ -0,0 +1,124 @@
+//! Scaling and rotating colliders, on their own and lined up with an object's sprite
+
+mod common;
+
+use std::collections::HashMap;
+use sdl2::rect::Rect;
+use ycraft::{
+    collision::CollisionShape,
+    obj::{
+        Frame, GameObjectState, Sprite
+    }
+};
+use common::{
+    Img, Spr
+};
+
+fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+    (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+}
+
+fn assert_polygon(shape: &CollisionShape, center: (f64, f64), points: &[(f64, f64)]) {
+    let CollisionShape::Polygon { center: actual_center, points: actual_points } = shape else {
+        panic!("expected a polygon, got {:?}", shape);
+    };
+    assert!(close(*actual_center, center), "center {:?} != {:?}", actual_center, center);
+    assert_eq!(actual_points.len(), points.len());
+    for (actual, expected) in actual_points.iter().zip(points) {
+        assert!(close(*actual, *expected), "points {:?} != {:?}", actual_points, points);
+    }
+}
+
+/// A 16x16 frame drawn at 32x32, so base_scale is 2
+fn state(origin: (i32, i32), scale: (f64, f64), angle: f64) -> GameObjectState<Img, Spr, ()> {
+    let mut spr = Sprite::new(
+        vec![ Frame::new(Img::Sheet, Rect::new(0, 0, 16, 16), (32, 32)) ], 0.0, origin
+    );
+    spr.scale = scale;
+    spr.angle = angle;
+    GameObjectState::new(
+        "obj", (100.0, 50.0),
+        CollisionShape::Rect { center: (0.0, 0.0), size: (10.0, 10.0) },
+        Spr::Blank, HashMap::from([ (Spr::Blank, spr) ]), ()
+    ).with_collider_following_sprite()
+}
+
+#[test]
+fn scaled_stretches_away_from_the_origin() {
+    let rect = CollisionShape::Rect { center: (2.0, 3.0), size: (4.0, 6.0) };
+    assert_eq!(
+        rect.scaled((2.0, -1.0)), CollisionShape::Rect { center: (4.0, -3.0), size: (8.0, 6.0) }
+    );
+
+    // Circles grow by the bigger scale either way
+    let circle = CollisionShape::Circle { center: (1.0, 1.0), radius: 2.0 };
+    assert_eq!(
+        circle.scaled((0.5, -3.0)), CollisionShape::Circle { center: (0.5, -3.0), radius: 6.0 }
+    );
+
+    let triangle = CollisionShape::Polygon {
+        center: (1.0, 0.0), points: vec![ (0.0, -1.0), (1.0, 1.0), (-1.0, 1.0) ]
+    };
+    assert_eq!(triangle.scaled((3.0, 2.0)), CollisionShape::Polygon {
+        center: (3.0, 0.0), points: vec![ (0.0, -2.0), (3.0, 2.0), (-3.0, 2.0) ]
+    });
+}
+
+#[test]
+fn rotated_turns_clockwise_around_the_pivot() {
+    // Quarter turns stay exact
+    let rect = CollisionShape::Rect { center: (10.0, 0.0), size: (4.0, 2.0) };
+    assert_eq!(rect.rotated(90.0, (0.0, 0.0)), CollisionShape::Polygon {
+        center: (0.0, 10.0), points: vec![ (1.0, -2.0), (1.0, 2.0), (-1.0, 2.0), (-1.0, -2.0) ]
+    });
+    assert_eq!(rect.rotated(360.0, (0.0, 0.0)), rect);
+
+    let circle = CollisionShape::Circle { center: (0.0, 0.0), radius: 3.0 };
+    assert_eq!(
+        circle.rotated(180.0, (5.0, 5.0)),
+        CollisionShape::Circle { center: (10.0, 10.0), radius: 3.0 }
+    );
+
+    let (sin, cos) = 30.0_f64.to_radians().sin_cos();
+    let turned = CollisionShape::Rect { center: (0.0, 0.0), size: (2.0, 2.0) }
+        .rotated(30.0, (-1.0, -1.0));
+    let rotate = |point: (f64, f64)| (point.0 * cos - point.1 * sin, point.0 * sin + point.1 * cos);
+    let offset = rotate((1.0, 1.0));
+    assert_polygon(
+        &turned, (offset.0 - 1.0, offset.1 - 1.0),
+        &[ (-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0) ].map(rotate)
+    );
+}
+
+#[test]
+fn world_collider_turns_around_the_sprites_middle() {
+    // Drawn 48x16 with its top left corner at pos - origin * base_scale * scale = pos - (12, 4),
+    // so SDL spins the sprite around pos + (12, 4)
+    let state = state((4, 4), (1.5, 0.5), 90.0);
+    assert_polygon(
+        &state.world_collider(), (116.0, 42.0),
+        &[ (2.5, -7.5), (2.5, 7.5), (-2.5, 7.5), (-2.5, -7.5) ]
+    );
+}
+
+#[test]
+fn world_collider_with_a_centered_origin_turns_in_place() {
+    let mut state = state((8, 8), (2.0, 3.0), 30.0);
+    state.collider = CollisionShape::Circle { center: (10.0, 0.0), radius: 4.0 };
+    let (sin, cos) = 30.0_f64.to_radians().sin_cos();
+    let CollisionShape::Circle { center, radius } = state.world_collider() else {
+        panic!("circles stay circles");
+    };
+    assert!(close(center, (100.0 + 20.0 * cos, 50.0 + 20.0 * sin)), "center {:?}", center);
+    assert_eq!(radius, 12.0);
+}
+
+#[test]
+fn world_collider_without_a_sprite_uses_the_collider_transform() {
+    let mut state = state((0, 0), (1.0, 1.0), 0.0).with_collider_transform((2.0, 1.0), 90.0);
+    state.collider_follows_sprite = false;
+    assert_polygon(
+        &state.world_collider(), (100.0, 50.0),
+        &[ (5.0, -10.0), (5.0, 10.0), (-5.0, 10.0), (-5.0, -10.0) ]
+    );
+}